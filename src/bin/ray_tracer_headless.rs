@@ -0,0 +1,188 @@
+use ray_tracer::{
+    camera::Camera,
+    cpu_renderer::CpuRenderer,
+    image::Image,
+    offscreen::OffscreenRenderer,
+    scene::Scene,
+    state::{
+        DEFAULT_MAX_BOUNCES, MAX_TRACED_BOUNCES, State, check_adapter_limits, required_limits,
+    },
+};
+
+const USAGE: &str = "usage: ray_tracer_headless <output.png> [--scene <scene.ron>] [--width <pixels>] [--height <pixels>] [--fallback-adapter] [--cpu] [--samples <count>] [--bounces <count>]";
+
+struct Arguments {
+    output: std::path::PathBuf,
+    scene: Option<std::path::PathBuf>,
+    width: u32,
+    height: u32,
+    force_fallback_adapter: bool,
+    cpu: bool,
+    /// When set, path tracing is used with this many samples per pixel
+    samples: Option<u32>,
+    max_bounces: u32,
+}
+
+fn parse_arguments() -> Result<Arguments, String> {
+    let mut output = None;
+    let mut scene = None;
+    let mut width = 1280;
+    let mut height = 720;
+    let mut force_fallback_adapter = false;
+    let mut cpu = false;
+    let mut samples = None;
+    let mut max_bounces = DEFAULT_MAX_BOUNCES;
+
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("expected a value after {name}"))
+        };
+        let parse_size = |name: &str, value: std::ffi::OsString| {
+            value
+                .to_str()
+                .and_then(|value| value.parse::<u32>().ok())
+                .filter(|&value| value > 0)
+                .ok_or_else(|| format!("{name} should be a positive integer"))
+        };
+
+        match arg.to_str() {
+            Some("--scene") => scene = Some(value("--scene")?.into()),
+            Some("--width") => width = parse_size("--width", value("--width")?)?,
+            Some("--height") => height = parse_size("--height", value("--height")?)?,
+            Some("--fallback-adapter") => force_fallback_adapter = true,
+            Some("--cpu") => cpu = true,
+            Some("--samples") => samples = Some(parse_size("--samples", value("--samples")?)?),
+            Some("--bounces") => {
+                max_bounces = value("--bounces")?
+                    .to_str()
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .filter(|&value| value <= MAX_TRACED_BOUNCES)
+                    .ok_or_else(|| {
+                        format!("--bounces should be an integer from 0 to {MAX_TRACED_BOUNCES}")
+                    })?;
+            }
+            Some(flag) if flag.starts_with("--") => return Err(format!("unknown flag {flag}")),
+            _ if output.is_none() => output = Some(arg.into()),
+            _ => return Err("only one output path can be given".into()),
+        }
+    }
+
+    if cpu && samples.is_some() {
+        return Err("path tracing is only supported on the gpu".into());
+    }
+
+    Ok(Arguments {
+        output: output.ok_or("an output path is required")?,
+        scene,
+        width,
+        height,
+        force_fallback_adapter,
+        cpu,
+        samples,
+        max_bounces,
+    })
+}
+
+fn main() {
+    let arguments = match parse_arguments() {
+        Ok(arguments) => arguments,
+        Err(e) => {
+            eprintln!("ERROR: {e}");
+            eprintln!("{USAGE}");
+            std::process::exit(1);
+        }
+    };
+
+    let scene = match &arguments.scene {
+        Some(path) => match Scene::load(path) {
+            Ok(scene) => scene,
+            Err(e) => {
+                eprintln!("ERROR: {}: {e}", path.display());
+                std::process::exit(1);
+            }
+        },
+        None => Scene::default(),
+    };
+
+    let image = if arguments.cpu {
+        render_cpu(
+            scene,
+            arguments.width,
+            arguments.height,
+            arguments.max_bounces,
+        )
+    } else {
+        render_gpu(
+            scene,
+            arguments.width,
+            arguments.height,
+            arguments.force_fallback_adapter,
+            arguments.samples,
+            arguments.max_bounces,
+        )
+    };
+
+    if let Err(e) = image.save_png(&arguments.output) {
+        eprintln!("ERROR: {}: {e}", arguments.output.display());
+        std::process::exit(1);
+    }
+}
+
+fn render_cpu(scene: Scene, width: u32, height: u32, max_bounces: u32) -> Image {
+    let mut camera = Camera::default();
+    scene.lighting.apply_to_camera(&mut camera);
+    CpuRenderer {
+        camera: &camera,
+        materials: &scene.materials,
+        objects: &scene.objects,
+        max_bounces,
+    }
+    .render(width, height)
+}
+
+fn render_gpu(
+    scene: Scene,
+    width: u32,
+    height: u32,
+    force_fallback_adapter: bool,
+    samples: Option<u32>,
+    max_bounces: u32,
+) -> Image {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        ..Default::default()
+    });
+
+    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        force_fallback_adapter,
+        compatible_surface: None,
+    }))
+    .expect("an adapter should have been requested successfully");
+    if let Err(e) = check_adapter_limits(&adapter) {
+        eprintln!("ERROR: {e}");
+        std::process::exit(1);
+    }
+
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            label: None,
+            required_features: wgpu::Features::empty(),
+            required_limits: required_limits(),
+            memory_hints: wgpu::MemoryHints::Performance,
+        },
+        None,
+    ))
+    .expect("device should have been requested successfully");
+
+    let mut state = State::new(&device, &queue, scene);
+    state.set_max_bounces(max_bounces);
+    let renderer = OffscreenRenderer::new(&device, width, height);
+    if let Some(samples) = samples {
+        state.set_path_tracing(true);
+        renderer.render_frames(&mut state, &device, &queue, samples)
+    } else {
+        renderer.render(&mut state, &device, &queue)
+    }
+}
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
-use ray_tracer::state::State;
+use ray_tracer::{
+    scene::Scene,
+    state::{State, check_adapter_limits, required_limits},
+};
 use winit::{
     application::ApplicationHandler,
     dpi::{PhysicalPosition, PhysicalSize},
-    event::{DeviceEvent, KeyEvent, MouseScrollDelta, WindowEvent},
+    event::{DeviceEvent, ElementState, KeyEvent, MouseScrollDelta, WindowEvent},
     event_loop::{ActiveEventLoop, ControlFlow, EventLoop},
     keyboard::PhysicalKey,
     window::{Window, WindowId},
@@ -14,6 +17,7 @@ struct WindowState {
     window: Arc<Window>,
     surface: wgpu::Surface<'static>,
     surface_config: wgpu::SurfaceConfiguration,
+    egui_state: egui_winit::State,
 }
 
 struct App {
@@ -55,10 +59,24 @@ impl ApplicationHandler for App {
         };
         surface.configure(&self.device, &surface_config);
 
+        let state = self
+            .state
+            .as_ref()
+            .expect("the state should exist unless the app is exiting");
+        let egui_state = egui_winit::State::new(
+            state.egui_context().clone(),
+            egui::ViewportId::ROOT,
+            &window,
+            Some(window.scale_factor() as f32),
+            window.theme(),
+            Some(self.device.limits().max_texture_dimension_2d as usize),
+        );
+
         self.window_state = Some(WindowState {
             window,
             surface,
             surface_config,
+            egui_state,
         });
     }
 
@@ -108,7 +126,7 @@ impl ApplicationHandler for App {
             window,
             surface,
             surface_config,
-            ..
+            egui_state,
         } = self
             .window_state
             .as_mut()
@@ -120,6 +138,9 @@ impl ApplicationHandler for App {
             .as_mut()
             .expect("the state should exist unless the app is exiting");
 
+        // releases always go through, so nothing gets stuck when a button is let go over the ui
+        let consumed_by_ui = egui_state.on_window_event(window, &event).consumed;
+
         let mut resized = |surface_config: &mut wgpu::SurfaceConfiguration,
                            size: winit::dpi::PhysicalSize<u32>| {
             surface_config.width = size.width.max(1);
@@ -160,7 +181,9 @@ impl ApplicationHandler for App {
                     }
                 };
 
-                state.render(&self.device, &self.queue, &surface_texture.texture);
+                state.set_ui_input(egui_state.take_egui_input(window));
+                state.render(&self.device, &self.queue, &surface_texture.texture, true);
+                egui_state.handle_platform_output(window, state.take_ui_platform_output());
 
                 window.pre_present_notify();
                 surface_texture.present();
@@ -180,25 +203,32 @@ impl ApplicationHandler for App {
                         ..
                     },
                 is_synthetic: _,
-            } => state.key(key, key_state, window),
+            } if !consumed_by_ui || key_state == ElementState::Released => {
+                state.key(key, key_state, window);
+            }
 
             WindowEvent::MouseWheel {
                 device_id: _,
                 delta: MouseScrollDelta::LineDelta(x, y),
                 phase: _,
-            } => state.mouse_scrolled(cgmath::vec2(x, y)),
+            } if !consumed_by_ui => state.mouse_scrolled(cgmath::vec2(x, y)),
 
             WindowEvent::MouseWheel {
                 device_id: _,
                 delta: MouseScrollDelta::PixelDelta(delta),
                 phase: _,
-            } => state.mouse_scrolled(cgmath::vec2(delta.x as f32, delta.y as f32)),
+            } if !consumed_by_ui => {
+                state.mouse_scrolled(cgmath::vec2(delta.x as f32, delta.y as f32))
+            }
 
             WindowEvent::CursorMoved {
                 device_id: _,
                 position,
             } => {
                 self.cursor_position = position;
+                if consumed_by_ui {
+                    return;
+                }
                 let PhysicalSize { width, height } = window.inner_size();
                 state.cursor_moved(cgmath::Vector2 {
                     x: (((self.cursor_position.x as f32 + 0.5) / width as f32) * 2.0 - 1.0)
@@ -211,7 +241,7 @@ impl ApplicationHandler for App {
                 device_id: _,
                 state: button_state,
                 button,
-            } => {
+            } if !consumed_by_ui || button_state == ElementState::Released => {
                 let PhysicalSize { width, height } = window.inner_size();
                 state.mouse(
                     button,
@@ -230,6 +260,17 @@ impl ApplicationHandler for App {
 }
 
 fn main() {
+    let scene = match std::env::args_os().nth(1) {
+        Some(path) => match Scene::load(&path) {
+            Ok(scene) => scene,
+            Err(e) => {
+                eprintln!("ERROR: {}: {e}", path.to_string_lossy());
+                std::process::exit(1);
+            }
+        },
+        None => Scene::default(),
+    };
+
     let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
         ..Default::default()
     });
@@ -240,19 +281,23 @@ fn main() {
         compatible_surface: None,
     }))
     .expect("an adapter should have been requested successfully");
+    if let Err(e) = check_adapter_limits(&adapter) {
+        eprintln!("ERROR: {e}");
+        std::process::exit(1);
+    }
 
     let (device, queue) = pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: None,
             required_features: wgpu::Features::empty(),
-            required_limits: wgpu::Limits::default(),
+            required_limits: required_limits(),
             memory_hints: wgpu::MemoryHints::Performance,
         },
         None,
     ))
     .expect("device should have been requested successfully");
 
-    let state = State::new(&device, &queue);
+    let state = State::new(&device, &queue, scene);
 
     let event_loop = EventLoop::new().expect("the event loop should be created");
     event_loop.set_control_flow(ControlFlow::Poll);
@@ -0,0 +1,238 @@
+use crate::{math::Rotor, objects::Object, ray::Ray};
+
+/// An axis aligned bounding box in 4d
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: cgmath::Vector4<f32>,
+    pub max: cgmath::Vector4<f32>,
+}
+
+impl Aabb {
+    pub fn union(self, other: Aabb) -> Aabb {
+        Aabb {
+            min: cgmath::vec4(
+                self.min.x.min(other.min.x),
+                self.min.y.min(other.min.y),
+                self.min.z.min(other.min.z),
+                self.min.w.min(other.min.w),
+            ),
+            max: cgmath::vec4(
+                self.max.x.max(other.max.x),
+                self.max.y.max(other.max.y),
+                self.max.z.max(other.max.z),
+                self.max.w.max(other.max.w),
+            ),
+        }
+    }
+
+    /// The result has a negative size if the boxes dont overlap, so nothing will be inside it
+    pub fn intersection(self, other: Aabb) -> Aabb {
+        Aabb {
+            min: cgmath::vec4(
+                self.min.x.max(other.min.x),
+                self.min.y.max(other.min.y),
+                self.min.z.max(other.min.z),
+                self.min.w.max(other.min.w),
+            ),
+            max: cgmath::vec4(
+                self.max.x.min(other.max.x),
+                self.max.y.min(other.max.y),
+                self.max.z.min(other.max.z),
+                self.max.w.min(other.max.w),
+            ),
+        }
+    }
+
+    /// The bounding box of this box after it has been rotated around the origin and then moved by `position`
+    pub fn transformed(self, rotation: Rotor, position: cgmath::Vector4<f32>) -> Aabb {
+        let half_size = (self.max - self.min) * 0.5;
+        // each rotated edge adds its length along each axis to the extents
+        let half_extents = [0, 1, 2, 3]
+            .map(|axis| {
+                let mut edge = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+                edge[axis] = half_size[axis];
+                let edge = rotation.rotate(edge);
+                cgmath::vec4(edge.x.abs(), edge.y.abs(), edge.z.abs(), edge.w.abs())
+            })
+            .into_iter()
+            .sum::<cgmath::Vector4<f32>>();
+        let center = position + rotation.rotate(self.center());
+        Aabb {
+            min: center - half_extents,
+            max: center + half_extents,
+        }
+    }
+
+    /// The smallest box containing all of the points, `None` if there arent any
+    pub fn from_points(points: impl IntoIterator<Item = cgmath::Vector4<f32>>) -> Option<Aabb> {
+        points
+            .into_iter()
+            .map(|point| Aabb {
+                min: point,
+                max: point,
+            })
+            .reduce(Aabb::union)
+    }
+
+    /// Whether the ray goes through the box somewhere in front of its origin and before `max_distance`,
+    /// the same as `bounds_hit` in `ray_tracing.wgsl`
+    pub fn hit_by(
+        &self,
+        ray: Ray,
+        inverse_direction: cgmath::Vector4<f32>,
+        max_distance: f32,
+    ) -> bool {
+        let mut near_distance = f32::NEG_INFINITY;
+        let mut far_distance = f32::INFINITY;
+        for axis in 0..4 {
+            // rays going along the sides of the box would get nan distances to them, so they are only checked for being between them
+            if ray.direction[axis] == 0.0 {
+                if ray.origin[axis] < self.min[axis] || ray.origin[axis] > self.max[axis] {
+                    return false;
+                }
+                continue;
+            }
+            let a = (self.min[axis] - ray.origin[axis]) * inverse_direction[axis];
+            let b = (self.max[axis] - ray.origin[axis]) * inverse_direction[axis];
+            near_distance = near_distance.max(a.min(b));
+            far_distance = far_distance.min(a.max(b));
+        }
+        near_distance <= far_distance && far_distance > 0.0 && near_distance < max_distance
+    }
+
+    pub fn center(&self) -> cgmath::Vector4<f32> {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn longest_axis(&self) -> usize {
+        let size = self.max - self.min;
+        (1..4).fold(0, |longest, axis| {
+            if size[axis] > size[longest] {
+                axis
+            } else {
+                longest
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum BvhNodeKind {
+    /// The left child is always the node directly after this one
+    Interior { right_child: usize },
+    /// `object` is the index of whatever the bvh was built from, which is a cell for the bvh of a `Mesh`
+    Leaf { object: usize },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BvhNode {
+    pub bounds: Aabb,
+    pub kind: BvhNodeKind,
+}
+
+/// A bounding volume hierarchy over all the objects that have bounds, the root is the first node
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    pub nodes: Vec<BvhNode>,
+}
+
+impl Bvh {
+    pub fn build(objects: &[Object]) -> Self {
+        Self::from_leaves(
+            objects
+                .iter()
+                .enumerate()
+                .filter_map(|(index, object)| Some((index, object.bounds()?)))
+                .collect(),
+        )
+    }
+
+    /// Builds a bvh over anything with bounds, each leaf is the index of one thing along with its bounds
+    pub fn from_leaves(mut leaves: Vec<(usize, Aabb)>) -> Self {
+        let mut nodes = Vec::with_capacity((leaves.len() * 2).saturating_sub(1));
+        if !leaves.is_empty() {
+            Self::build_node(&mut nodes, &mut leaves);
+        }
+        Self { nodes }
+    }
+
+    fn build_node(nodes: &mut Vec<BvhNode>, leaves: &mut [(usize, Aabb)]) {
+        if let [(object, bounds)] = *leaves {
+            nodes.push(BvhNode {
+                bounds,
+                kind: BvhNodeKind::Leaf { object },
+            });
+            return;
+        }
+
+        let bounds = leaves
+            .iter()
+            .map(|&(_, bounds)| bounds)
+            .reduce(Aabb::union)
+            .expect("there should be at least 2 leaves");
+        let split_axis = leaves
+            .iter()
+            .map(|(_, bounds)| {
+                let center = bounds.center();
+                Aabb {
+                    min: center,
+                    max: center,
+                }
+            })
+            .reduce(Aabb::union)
+            .expect("there should be at least 2 leaves")
+            .longest_axis();
+
+        // splitting at the median keeps the tree balanced, so the traversal stack in the shader stays small
+        let middle = leaves.len() / 2;
+        leaves.select_nth_unstable_by(middle, |(_, a), (_, b)| {
+            a.center()[split_axis].total_cmp(&b.center()[split_axis])
+        });
+        let (left, right) = leaves.split_at_mut(middle);
+
+        let index = nodes.len();
+        nodes.push(BvhNode {
+            bounds,
+            kind: BvhNodeKind::Interior { right_child: 0 },
+        });
+        Self::build_node(nodes, left);
+        let right_child = nodes.len();
+        Self::build_node(nodes, right);
+        nodes[index].kind = BvhNodeKind::Interior { right_child };
+    }
+
+    /// Updates the bounds of every node after objects have moved, without changing the structure of the tree.
+    /// This is much faster than rebuilding, but the tree gets worse the further objects move.
+    /// Objects that gained or lost their bounds (like a csg difference changed to a union with a hyper plane) need
+    /// different leaves, so then the tree is rebuilt instead
+    pub fn refit(&mut self, objects: &[Object]) {
+        let bounds = objects.iter().map(Object::bounds).collect::<Vec<_>>();
+        let mut leaves = self.nodes.iter().filter_map(|node| match node.kind {
+            BvhNodeKind::Leaf { object } => Some(object),
+            BvhNodeKind::Interior { .. } => None,
+        });
+        // every leaf having bounds and there being as many leaves as objects with bounds means they are the same objects
+        let same_leaves = leaves.clone().count() == bounds.iter().flatten().count()
+            && leaves.all(|object| bounds.get(object).is_some_and(Option::is_some));
+        if !same_leaves {
+            *self = Self::build(objects);
+            return;
+        }
+        self.refit_leaves(|object| {
+            bounds[object].expect("only objects with bounds should be in the bvh")
+        });
+    }
+
+    /// The same as `refit`, but with the new bounds of each leaf coming from `leaf_bounds`
+    pub fn refit_leaves(&mut self, leaf_bounds: impl Fn(usize) -> Aabb) {
+        // children always come after their parent, so going backwards updates them first
+        for index in (0..self.nodes.len()).rev() {
+            self.nodes[index].bounds = match self.nodes[index].kind {
+                BvhNodeKind::Interior { right_child } => self.nodes[index + 1]
+                    .bounds
+                    .union(self.nodes[right_child].bounds),
+                BvhNodeKind::Leaf { object } => leaf_bounds(object),
+            };
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::math::Rotor;
-use cgmath::Zero;
+use cgmath::{InnerSpace, Zero};
 use winit::{event::ElementState, keyboard::KeyCode};
 
 #[derive(Debug)]
@@ -14,6 +14,9 @@ pub struct Camera {
     pub base_rotation: Rotor,
     pub volume_mode: bool,
     pub volume_mode_percentage: f32,
+    /// Shows the 3d cross-section of the world along the camera's ana axis, as if nothing outside it existed
+    pub slice_mode: bool,
+    pub slice_mode_percentage: f32,
     pub xy_rotation: f32,
 
     pub sun_direction: cgmath::Vector4<f32>,
@@ -40,6 +43,22 @@ impl Camera {
             * Rotor::rotation_xy(self.xy_rotation * (1.0 - self.volume_mode_percentage))
     }
 
+    /// Removes the part of a vector that points out of the slice (more of it the further into slice mode the camera is),
+    /// keeping its length. Vectors that point straight out of the slice are left alone
+    pub fn flatten_to_slice(&self, vector: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        if self.slice_mode_percentage <= 0.0 {
+            return vector;
+        }
+
+        let ana = self.get_rotation().rotate(Camera::ANA);
+        let flattened = vector - ana * (vector.dot(ana) * self.slice_mode_percentage);
+        let length_squared = flattened.magnitude2();
+        if length_squared < 0.000001 {
+            return vector;
+        }
+        flattened * (vector.magnitude() / length_squared.sqrt())
+    }
+
     pub fn update(&mut self, ts: f32) {
         if self.volume_mode {
             self.volume_mode_percentage += ts;
@@ -48,6 +67,13 @@ impl Camera {
         }
         self.volume_mode_percentage = self.volume_mode_percentage.clamp(0.0, 1.0);
 
+        if self.slice_mode {
+            self.slice_mode_percentage += ts;
+        } else {
+            self.slice_mode_percentage -= ts;
+        }
+        self.slice_mode_percentage = self.slice_mode_percentage.clamp(0.0, 1.0);
+
         if self.volume_mode_percentage >= 1.0 {
             self.xy_rotation = 0.0;
         }
@@ -62,15 +88,15 @@ impl Camera {
         self.position += up * (self.up_movement * ts);
         self.position += right * (self.right_movement * ts);
         self.position += ana * (self.ana_movement * ts);
-
-        // not really sure if this needs to be done here, but doing it somewhere is probably good
-        self.base_rotation = self.base_rotation.normalized();
     }
 
     pub fn key(&mut self, key: KeyCode, state: ElementState) {
         if let (KeyCode::KeyV, ElementState::Pressed) = (key, state) {
             self.volume_mode = !self.volume_mode;
         }
+        if let (KeyCode::KeyC, ElementState::Pressed) = (key, state) {
+            self.slice_mode = !self.slice_mode;
+        }
 
         let speed = 2.0;
         let movement = match key {
@@ -107,6 +133,7 @@ impl Camera {
         } else {
             self.base_rotation = self.base_rotation * Rotor::rotation_xw(delta.y * sensitivity);
         }
+        self.base_rotation = self.base_rotation.normalized();
     }
 
     pub fn mouse_moved(&mut self, delta: cgmath::Vector2<f32>) {
@@ -123,6 +150,9 @@ impl Camera {
 
             self.base_rotation = self.base_rotation * Rotor::rotation_xz(delta.x * sensitivity);
         }
+        // normalizing only when the rotation changes, because renormalizing an already normalized rotor
+        // can still change the last few bits, which would reset path tracing accumulation every frame
+        self.base_rotation = self.base_rotation.normalized();
     }
 }
 
@@ -139,6 +169,8 @@ impl Default for Camera {
             base_rotation: Rotor::IDENTITY,
             volume_mode: false,
             volume_mode_percentage: 0.0,
+            slice_mode: false,
+            slice_mode_percentage: 0.0,
             xy_rotation: 0.0,
 
             sun_direction: cgmath::vec4(-0.2, 1.0, 0.1, 0.0),
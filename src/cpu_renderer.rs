@@ -0,0 +1,209 @@
+//! A cpu implementation of `ray_tracing.wgsl`, used as a reference for the gpu output and for rendering without a gpu
+
+use crate::{
+    camera::Camera,
+    image::Image,
+    material::{Material, MaterialKind},
+    objects::Object,
+    ray::{Hit, Ray, closest_hit},
+};
+use cgmath::{ElementWise, InnerSpace};
+
+pub struct CpuRenderer<'a> {
+    pub camera: &'a Camera,
+    pub materials: &'a [Material],
+    pub objects: &'a [Object],
+    pub max_bounces: u32,
+}
+
+const MAX_TRACED_RAYS: usize = 16;
+
+impl CpuRenderer<'_> {
+    pub fn ray_color(&self, primary_ray: Ray) -> cgmath::Vector3<f32> {
+        let camera = self.camera;
+        let mut color = cgmath::vec3(0.0, 0.0, 0.0);
+
+        // the same limit as the shader, so both produce the same image with lots of bounces
+        let mut stack = Vec::with_capacity(MAX_TRACED_RAYS);
+        stack.push((primary_ray, cgmath::vec3(1.0, 1.0, 1.0), 0));
+
+        let sun_direction = camera.flatten_to_slice(camera.sun_direction);
+        while let Some((ray, throughput, bounces)) = stack.pop() {
+            let Some((_, mut hit)) = closest_hit(self.objects, ray) else {
+                if sun_direction.dot(ray.direction) > 0.99 {
+                    color += throughput.mul_element_wise(camera.sun_color);
+                } else {
+                    color += throughput.mul_element_wise(sky_color(camera, ray.direction));
+                }
+                continue;
+            };
+            // in slice mode surfaces are shaded like the 3d shapes they are cut into
+            hit.normal = camera.flatten_to_slice(hit.normal);
+
+            let material = &self.materials[hit.material as usize];
+            color += throughput.mul_element_wise(material.color) * material.emission_strength;
+
+            if material.kind == MaterialKind::Diffuse || bounces >= self.max_bounces {
+                color += throughput
+                    .mul_element_wise(material.color)
+                    .mul_element_wise(
+                        camera.ambient_light_color + self.sun_light(&hit, sun_direction),
+                    );
+                continue;
+            }
+
+            let reflected = ray_from_hit(&hit, reflect_direction(ray.direction, hit.normal));
+            match material.kind {
+                MaterialKind::Diffuse => unreachable!("diffuse materials dont bounce"),
+                MaterialKind::Metal { roughness } => {
+                    // rays arent scattered like when path tracing, so rough metal is shaded partly like a diffuse
+                    // surface instead, which keeps the image free of noise
+                    color += throughput
+                        .mul_element_wise(material.color)
+                        .mul_element_wise(
+                            camera.ambient_light_color + self.sun_light(&hit, sun_direction),
+                        )
+                        * roughness;
+                    stack.push((
+                        reflected,
+                        throughput.mul_element_wise(material.color) * (1.0 - roughness),
+                        bounces + 1,
+                    ));
+                }
+                MaterialKind::Dielectric {
+                    index_of_refraction,
+                } => {
+                    let (refracted, reflectance) =
+                        refract_direction(ray.direction, &hit, index_of_refraction);
+                    if let Some(refracted) = refracted
+                        && stack.len() < MAX_TRACED_RAYS
+                    {
+                        stack.push((
+                            ray_from_hit(&hit, refracted),
+                            throughput.mul_element_wise(material.color) * (1.0 - reflectance),
+                            bounces + 1,
+                        ));
+                    }
+                    if stack.len() < MAX_TRACED_RAYS {
+                        stack.push((reflected, throughput * reflectance, bounces + 1));
+                    }
+                }
+            }
+        }
+
+        color
+    }
+
+    fn sun_light(&self, hit: &Hit, sun_direction: cgmath::Vector4<f32>) -> cgmath::Vector3<f32> {
+        let sun_ray = Ray {
+            origin: hit.position + hit.normal * 0.001,
+            direction: sun_direction,
+        };
+        if closest_hit(self.objects, sun_ray).is_some() {
+            return cgmath::vec3(0.0, 0.0, 0.0);
+        }
+        self.camera.sun_light_color * sun_ray.direction.dot(hit.normal).max(0.0)
+    }
+
+    /// The color of a pixel, where `y` counts up from the bottom of the image like it does in the shader
+    pub fn pixel_color(&self, x: u32, y: u32, width: u32, height: u32) -> cgmath::Vector3<f32> {
+        let rotation = self.camera.get_rotation();
+        let forward = rotation.rotate(Camera::FORWARD);
+        let up = rotation.rotate(Camera::UP);
+        let right = rotation.rotate(Camera::RIGHT);
+
+        let aspect = width as f32 / height as f32;
+        let uv = cgmath::vec2(
+            ((x as f32 + 0.5) / width as f32) * 2.0 - 1.0,
+            ((y as f32 + 0.5) / height as f32) * 2.0 - 1.0,
+        );
+
+        let ray = Ray {
+            origin: self.camera.position,
+            direction: (right * (uv.x * aspect) + up * uv.y + forward).normalize(),
+        };
+        self.ray_color(ray)
+    }
+
+    /// Renders the whole image, splitting the rows between all available threads
+    pub fn render(&self, width: u32, height: u32) -> Image {
+        let mut image = Image::new(width, height);
+
+        let thread_count = std::thread::available_parallelism().map_or(1, |count| count.get());
+        let rows_per_thread = (height as usize).div_ceil(thread_count).max(1);
+        std::thread::scope(|scope| {
+            for (chunk_index, chunk) in image
+                .pixels
+                .chunks_mut(rows_per_thread * width as usize)
+                .enumerate()
+            {
+                scope.spawn(move || {
+                    let first_row = chunk_index * rows_per_thread;
+                    for (index, pixel) in chunk.iter_mut().enumerate() {
+                        let x = (index % width as usize) as u32;
+                        let row = (first_row + index / width as usize) as u32;
+                        // rows in the image start at the top, but the shader starts at the bottom
+                        let color = self.pixel_color(x, height - 1 - row, width, height);
+                        *pixel = color_to_pixel(color);
+                    }
+                });
+            }
+        });
+
+        image
+    }
+}
+
+fn sky_color(camera: &Camera, direction: cgmath::Vector4<f32>) -> cgmath::Vector3<f32> {
+    camera.down_sky_color
+        + (camera.up_sky_color - camera.down_sky_color) * (direction.y * 0.5 + 0.5)
+}
+
+/// Moves the origin off the surface to the side the direction is going towards, so the new ray doesnt hit the same surface again
+fn ray_from_hit(hit: &Hit, direction: cgmath::Vector4<f32>) -> Ray {
+    Ray {
+        origin: hit.position + hit.normal * (0.001 * direction.dot(hit.normal).signum()),
+        direction,
+    }
+}
+
+fn reflect_direction(
+    direction: cgmath::Vector4<f32>,
+    normal: cgmath::Vector4<f32>,
+) -> cgmath::Vector4<f32> {
+    direction - normal * (2.0 * direction.dot(normal))
+}
+
+/// Returns the refracted direction (none if there is total internal reflection) and the fraction of light that gets reflected instead
+fn refract_direction(
+    direction: cgmath::Vector4<f32>,
+    hit: &Hit,
+    index_of_refraction: f32,
+) -> (Option<cgmath::Vector4<f32>>, f32) {
+    let eta = if hit.front_face {
+        1.0 / index_of_refraction
+    } else {
+        index_of_refraction
+    };
+
+    let cos_theta = (-direction.dot(hit.normal)).min(1.0);
+    let sin_theta_squared = eta * eta * (1.0 - cos_theta * cos_theta);
+    if sin_theta_squared > 1.0 {
+        return (None, 1.0);
+    }
+
+    let refracted = (direction * eta
+        + hit.normal * (eta * cos_theta - (1.0 - sin_theta_squared).sqrt()))
+    .normalize();
+
+    // schlick's approximation
+    let r0 = (1.0 - eta) / (1.0 + eta);
+    let r0_squared = r0 * r0;
+    let reflectance = r0_squared + (1.0 - r0_squared) * (1.0 - cos_theta).powi(5);
+    (Some(refracted), reflectance)
+}
+
+fn color_to_pixel(color: cgmath::Vector3<f32>) -> [u8; 4] {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+    [channel(color.x), channel(color.y), channel(color.z), 255]
+}
@@ -188,3 +188,4 @@ tuple_impls!(A B, C D, E F, G H, I J);
 tuple_impls!(A B, C D, E F, G H, I J, K L);
 tuple_impls!(A B, C D, E F, G H, I J, K L, M N);
 tuple_impls!(A B, C D, E F, G H, I J, K L, M N, O P);
+tuple_impls!(A B, C D, E F, G H, I J, K L, M N, O P, Q R);
@@ -1,7 +1,14 @@
 use crate::{
+    bvh::{Bvh, BvhNodeKind},
     camera::Camera,
-    material::Material,
-    objects::{HyperPlane, HyperSphere},
+    material::{Material, MaterialKind},
+    math::Rotor,
+    mesh::Tetrahedron,
+    objects::{
+        Csg, CsgOperation, HyperCube, HyperCylinder, HyperPlane, HyperSphere, Object, Sdf,
+        TetrahedronMesh,
+    },
+    sdf::SdfShape,
 };
 use cgmath::InnerSpace;
 use encase::{ArrayLength, ShaderSize, ShaderType};
@@ -13,18 +20,20 @@ pub struct GpuLengthArray<T: ShaderSize> {
     pub data: Vec<T>,
 }
 
-#[derive(Debug, ShaderType)]
+#[derive(Debug, PartialEq, ShaderType)]
 pub struct GpuCamera {
     pub position: cgmath::Vector4<f32>,
     pub forward: cgmath::Vector4<f32>,
     pub up: cgmath::Vector4<f32>,
     pub right: cgmath::Vector4<f32>,
+    pub ana: cgmath::Vector4<f32>,
     pub sun_direction: cgmath::Vector4<f32>,
     pub sun_color: cgmath::Vector3<f32>,
     pub sun_light_color: cgmath::Vector3<f32>,
     pub ambient_light_color: cgmath::Vector3<f32>,
     pub up_sky_color: cgmath::Vector3<f32>,
     pub down_sky_color: cgmath::Vector3<f32>,
+    pub slice_percentage: f32,
 }
 
 impl GpuCamera {
@@ -40,6 +49,8 @@ impl GpuCamera {
             base_rotation: _,
             volume_mode: _,
             volume_mode_percentage: _,
+            slice_mode: _,
+            slice_mode_percentage,
             xy_rotation: _,
 
             sun_direction,
@@ -55,25 +66,42 @@ impl GpuCamera {
             forward: rotation.rotate(Camera::FORWARD),
             up: rotation.rotate(Camera::UP),
             right: rotation.rotate(Camera::RIGHT),
-            sun_direction,
+            ana: rotation.rotate(Camera::ANA),
+            sun_direction: camera.flatten_to_slice(sun_direction),
             sun_color,
             sun_light_color,
             ambient_light_color,
             up_sky_color,
             down_sky_color,
+            slice_percentage: slice_mode_percentage,
         }
     }
 }
 
+#[derive(Debug, ShaderType)]
+pub struct GpuRenderSettings {
+    pub path_tracing: u32,
+    pub accumulated_frames: u32,
+    pub max_bounces: u32,
+    /// The id of the object to highlight, see `GpuHyperSphere::id`
+    pub selected_object: u32,
+}
+
+impl GpuRenderSettings {
+    pub const NOTHING_SELECTED: u32 = u32::MAX;
+}
+
 #[derive(Debug, ShaderType)]
 pub struct GpuHyperSphere {
     pub position: cgmath::Vector4<f32>,
     pub radius: f32,
     pub material: u32,
+    /// The index of the object in the scene, so the shader can tell which object is selected
+    pub id: u32,
 }
 
 impl GpuHyperSphere {
-    pub fn from_hyper_sphere(hyper_sphere: &HyperSphere) -> Self {
+    pub fn from_hyper_sphere(hyper_sphere: &HyperSphere, id: u32) -> Self {
         let HyperSphere {
             position,
             radius,
@@ -83,6 +111,7 @@ impl GpuHyperSphere {
             position,
             radius,
             material,
+            id,
         }
     }
 }
@@ -92,10 +121,11 @@ pub struct GpuHyperPlane {
     pub normal: cgmath::Vector4<f32>,
     pub distance: f32,
     pub material: u32,
+    pub id: u32,
 }
 
 impl GpuHyperPlane {
-    pub fn from_hyper_plane(hyper_plane: &HyperPlane) -> Self {
+    pub fn from_hyper_plane(hyper_plane: &HyperPlane, id: u32) -> Self {
         let HyperPlane {
             position,
             normal,
@@ -105,6 +135,729 @@ impl GpuHyperPlane {
             normal,
             distance: position.dot(normal),
             material,
+            id,
+        }
+    }
+}
+
+#[derive(Debug, ShaderType)]
+pub struct GpuHyperCube {
+    pub position: cgmath::Vector4<f32>,
+    pub half_extents: cgmath::Vector4<f32>,
+    pub rotation: Rotor,
+    pub material: u32,
+    pub id: u32,
+}
+
+impl GpuHyperCube {
+    pub fn from_hyper_cube(hyper_cube: &HyperCube, id: u32) -> Self {
+        let HyperCube {
+            position,
+            half_extents,
+            rotation,
+            material,
+        } = *hyper_cube;
+        Self {
+            position,
+            half_extents,
+            rotation,
+            material,
+            id,
+        }
+    }
+}
+
+#[derive(Debug, ShaderType)]
+pub struct GpuHyperCylinder {
+    pub position: cgmath::Vector4<f32>,
+    pub rotation: Rotor,
+    /// The axes of each factor, see `HyperCylinderFactor`
+    pub factor_axes: [cgmath::Vector4<f32>; 3],
+    pub factor_radii: cgmath::Vector3<f32>,
+    pub material: u32,
+    pub id: u32,
+}
+
+impl GpuHyperCylinder {
+    pub fn from_hyper_cylinder(hyper_cylinder: &HyperCylinder, id: u32) -> Self {
+        let factors = hyper_cylinder.factors();
+        Self {
+            position: hyper_cylinder.position,
+            rotation: hyper_cylinder.rotation,
+            factor_axes: factors.map(|factor| factor.axes),
+            factor_radii: factors.map(|factor| factor.radius).into(),
+            material: hyper_cylinder.material,
+            id,
+        }
+    }
+}
+
+#[derive(Debug, ShaderType)]
+pub struct GpuHyperCylinders {
+    /// How many hyper cylinders at the start go on forever,
+    /// they arent in the bvh so the shader tests every ray against them
+    pub unbounded_length: u32,
+    pub length: ArrayLength,
+    #[size(runtime)]
+    pub data: Vec<GpuHyperCylinder>,
+}
+
+#[derive(Debug, ShaderType)]
+pub struct GpuBvhNode {
+    pub min: cgmath::Vector4<f32>,
+    pub max: cgmath::Vector4<f32>,
+    pub kind: u32,
+    /// The right child for interior nodes (the left child is the next node),
+    /// otherwise the index into the array of objects of the same kind
+    pub index: u32,
+}
+
+impl GpuBvhNode {
+    pub const INTERIOR: u32 = 0;
+    pub const HYPER_SPHERE: u32 = 1;
+    pub const HYPER_CUBE: u32 = 2;
+    /// The index is the root node of the csg object
+    pub const CSG: u32 = 3;
+    /// The index is the first node of the sdf object
+    pub const SDF: u32 = 4;
+    pub const HYPER_CYLINDER: u32 = 5;
+    /// The index is the root node of the bvh over the cells of the mesh, those come after the nodes of the scene
+    pub const TETRAHEDRON_MESH: u32 = 6;
+    /// Only in the bvhs of tetrahedron meshes, the index is the cell in `GpuObjects::tetrahedra`
+    pub const TETRAHEDRON: u32 = 7;
+
+    /// `leaves` is the kind and index for each object that the bvh was built from
+    fn from_bvh(bvh: &Bvh, leaves: &[Option<(u32, u32)>]) -> Vec<Self> {
+        bvh.nodes
+            .iter()
+            .map(|node| {
+                let (kind, index) = match node.kind {
+                    BvhNodeKind::Interior { right_child } => (Self::INTERIOR, right_child as u32),
+                    BvhNodeKind::Leaf { object } => {
+                        leaves[object].expect("only objects with bounds should be in the bvh")
+                    }
+                };
+                Self {
+                    min: node.bounds.min,
+                    max: node.bounds.max,
+                    kind,
+                    index,
+                }
+            })
+            .collect()
+    }
+}
+
+/// A node of a csg tree, the trees are stored in post order so children always come before their parent
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub struct GpuCsgNode {
+    pub position: cgmath::Vector4<f32>,
+    /// The radius in x for hyper spheres, the normal for hyper planes and the half extents for hyper cubes
+    pub parameters: cgmath::Vector4<f32>,
+    pub rotation: Rotor,
+    pub kind: u32,
+    /// How many nodes are in the subtree that ends with this node, including itself
+    pub subtree_size: u32,
+    pub material: u32,
+    /// The index of the whole csg object in the scene, see `GpuHyperSphere::id`
+    pub id: u32,
+    /// For the primitives that dont fit in a csg node, the index of the hyper cylinder in `GpuHyperCylinders`,
+    /// the first node of the object in `GpuSdfNodes` or the root bvh node of a tetrahedron mesh
+    pub index: u32,
+}
+
+impl GpuCsgNode {
+    pub const UNION: u32 = 0;
+    pub const INTERSECTION: u32 = 1;
+    pub const DIFFERENCE: u32 = 2;
+    pub const HYPER_SPHERE: u32 = 3;
+    pub const HYPER_PLANE: u32 = 4;
+    pub const HYPER_CUBE: u32 = 5;
+    pub const SDF: u32 = 6;
+    pub const HYPER_CYLINDER: u32 = 7;
+    pub const TETRAHEDRON_MESH: u32 = 8;
+
+    /// Adds the nodes of an object to the end of `nodes`, so the root is the last node. Returns how many nodes were added.
+    /// Any hyper cylinders, sdf objects and tetrahedron meshes in the tree are added to `hyper_cylinders`,
+    /// `sdf_nodes` and `meshes`
+    pub fn push_object(
+        nodes: &mut Vec<Self>,
+        hyper_cylinders: &mut Vec<GpuHyperCylinder>,
+        sdf_nodes: &mut Vec<GpuSdfNode>,
+        meshes: &mut GpuTetrahedronMeshes,
+        object: &Object,
+        id: u32,
+    ) -> u32 {
+        let node = match object {
+            Object::HyperSphere(HyperSphere {
+                position,
+                radius,
+                material,
+            }) => Self {
+                position: *position,
+                parameters: cgmath::vec4(*radius, 0.0, 0.0, 0.0),
+                rotation: Rotor::IDENTITY,
+                kind: Self::HYPER_SPHERE,
+                subtree_size: 1,
+                material: *material,
+                id,
+                index: 0,
+            },
+            Object::HyperPlane(HyperPlane {
+                position,
+                normal,
+                material,
+            }) => Self {
+                position: *position,
+                parameters: *normal,
+                rotation: Rotor::IDENTITY,
+                kind: Self::HYPER_PLANE,
+                subtree_size: 1,
+                material: *material,
+                id,
+                index: 0,
+            },
+            Object::HyperCube(HyperCube {
+                position,
+                half_extents,
+                rotation,
+                material,
+            }) => Self {
+                position: *position,
+                parameters: *half_extents,
+                rotation: *rotation,
+                kind: Self::HYPER_CUBE,
+                subtree_size: 1,
+                material: *material,
+                id,
+                index: 0,
+            },
+            Object::Csg(Csg {
+                operation,
+                left,
+                right,
+            }) => {
+                let left_size =
+                    Self::push_object(nodes, hyper_cylinders, sdf_nodes, meshes, left, id);
+                let right_size =
+                    Self::push_object(nodes, hyper_cylinders, sdf_nodes, meshes, right, id);
+                Self {
+                    position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    parameters: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    rotation: Rotor::IDENTITY,
+                    kind: match operation {
+                        CsgOperation::Union => Self::UNION,
+                        CsgOperation::Intersection => Self::INTERSECTION,
+                        CsgOperation::Difference => Self::DIFFERENCE,
+                    },
+                    subtree_size: 1 + left_size + right_size,
+                    material: 0,
+                    id,
+                    index: 0,
+                }
+            }
+            Object::Sdf(sdf) => Self::sdf(sdf_nodes, sdf, id),
+            Object::HyperTorus(hyper_torus) => Self::sdf(sdf_nodes, &hyper_torus.sdf(), id),
+            Object::HyperCylinder(hyper_cylinder) => {
+                hyper_cylinders.push(GpuHyperCylinder::from_hyper_cylinder(hyper_cylinder, id));
+                Self {
+                    position: hyper_cylinder.position,
+                    parameters: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                    rotation: hyper_cylinder.rotation,
+                    kind: Self::HYPER_CYLINDER,
+                    subtree_size: 1,
+                    material: hyper_cylinder.material,
+                    id,
+                    index: hyper_cylinders.len() as u32 - 1,
+                }
+            }
+            Object::TetrahedronMesh(tetrahedron_mesh) => Self {
+                position: tetrahedron_mesh.position,
+                parameters: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                rotation: tetrahedron_mesh.rotation,
+                kind: Self::TETRAHEDRON_MESH,
+                subtree_size: 1,
+                material: tetrahedron_mesh.material,
+                id,
+                index: meshes.push(tetrahedron_mesh, id),
+            },
+        };
+        nodes.push(node);
+        node.subtree_size
+    }
+
+    fn sdf(sdf_nodes: &mut Vec<GpuSdfNode>, sdf: &Sdf, id: u32) -> Self {
+        Self {
+            position: sdf.position,
+            parameters: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            rotation: sdf.rotation,
+            kind: Self::SDF,
+            subtree_size: 1,
+            material: sdf.material,
+            id,
+            index: GpuSdfNode::push_sdf(sdf_nodes, sdf, id),
+        }
+    }
+}
+
+#[derive(Debug, ShaderType)]
+pub struct GpuCsgNodes {
+    /// How many nodes at the start belong to csg objects without bounds,
+    /// they arent in the bvh so the shader tests every ray against them
+    pub unbounded_length: u32,
+    pub length: ArrayLength,
+    #[size(runtime)]
+    pub data: Vec<GpuCsgNode>,
+}
+
+/// A step of the program that works out the distance to an sdf object, each object starts with a node of kind `OBJECT`
+/// followed by its shape. Shapes are stored in post order, with nodes that move the point before their shape and
+/// `RESTORE_POINT` nodes after it
+#[derive(Debug, Clone, Copy, ShaderType)]
+pub struct GpuSdfNode {
+    /// The position of the object, or the offset for `TRANSFORM` nodes
+    pub position: cgmath::Vector4<f32>,
+    /// The parameters of shapes in the order they are in `SdfShape`, the smoothness in x for combining nodes,
+    /// the radius in x for `ROUND` and the period for `REPEAT`
+    pub parameters: cgmath::Vector4<f32>,
+    pub rotation: Rotor,
+    pub kind: u32,
+    /// How many nodes of the shape come after an `OBJECT` node
+    pub length: u32,
+    pub material: u32,
+    /// The index of the object in the scene, see `GpuHyperSphere::id`
+    pub id: u32,
+}
+
+impl GpuSdfNode {
+    pub const OBJECT: u32 = 0;
+    pub const UNION: u32 = 1;
+    pub const INTERSECTION: u32 = 2;
+    pub const DIFFERENCE: u32 = 3;
+    /// Subtracts the radius from the last distance
+    pub const ROUND: u32 = 4;
+    /// Saves the current point and then moves it
+    pub const TRANSFORM: u32 = 5;
+    /// Saves the current point and then moves it into the period around the origin
+    pub const REPEAT: u32 = 6;
+    /// Goes back to the last saved point
+    pub const RESTORE_POINT: u32 = 7;
+    pub const HYPER_SPHERE: u32 = 8;
+    /// A hyper cube with sharp corners, rounded boxes are this followed by a `ROUND`
+    pub const BOX: u32 = 9;
+    pub const SPHERINDER: u32 = 10;
+    pub const CUBINDER: u32 = 11;
+    pub const DUOCYLINDER: u32 = 12;
+    pub const SPHERITORUS: u32 = 13;
+    pub const TORISPHERE: u32 = 14;
+    pub const TIGER: u32 = 15;
+    pub const DITORUS: u32 = 16;
+
+    fn new(kind: u32, parameters: cgmath::Vector4<f32>) -> Self {
+        Self {
+            position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            parameters,
+            rotation: Rotor::IDENTITY,
+            kind,
+            length: 0,
+            material: 0,
+            id: 0,
+        }
+    }
+
+    /// Adds the nodes of an sdf object to the end of `nodes`, returning the index of its first node
+    pub fn push_sdf(nodes: &mut Vec<Self>, sdf: &Sdf, id: u32) -> u32 {
+        let index = nodes.len();
+        nodes.push(Self {
+            position: sdf.position,
+            rotation: sdf.rotation,
+            material: sdf.material,
+            id,
+            ..Self::new(Self::OBJECT, cgmath::vec4(0.0, 0.0, 0.0, 0.0))
+        });
+        Self::push_shape(nodes, &sdf.shape);
+        nodes[index].length = (nodes.len() - index - 1) as u32;
+        index as u32
+    }
+
+    fn push_shape(nodes: &mut Vec<Self>, shape: &SdfShape) {
+        let node = match *shape {
+            SdfShape::HyperSphere { radius } => {
+                Self::new(Self::HYPER_SPHERE, cgmath::vec4(radius, 0.0, 0.0, 0.0))
+            }
+            SdfShape::RoundedBox {
+                half_extents,
+                radius,
+            } => {
+                // the same clamping as `sdf::rounded_box_distance`
+                let radius = radius.clamp(
+                    0.0,
+                    half_extents
+                        .x
+                        .min(half_extents.y)
+                        .min(half_extents.z)
+                        .min(half_extents.w),
+                );
+                let offset = cgmath::vec4(1.0, 1.0, 1.0, 1.0) * radius;
+                nodes.push(Self::new(Self::BOX, half_extents - offset));
+                Self::new(Self::ROUND, cgmath::vec4(radius, 0.0, 0.0, 0.0))
+            }
+            SdfShape::Spherinder {
+                radius,
+                half_height,
+            } => Self::new(
+                Self::SPHERINDER,
+                cgmath::vec4(radius, half_height, 0.0, 0.0),
+            ),
+            SdfShape::Cubinder {
+                radius,
+                half_extents,
+            } => Self::new(
+                Self::CUBINDER,
+                cgmath::vec4(radius, half_extents.x, half_extents.y, 0.0),
+            ),
+            SdfShape::Duocylinder { radii } => {
+                Self::new(Self::DUOCYLINDER, cgmath::vec4(radii.x, radii.y, 0.0, 0.0))
+            }
+            SdfShape::Spheritorus {
+                major_radius,
+                minor_radius,
+            } => Self::new(
+                Self::SPHERITORUS,
+                cgmath::vec4(major_radius, minor_radius, 0.0, 0.0),
+            ),
+            SdfShape::Torisphere {
+                major_radius,
+                minor_radius,
+            } => Self::new(
+                Self::TORISPHERE,
+                cgmath::vec4(major_radius, minor_radius, 0.0, 0.0),
+            ),
+            SdfShape::Tiger {
+                major_radii,
+                minor_radius,
+            } => Self::new(
+                Self::TIGER,
+                cgmath::vec4(major_radii.x, major_radii.y, minor_radius, 0.0),
+            ),
+            SdfShape::Ditorus {
+                major_radius,
+                middle_radius,
+                minor_radius,
+            } => Self::new(
+                Self::DITORUS,
+                cgmath::vec4(major_radius, middle_radius, minor_radius, 0.0),
+            ),
+            SdfShape::Combine {
+                operation,
+                ref left,
+                ref right,
+                smoothness,
+            } => {
+                Self::push_shape(nodes, left);
+                Self::push_shape(nodes, right);
+                let kind = match operation {
+                    CsgOperation::Union => Self::UNION,
+                    CsgOperation::Intersection => Self::INTERSECTION,
+                    CsgOperation::Difference => Self::DIFFERENCE,
+                };
+                Self::new(kind, cgmath::vec4(smoothness, 0.0, 0.0, 0.0))
+            }
+            SdfShape::Transformed {
+                position,
+                rotation,
+                ref shape,
+            } => {
+                nodes.push(Self {
+                    position,
+                    rotation,
+                    ..Self::new(Self::TRANSFORM, cgmath::vec4(0.0, 0.0, 0.0, 0.0))
+                });
+                Self::push_shape(nodes, shape);
+                Self::new(Self::RESTORE_POINT, cgmath::vec4(0.0, 0.0, 0.0, 0.0))
+            }
+            SdfShape::Repeat { period, ref shape } => {
+                nodes.push(Self::new(Self::REPEAT, period));
+                Self::push_shape(nodes, shape);
+                Self::new(Self::RESTORE_POINT, cgmath::vec4(0.0, 0.0, 0.0, 0.0))
+            }
+        };
+        nodes.push(node);
+    }
+}
+
+#[derive(Debug, ShaderType)]
+pub struct GpuSdfNodes {
+    /// How many nodes at the start belong to sdf objects without bounds,
+    /// they arent in the bvh so the shader tests every ray against them
+    pub unbounded_length: u32,
+    pub length: ArrayLength,
+    #[size(runtime)]
+    pub data: Vec<GpuSdfNode>,
+}
+
+/// A cell of a tetrahedron mesh, already moved into the same place as the mesh
+#[derive(Debug, ShaderType)]
+pub struct GpuTetrahedron {
+    pub vertex: cgmath::Vector4<f32>,
+    /// See `Tetrahedron::unnormalized_normal`
+    pub normal: cgmath::Vector4<f32>,
+    /// See `Tetrahedron::dual_edges`
+    pub dual_edges: [cgmath::Vector4<f32>; 3],
+    pub material: u32,
+    pub id: u32,
+}
+
+impl GpuTetrahedron {
+    pub fn from_tetrahedron(tetrahedron: &Tetrahedron, material: u32, id: u32) -> Self {
+        Self {
+            vertex: tetrahedron.vertices[0],
+            normal: tetrahedron.unnormalized_normal(),
+            dual_edges: tetrahedron.dual_edges(),
+            material,
+            id,
+        }
+    }
+}
+
+/// The cells of all the tetrahedron meshes in a scene, and the bvh nodes over them that go after the nodes of the scene bvh
+#[derive(Debug)]
+pub struct GpuTetrahedronMeshes {
+    /// The index in the final array of bvh nodes that `bvh_nodes` starts at
+    pub first_node: u32,
+    pub bvh_nodes: Vec<GpuBvhNode>,
+    pub tetrahedra: Vec<GpuTetrahedron>,
+}
+
+impl GpuTetrahedronMeshes {
+    /// Adds the cells of a mesh and the bvh over them, returning the index of the root bvh node
+    pub fn push(&mut self, tetrahedron_mesh: &TetrahedronMesh, id: u32) -> u32 {
+        let TetrahedronMesh {
+            position,
+            rotation,
+            ref mesh,
+            ..
+        } = *tetrahedron_mesh;
+
+        // moving the cells into place here means the shader doesnt have to move every ray into the space of the mesh
+        let cells = (0..mesh.cells().len())
+            .map(|cell| Tetrahedron {
+                vertices: mesh
+                    .cell(cell)
+                    .vertices
+                    .map(|vertex| position + rotation.rotate(vertex)),
+            })
+            .collect::<Vec<_>>();
+        let mut bvh = mesh.bvh().clone();
+        bvh.refit_leaves(|cell| cells[cell].bounds());
+
+        let root = self.first_node + self.bvh_nodes.len() as u32;
+        let first_tetrahedron = self.tetrahedra.len() as u32;
+        self.bvh_nodes
+            .extend(bvh.nodes.iter().map(|node| GpuBvhNode {
+                min: node.bounds.min,
+                max: node.bounds.max,
+                kind: match node.kind {
+                    BvhNodeKind::Interior { .. } => GpuBvhNode::INTERIOR,
+                    BvhNodeKind::Leaf { .. } => GpuBvhNode::TETRAHEDRON,
+                },
+                index: match node.kind {
+                    BvhNodeKind::Interior { right_child } => root + right_child as u32,
+                    BvhNodeKind::Leaf { object: cell } => first_tetrahedron + cell as u32,
+                },
+            }));
+        self.tetrahedra
+            .extend(cells.iter().enumerate().map(|(index, cell)| {
+                GpuTetrahedron::from_tetrahedron(cell, tetrahedron_mesh.cell_material(index), id)
+            }));
+        root
+    }
+}
+
+/// All the objects in a scene, laid out the way `ray_tracing.wgsl` expects them
+#[derive(Debug)]
+pub struct GpuObjects {
+    pub hyper_spheres: GpuLengthArray<GpuHyperSphere>,
+    pub hyper_planes: GpuLengthArray<GpuHyperPlane>,
+    pub hyper_cubes: GpuLengthArray<GpuHyperCube>,
+    pub hyper_cylinders: GpuHyperCylinders,
+    pub csg_nodes: GpuCsgNodes,
+    pub sdf_nodes: GpuSdfNodes,
+    /// The nodes of the scene bvh followed by the bvhs of the tetrahedron meshes
+    pub bvh_nodes: GpuLengthArray<GpuBvhNode>,
+    pub tetrahedra: GpuLengthArray<GpuTetrahedron>,
+}
+
+impl GpuObjects {
+    /// `bvh` needs to have been built from the same objects
+    pub fn from_objects(objects: &[Object], bvh: &Bvh) -> Self {
+        let mut hyper_spheres = vec![];
+        let mut hyper_planes = vec![];
+        let mut hyper_cubes = vec![];
+        let mut hyper_cylinders = vec![];
+        let mut csg_nodes = vec![];
+        let mut sdf_nodes = vec![];
+        let mut meshes = GpuTetrahedronMeshes {
+            first_node: bvh.nodes.len() as u32,
+            bvh_nodes: vec![],
+            tetrahedra: vec![],
+        };
+        // the kind and index that the bvh uses to refer to each object, `None` for objects that arent in the bvh
+        let mut bvh_leaves = vec![None; objects.len()];
+
+        // objects without bounds have to come first, and csg objects can add hyper cylinders and sdf nodes,
+        // so those are added after
+        let mut bounded_hyper_cylinders = vec![];
+        let mut unbounded_csgs = vec![];
+        let mut bounded_csgs = vec![];
+        let mut bounded_sdfs = vec![];
+        for (index, object) in objects.iter().enumerate() {
+            let id = index as u32;
+            match object {
+                Object::HyperSphere(hyper_sphere) => {
+                    bvh_leaves[index] =
+                        Some((GpuBvhNode::HYPER_SPHERE, hyper_spheres.len() as u32));
+                    hyper_spheres.push(GpuHyperSphere::from_hyper_sphere(hyper_sphere, id));
+                }
+                Object::HyperPlane(hyper_plane) => {
+                    hyper_planes.push(GpuHyperPlane::from_hyper_plane(hyper_plane, id));
+                }
+                Object::HyperCube(hyper_cube) => {
+                    bvh_leaves[index] = Some((GpuBvhNode::HYPER_CUBE, hyper_cubes.len() as u32));
+                    hyper_cubes.push(GpuHyperCube::from_hyper_cube(hyper_cube, id));
+                }
+                Object::HyperCylinder(hyper_cylinder) => {
+                    if object.bounds().is_some() {
+                        bounded_hyper_cylinders.push(index);
+                    } else {
+                        hyper_cylinders
+                            .push(GpuHyperCylinder::from_hyper_cylinder(hyper_cylinder, id));
+                    }
+                }
+                Object::Csg(_) => {
+                    if object.bounds().is_some() {
+                        bounded_csgs.push(index);
+                    } else {
+                        unbounded_csgs.push(index);
+                    }
+                }
+                Object::Sdf(sdf) => {
+                    if object.bounds().is_some() {
+                        bounded_sdfs.push((index, sdf.clone()));
+                    } else {
+                        GpuSdfNode::push_sdf(&mut sdf_nodes, sdf, id);
+                    }
+                }
+                // tori are always bounded, and are rendered the same way as their distance field
+                Object::HyperTorus(hyper_torus) => bounded_sdfs.push((index, hyper_torus.sdf())),
+                Object::TetrahedronMesh(tetrahedron_mesh) => {
+                    bvh_leaves[index] = Some((
+                        GpuBvhNode::TETRAHEDRON_MESH,
+                        meshes.push(tetrahedron_mesh, id),
+                    ));
+                }
+            }
+        }
+
+        let hyper_cylinders_unbounded_length = hyper_cylinders.len() as u32;
+        let sdf_unbounded_length = sdf_nodes.len() as u32;
+        for index in unbounded_csgs {
+            GpuCsgNode::push_object(
+                &mut csg_nodes,
+                &mut hyper_cylinders,
+                &mut sdf_nodes,
+                &mut meshes,
+                &objects[index],
+                index as u32,
+            );
+        }
+        let csg_unbounded_length = csg_nodes.len() as u32;
+        for index in bounded_csgs {
+            GpuCsgNode::push_object(
+                &mut csg_nodes,
+                &mut hyper_cylinders,
+                &mut sdf_nodes,
+                &mut meshes,
+                &objects[index],
+                index as u32,
+            );
+            bvh_leaves[index] = Some((GpuBvhNode::CSG, csg_nodes.len() as u32 - 1));
+        }
+        for index in bounded_hyper_cylinders {
+            let Object::HyperCylinder(hyper_cylinder) = &objects[index] else {
+                unreachable!("only hyper cylinders were added to `bounded_hyper_cylinders`");
+            };
+            bvh_leaves[index] = Some((GpuBvhNode::HYPER_CYLINDER, hyper_cylinders.len() as u32));
+            hyper_cylinders.push(GpuHyperCylinder::from_hyper_cylinder(
+                hyper_cylinder,
+                index as u32,
+            ));
+        }
+        for (index, sdf) in bounded_sdfs {
+            let first = GpuSdfNode::push_sdf(&mut sdf_nodes, &sdf, index as u32);
+            bvh_leaves[index] = Some((GpuBvhNode::SDF, first));
+        }
+
+        Self {
+            hyper_spheres: GpuLengthArray {
+                length: ArrayLength,
+                data: hyper_spheres,
+            },
+            hyper_planes: GpuLengthArray {
+                length: ArrayLength,
+                data: hyper_planes,
+            },
+            hyper_cubes: GpuLengthArray {
+                length: ArrayLength,
+                data: hyper_cubes,
+            },
+            hyper_cylinders: GpuHyperCylinders {
+                unbounded_length: hyper_cylinders_unbounded_length,
+                length: ArrayLength,
+                data: hyper_cylinders,
+            },
+            csg_nodes: GpuCsgNodes {
+                unbounded_length: csg_unbounded_length,
+                length: ArrayLength,
+                data: csg_nodes,
+            },
+            sdf_nodes: GpuSdfNodes {
+                unbounded_length: sdf_unbounded_length,
+                length: ArrayLength,
+                data: sdf_nodes,
+            },
+            bvh_nodes: GpuLengthArray {
+                length: ArrayLength,
+                data: GpuBvhNode::from_bvh(bvh, &bvh_leaves)
+                    .into_iter()
+                    .chain(meshes.bvh_nodes)
+                    .collect(),
+            },
+            tetrahedra: GpuLengthArray {
+                length: ArrayLength,
+                data: meshes.tetrahedra,
+            },
+        }
+    }
+}
+
+/// The `GpuObjects` that were last built, kept so they are only rebuilt and uploaded after the scene changes
+#[derive(Debug, Default)]
+pub struct GpuObjectsCache {
+    objects: Option<GpuObjects>,
+}
+
+impl GpuObjectsCache {
+    /// Marks the objects, materials or bvh as changed, so the next `update` rebuilds them
+    pub fn invalidate(&mut self) {
+        self.objects = None;
+    }
+
+    /// Returns the rebuilt objects if they changed since the last update, or `None` if the built ones are still current
+    pub fn update(&mut self, objects: &[Object], bvh: &Bvh) -> Option<&GpuObjects> {
+        match self.objects {
+            Some(_) => None,
+            None => Some(self.objects.insert(GpuObjects::from_objects(objects, bvh))),
         }
     }
 }
@@ -112,12 +865,37 @@ impl GpuHyperPlane {
 #[derive(Debug, ShaderType)]
 pub struct GpuMaterial {
     pub color: cgmath::Vector3<f32>,
+    pub kind: u32,
+    pub roughness: f32,
+    pub index_of_refraction: f32,
+    pub emission_strength: f32,
 }
 
 impl GpuMaterial {
+    pub const DIFFUSE: u32 = 0;
+    pub const METAL: u32 = 1;
+    pub const DIELECTRIC: u32 = 2;
+
     pub fn from_material(material: &Material) -> Self {
-        let Material { color } = *material;
-        Self { color }
+        let Material {
+            color,
+            kind,
+            emission_strength,
+        } = *material;
+        let (kind, roughness, index_of_refraction) = match kind {
+            MaterialKind::Diffuse => (Self::DIFFUSE, 0.0, 1.0),
+            MaterialKind::Metal { roughness } => (Self::METAL, roughness, 1.0),
+            MaterialKind::Dielectric {
+                index_of_refraction,
+            } => (Self::DIELECTRIC, 0.0, index_of_refraction),
+        };
+        Self {
+            color,
+            kind,
+            roughness,
+            index_of_refraction,
+            emission_strength,
+        }
     }
 }
 
@@ -133,3 +911,24 @@ pub struct GpuLine {
     pub width: f32,
     pub color: cgmath::Vector4<f32>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Scene;
+
+    #[test]
+    fn unchanged_scene_is_not_rebuilt() {
+        let scene = Scene::default();
+        let bvh = Bvh::build(&scene.objects);
+        let mut cache = GpuObjectsCache::default();
+
+        assert!(cache.update(&scene.objects, &bvh).is_some());
+        assert!(cache.update(&scene.objects, &bvh).is_none());
+        assert!(cache.update(&scene.objects, &bvh).is_none());
+
+        cache.invalidate();
+        assert!(cache.update(&scene.objects, &bvh).is_some());
+        assert!(cache.update(&scene.objects, &bvh).is_none());
+    }
+}
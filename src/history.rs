@@ -0,0 +1,263 @@
+use crate::{material::Material, objects::Object};
+
+/// A single change to the scene that can be undone
+#[derive(Debug, Clone)]
+pub enum Edit {
+    /// Replaces an object, used for moving and rotating objects and for changing their properties
+    ModifyObject {
+        index: usize,
+        before: Object,
+        after: Object,
+    },
+    AddObject {
+        index: usize,
+        object: Object,
+    },
+    DeleteObject {
+        index: usize,
+        object: Object,
+    },
+    ModifyMaterial {
+        index: usize,
+        before: Material,
+        after: Material,
+    },
+}
+
+impl Edit {
+    pub fn apply(&self, objects: &mut Vec<Object>, materials: &mut [Material]) {
+        match self {
+            Edit::ModifyObject { index, after, .. } => objects[*index] = after.clone(),
+            Edit::AddObject { index, object } => objects.insert(*index, object.clone()),
+            Edit::DeleteObject { index, .. } => {
+                objects.remove(*index);
+            }
+            Edit::ModifyMaterial { index, after, .. } => materials[*index] = after.clone(),
+        }
+    }
+
+    /// Returns the edit that undoes this one
+    pub fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::ModifyObject {
+                index,
+                before,
+                after,
+            } => Edit::ModifyObject {
+                index,
+                before: after,
+                after: before,
+            },
+            Edit::AddObject { index, object } => Edit::DeleteObject { index, object },
+            Edit::DeleteObject { index, object } => Edit::AddObject { index, object },
+            Edit::ModifyMaterial {
+                index,
+                before,
+                after,
+            } => Edit::ModifyMaterial {
+                index,
+                before: after,
+                after: before,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    undo_stack: Vec<Edit>,
+    redo_stack: Vec<Edit>,
+}
+
+impl History {
+    /// Records an edit that has already been applied, this clears everything that could be redone
+    pub fn record(&mut self, edit: Edit) {
+        self.undo_stack.push(edit);
+        self.redo_stack.clear();
+    }
+
+    /// Records an object that was changed bit by bit, like while dragging a gizmo, as a single edit.
+    /// Nothing is recorded if the object ended up the same as it started
+    pub fn record_object_change(&mut self, index: usize, before: Object, after: Object) {
+        if before != after {
+            self.record(Edit::ModifyObject {
+                index,
+                before,
+                after,
+            });
+        }
+    }
+
+    /// Returns the edit that needs to be applied to undo the last edit
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo_stack.pop()?;
+        let inverse = edit.inverse();
+        self.redo_stack.push(edit);
+        Some(inverse)
+    }
+
+    /// Returns the edit that needs to be applied to redo the last undone edit
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo_stack.pop()?;
+        self.undo_stack.push(edit.clone());
+        Some(edit)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::objects::HyperSphere;
+
+    fn hyper_sphere(x: f32) -> Object {
+        Object::HyperSphere(HyperSphere {
+            position: cgmath::vec4(x, 0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: 0,
+        })
+    }
+
+    /// Applies the edit and records it, the way the editor does
+    fn edit(history: &mut History, objects: &mut Vec<Object>, edit: Edit) {
+        edit.apply(objects, &mut []);
+        history.record(edit);
+    }
+
+    #[test]
+    fn undo_and_redo() {
+        let mut history = History::default();
+        let mut objects = vec![hyper_sphere(0.0)];
+        edit(
+            &mut history,
+            &mut objects,
+            Edit::AddObject {
+                index: 1,
+                object: hyper_sphere(1.0),
+            },
+        );
+        assert_eq!(objects, [hyper_sphere(0.0), hyper_sphere(1.0)]);
+
+        history.undo().unwrap().apply(&mut objects, &mut []);
+        assert_eq!(objects, [hyper_sphere(0.0)]);
+        assert!(!history.can_undo());
+        assert!(history.undo().is_none());
+
+        history.redo().unwrap().apply(&mut objects, &mut []);
+        assert_eq!(objects, [hyper_sphere(0.0), hyper_sphere(1.0)]);
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut history = History::default();
+        let mut objects = vec![hyper_sphere(0.0)];
+        edit(
+            &mut history,
+            &mut objects,
+            Edit::DeleteObject {
+                index: 0,
+                object: hyper_sphere(0.0),
+            },
+        );
+        history.undo().unwrap().apply(&mut objects, &mut []);
+        assert!(history.can_redo());
+
+        edit(
+            &mut history,
+            &mut objects,
+            Edit::ModifyObject {
+                index: 0,
+                before: hyper_sphere(0.0),
+                after: hyper_sphere(2.0),
+            },
+        );
+        assert!(!history.can_redo());
+        assert!(history.redo().is_none());
+        assert_eq!(objects, [hyper_sphere(2.0)]);
+    }
+
+    #[test]
+    fn inverse_round_trips() {
+        let objects = vec![hyper_sphere(0.0), hyper_sphere(1.0)];
+        let materials = vec![Material::diffuse(cgmath::vec3(0.1, 0.2, 0.3))];
+        let edits = [
+            Edit::ModifyObject {
+                index: 1,
+                before: hyper_sphere(1.0),
+                after: hyper_sphere(3.0),
+            },
+            Edit::AddObject {
+                index: 1,
+                object: hyper_sphere(2.0),
+            },
+            Edit::DeleteObject {
+                index: 0,
+                object: hyper_sphere(0.0),
+            },
+            Edit::ModifyMaterial {
+                index: 0,
+                before: materials[0].clone(),
+                after: Material::diffuse(cgmath::vec3(0.9, 0.8, 0.7)),
+            },
+        ];
+
+        for edit in edits {
+            let mut edited_objects = objects.clone();
+            let mut edited_materials = materials.clone();
+            edit.apply(&mut edited_objects, &mut edited_materials);
+            assert!(
+                edited_objects != objects || edited_materials != materials,
+                "{edit:?} didnt change anything"
+            );
+
+            // the inverse undoes the edit
+            let mut undone_objects = edited_objects.clone();
+            let mut undone_materials = edited_materials.clone();
+            edit.inverse()
+                .apply(&mut undone_objects, &mut undone_materials);
+            assert_eq!(undone_objects, objects, "{edit:?}");
+            assert_eq!(undone_materials, materials, "{edit:?}");
+
+            // and the inverse of the inverse does the same as the edit
+            edit.inverse()
+                .inverse()
+                .apply(&mut undone_objects, &mut undone_materials);
+            assert_eq!(undone_objects, edited_objects, "{edit:?}");
+            assert_eq!(undone_materials, edited_materials, "{edit:?}");
+        }
+    }
+
+    #[test]
+    fn gizmo_drag_is_one_undo_step() {
+        let mut history = History::default();
+        let mut objects = vec![hyper_sphere(0.0)];
+
+        // a drag moves the object a little every frame, and is recorded once the mouse is released
+        let drag_start = objects[0].clone();
+        for _ in 0..10 {
+            objects[0].move_position(cgmath::vec4(0.1, 0.0, 0.0, 0.0));
+        }
+        history.record_object_change(0, drag_start, objects[0].clone());
+
+        history.undo().unwrap().apply(&mut objects, &mut []);
+        assert_eq!(objects, [hyper_sphere(0.0)]);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn gizmo_click_isnt_recorded() {
+        let mut history = History::default();
+        let object = hyper_sphere(0.0);
+        history.record_object_change(0, object.clone(), object);
+        assert!(!history.can_undo());
+    }
+}
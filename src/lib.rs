@@ -1,8 +1,18 @@
+pub mod bvh;
 pub mod camera;
+pub mod cpu_renderer;
 pub mod gpu_buffers;
 mod gpu_types;
-pub mod objects;
+pub mod history;
+pub mod image;
 pub mod material;
 pub mod math;
+pub mod mesh;
+pub mod objects;
+pub mod offscreen;
+pub mod polytope;
 pub mod ray;
+pub mod scene;
+pub mod sdf;
 pub mod state;
+pub mod triangle_mesh;
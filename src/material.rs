@@ -1,4 +1,31 @@
-#[derive(Debug)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum MaterialKind {
+    #[default]
+    Diffuse,
+    /// Reflects rays, with a `roughness` of 0 being a perfect mirror
+    Metal { roughness: f32 },
+    /// Reflects and refracts rays like glass or water
+    Dielectric { index_of_refraction: f32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Material {
     pub color: cgmath::Vector3<f32>,
+    #[serde(default)]
+    pub kind: MaterialKind,
+    /// How much light the material gives off, as a multiple of `color`
+    #[serde(default)]
+    pub emission_strength: f32,
+}
+
+impl Material {
+    pub fn diffuse(color: cgmath::Vector3<f32>) -> Self {
+        Self {
+            color,
+            kind: MaterialKind::Diffuse,
+            emission_strength: 0.0,
+        }
+    }
 }
@@ -1,5 +1,9 @@
+mod bivector;
+mod pga;
 mod rotor;
 mod transform;
 
+pub use bivector::*;
+pub use pga::*;
 pub use rotor::*;
 pub use transform::*;
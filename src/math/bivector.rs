@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// How close the two angles of a bivector can be, compared to their sum, before they count as the same
+const ISOCLINIC_TOLERANCE: f32 = 0.001;
+
+/// An oriented area in 4D, each component is the part of it in the plane going from the first axis towards the second
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Bivector {
+    pub xy: f32,
+    pub xz: f32,
+    pub xw: f32,
+    pub yz: f32,
+    pub yw: f32,
+    pub zw: f32,
+}
+
+impl Bivector {
+    pub const ZERO: Self = Self {
+        xy: 0.0,
+        xz: 0.0,
+        xw: 0.0,
+        yz: 0.0,
+        yw: 0.0,
+        zw: 0.0,
+    };
+
+    /// The plane going from `a` towards `b`, with the area of the parallelogram they make
+    pub fn wedge(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) -> Self {
+        Self {
+            xy: a.x * b.y - a.y * b.x,
+            xz: a.x * b.z - a.z * b.x,
+            xw: a.x * b.w - a.w * b.x,
+            yz: a.y * b.z - a.z * b.y,
+            yw: a.y * b.w - a.w * b.y,
+            zw: a.z * b.w - a.w * b.z,
+        }
+    }
+
+    /// The bivector for the planes at right angles to this one, xy goes to zw and so on
+    pub fn dual(self) -> Self {
+        let Self {
+            xy,
+            xz,
+            xw,
+            yz,
+            yw,
+            zw,
+        } = self;
+        Self {
+            xy: zw,
+            xz: -yw,
+            xw: yz,
+            yz: xw,
+            yw: -xz,
+            zw: xy,
+        }
+    }
+
+    /// How fast `vector` moves when turning with this bivector as the angular velocity, which is at right angles to `vector`
+    pub fn velocity(self, vector: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        let Self {
+            xy,
+            xz,
+            xw,
+            yz,
+            yw,
+            zw,
+        } = self;
+        cgmath::Vector4 {
+            x: -xy * vector.y - xz * vector.z - xw * vector.w,
+            y: xy * vector.x - yz * vector.z - yw * vector.w,
+            z: xz * vector.x + yz * vector.y - zw * vector.w,
+            w: xw * vector.x + yw * vector.y + zw * vector.z,
+        }
+    }
+
+    /// Splits the bivector into two single planes at right angles to each other that add up to it, the first one being the biggest.
+    /// When both planes have the same size any pair of planes that works is returned
+    pub fn split(self) -> (Self, Self) {
+        let magnitude_squared = self.magnitude_squared();
+        let pfaffian = self.pfaffian();
+        // the sizes of the two planes are half the sum and half the difference of these
+        let sum = (magnitude_squared + 2.0 * pfaffian.abs()).max(0.0).sqrt();
+        let difference = (magnitude_squared - 2.0 * pfaffian.abs()).max(0.0).sqrt();
+        let first = if difference > sum * ISOCLINIC_TOLERANCE {
+            let first_size = (sum + difference) * 0.5;
+            (self * (first_size * first_size) - self.dual() * pfaffian) * (sum * difference).recip()
+        } else {
+            // every direction is turned in one of the planes, so the plane it turns through is one of them
+            let axis = cgmath::vec4(1.0, 0.0, 0.0, 0.0);
+            Self::wedge(axis, self.velocity(axis))
+        };
+        (first, self - first)
+    }
+
+    /// The xyzw part of the bivector wedged with itself halved, which is 0 when the bivector is a single plane
+    pub fn pfaffian(self) -> f32 {
+        self.xy * self.zw - self.xz * self.yw + self.xw * self.yz
+    }
+
+    pub fn magnitude_squared(self) -> f32 {
+        let Self {
+            xy,
+            xz,
+            xw,
+            yz,
+            yw,
+            zw,
+        } = self;
+        xy * xy + xz * xz + xw * xw + yz * yz + yw * yw + zw * zw
+    }
+
+    pub fn magnitude(self) -> f32 {
+        self.magnitude_squared().sqrt()
+    }
+
+    pub fn normalized(self) -> Self {
+        self * self.magnitude().recip()
+    }
+}
+
+impl Default for Bivector {
+    fn default() -> Self {
+        Self::ZERO
+    }
+}
+
+impl Add<Self> for Bivector {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            xy: self.xy + rhs.xy,
+            xz: self.xz + rhs.xz,
+            xw: self.xw + rhs.xw,
+            yz: self.yz + rhs.yz,
+            yw: self.yw + rhs.yw,
+            zw: self.zw + rhs.zw,
+        }
+    }
+}
+
+impl Sub<Self> for Bivector {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Neg for Bivector {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        self * -1.0
+    }
+}
+
+impl Mul<f32> for Bivector {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            xy: self.xy * rhs,
+            xz: self.xz * rhs,
+            xw: self.xw * rhs,
+            yz: self.yz * rhs,
+            yw: self.yw * rhs,
+            zw: self.zw * rhs,
+        }
+    }
+}
@@ -0,0 +1,1347 @@
+use crate::math::Transform;
+use cgmath::InnerSpace;
+use std::ops::{BitAnd, BitXor, Mul};
+
+// the elements of 4d projective geometric algebra that arent transforms, with e0 squaring to 0.
+// `^` is the wedge product which meets flats, and `&` is the regressive product which joins them
+
+/// A 3d flat in 4d, made of the points where `normal.dot(point) == distance`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hyperplane {
+    pub e0: f32,
+    pub e1: f32,
+    pub e2: f32,
+    pub e3: f32,
+    pub e4: f32,
+}
+
+/// A 2d flat in 4d, where two hyperplanes meet
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub e01: f32,
+    pub e02: f32,
+    pub e03: f32,
+    pub e04: f32,
+    pub e12: f32,
+    pub e13: f32,
+    pub e14: f32,
+    pub e23: f32,
+    pub e24: f32,
+    pub e34: f32,
+}
+
+/// A line in 4d, where three hyperplanes meet or going through two points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Line {
+    pub e012: f32,
+    pub e013: f32,
+    pub e014: f32,
+    pub e023: f32,
+    pub e024: f32,
+    pub e034: f32,
+    pub e123: f32,
+    pub e124: f32,
+    pub e134: f32,
+    pub e234: f32,
+}
+
+/// A point in 4d, with a weight of 0 for directions which are points infinitely far away
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    pub e0123: f32,
+    pub e0124: f32,
+    pub e0134: f32,
+    pub e0234: f32,
+    pub e1234: f32,
+}
+
+/// The odd parts of the algebra, so that hyperplanes and lines can be transformed
+#[derive(Debug, Clone, Copy)]
+struct Flector {
+    e0: f32,
+    e1: f32,
+    e2: f32,
+    e3: f32,
+    e4: f32,
+    e012: f32,
+    e013: f32,
+    e014: f32,
+    e023: f32,
+    e024: f32,
+    e034: f32,
+    e123: f32,
+    e124: f32,
+    e134: f32,
+    e234: f32,
+    e01234: f32,
+}
+
+const ZERO_FLECTOR: Flector = Flector {
+    e0: 0.0,
+    e1: 0.0,
+    e2: 0.0,
+    e3: 0.0,
+    e4: 0.0,
+    e012: 0.0,
+    e013: 0.0,
+    e014: 0.0,
+    e023: 0.0,
+    e024: 0.0,
+    e034: 0.0,
+    e123: 0.0,
+    e124: 0.0,
+    e134: 0.0,
+    e234: 0.0,
+    e01234: 0.0,
+};
+
+const ZERO_TRANSFORM: Transform = Transform {
+    s: 0.0,
+    e01: 0.0,
+    e02: 0.0,
+    e03: 0.0,
+    e04: 0.0,
+    e12: 0.0,
+    e13: 0.0,
+    e14: 0.0,
+    e23: 0.0,
+    e24: 0.0,
+    e34: 0.0,
+    e0123: 0.0,
+    e0124: 0.0,
+    e0134: 0.0,
+    e0234: 0.0,
+    e1234: 0.0,
+};
+
+impl Hyperplane {
+    pub fn new(normal: cgmath::Vector4<f32>, distance: f32) -> Self {
+        Self {
+            e0: distance,
+            e1: normal.x,
+            e2: -normal.y,
+            e3: normal.z,
+            e4: -normal.w,
+        }
+    }
+
+    pub fn normal(self) -> cgmath::Vector4<f32> {
+        cgmath::vec4(self.e1, -self.e2, self.e3, -self.e4)
+    }
+
+    /// How far along the normal the hyperplane is from the origin, in lengths of the normal
+    pub fn distance(self) -> f32 {
+        self.e0
+    }
+
+    /// How far `point` is in front of the hyperplane, negative when it is behind it
+    pub fn signed_distance(self, point: cgmath::Vector4<f32>) -> f32 {
+        let normal = self.normal();
+        (normal.dot(point) - self.distance()) / normal.magnitude()
+    }
+
+    pub fn normalized(self) -> Self {
+        self * self.normal().magnitude().recip()
+    }
+
+    pub fn transformed(self, transform: Transform) -> Self {
+        let Self { e0, e1, e2, e3, e4 } = self;
+        let flector = transform
+            * Flector {
+                e0,
+                e1,
+                e2,
+                e3,
+                e4,
+                ..ZERO_FLECTOR
+            }
+            * !transform;
+        Self {
+            e0: flector.e0,
+            e1: flector.e1,
+            e2: flector.e2,
+            e3: flector.e3,
+            e4: flector.e4,
+        }
+    }
+
+    /// The closest point on the hyperplane to `point`
+    pub fn project(self, point: Point) -> Point {
+        (self.perpendicular_through(point) ^ self).normalized()
+    }
+
+    /// The offset from the closest point on the hyperplane to `point`
+    pub fn reject(self, point: Point) -> cgmath::Vector4<f32> {
+        point.position() - self.project(point).position()
+    }
+
+    /// The line through `point` at right angles to the hyperplane
+    pub fn perpendicular_through(self, point: Point) -> Line {
+        let Self {
+            e1: b1,
+            e2: c1,
+            e3: d1,
+            e4: f1,
+            ..
+        } = self;
+        let Point {
+            e0123: a2,
+            e0124: b2,
+            e0134: c2,
+            e0234: d2,
+            e1234: f2,
+        } = point;
+        Line {
+            e012: -a2 * d1 + -b2 * f1,
+            e013: -c2 * f1 + a2 * c1,
+            e014: b2 * c1 + c2 * d1,
+            e023: -a2 * b1 + -d2 * f1,
+            e024: -b1 * b2 + d1 * d2,
+            e034: -b1 * c2 + -c1 * d2,
+            e123: -f1 * f2,
+            e124: d1 * f2,
+            e134: -c1 * f2,
+            e234: b1 * f2,
+        }
+    }
+}
+
+impl Plane {
+    /// Makes the size of the plane 1, so that projecting onto it doesnt scale things
+    pub fn normalized(self) -> Self {
+        let Self {
+            e12,
+            e13,
+            e14,
+            e23,
+            e24,
+            e34,
+            ..
+        } = self;
+        let magnitude =
+            (e12 * e12 + e13 * e13 + e14 * e14 + e23 * e23 + e24 * e24 + e34 * e34).sqrt();
+        self * magnitude.recip()
+    }
+
+    pub fn transformed(self, transform: Transform) -> Self {
+        let Self {
+            e01,
+            e02,
+            e03,
+            e04,
+            e12,
+            e13,
+            e14,
+            e23,
+            e24,
+            e34,
+        } = self;
+        let plane = Transform {
+            e01,
+            e02,
+            e03,
+            e04,
+            e12,
+            e13,
+            e14,
+            e23,
+            e24,
+            e34,
+            ..ZERO_TRANSFORM
+        };
+        let Transform {
+            e01,
+            e02,
+            e03,
+            e04,
+            e12,
+            e13,
+            e14,
+            e23,
+            e24,
+            e34,
+            ..
+        } = transform * plane * !transform;
+        Self {
+            e01,
+            e02,
+            e03,
+            e04,
+            e12,
+            e13,
+            e14,
+            e23,
+            e24,
+            e34,
+        }
+    }
+
+    /// The closest point on the plane to `point`
+    pub fn project(self, point: Point) -> Point {
+        (self.perpendicular_through(point) ^ self).normalized()
+    }
+
+    /// The offset from the closest point on the plane to `point`
+    pub fn reject(self, point: Point) -> cgmath::Vector4<f32> {
+        point.position() - self.project(point).position()
+    }
+
+    /// The plane through `point` at right angles to this one, which meets it in a single point
+    pub fn perpendicular_through(self, point: Point) -> Plane {
+        let Self {
+            e12: f1,
+            e13: g1,
+            e14: h1,
+            e23: i1,
+            e24: j1,
+            e34: k1,
+            ..
+        } = self;
+        let Point {
+            e0123: a2,
+            e0124: b2,
+            e0134: c2,
+            e0234: d2,
+            e1234: f2,
+        } = point;
+        Plane {
+            e01: -a2 * i1 + -b2 * j1 + -c2 * k1,
+            e02: -d2 * k1 + a2 * g1 + b2 * h1,
+            e03: -a2 * f1 + c2 * h1 + d2 * j1,
+            e04: -b2 * f1 + -c2 * g1 + -d2 * i1,
+            e12: -f2 * k1,
+            e13: f2 * j1,
+            e14: -f2 * i1,
+            e23: -f2 * h1,
+            e24: f2 * g1,
+            e34: -f1 * f2,
+        }
+    }
+}
+
+impl Line {
+    /// The line through `point` going along `direction`
+    pub fn new(point: cgmath::Vector4<f32>, direction: cgmath::Vector4<f32>) -> Self {
+        Point::new(point) & Point::ideal(direction)
+    }
+
+    /// Which way the line goes, from the first point to the second when it joins two points
+    pub fn direction(self) -> cgmath::Vector4<f32> {
+        cgmath::vec4(-self.e234, -self.e134, -self.e124, -self.e123)
+    }
+
+    pub fn normalized(self) -> Self {
+        self * self.direction().magnitude().recip()
+    }
+
+    pub fn transformed(self, transform: Transform) -> Self {
+        let Self {
+            e012,
+            e013,
+            e014,
+            e023,
+            e024,
+            e034,
+            e123,
+            e124,
+            e134,
+            e234,
+        } = self;
+        let flector = transform
+            * Flector {
+                e012,
+                e013,
+                e014,
+                e023,
+                e024,
+                e034,
+                e123,
+                e124,
+                e134,
+                e234,
+                ..ZERO_FLECTOR
+            }
+            * !transform;
+        Self {
+            e012: flector.e012,
+            e013: flector.e013,
+            e014: flector.e014,
+            e023: flector.e023,
+            e024: flector.e024,
+            e034: flector.e034,
+            e123: flector.e123,
+            e124: flector.e124,
+            e134: flector.e134,
+            e234: flector.e234,
+        }
+    }
+
+    /// The closest point on the line to `point`
+    pub fn project(self, point: Point) -> Point {
+        (self.perpendicular_through(point) ^ self).normalized()
+    }
+
+    /// The offset from the closest point on the line to `point`
+    pub fn reject(self, point: Point) -> cgmath::Vector4<f32> {
+        point.position() - self.project(point).position()
+    }
+
+    /// The hyperplane through `point` at right angles to the line
+    pub fn perpendicular_through(self, point: Point) -> Hyperplane {
+        let Self {
+            e123: h1,
+            e124: i1,
+            e134: j1,
+            e234: k1,
+            ..
+        } = self;
+        let Point {
+            e0123: a2,
+            e0124: b2,
+            e0134: c2,
+            e0234: d2,
+            e1234: f2,
+        } = point;
+        Hyperplane {
+            e0: a2 * h1 + b2 * i1 + c2 * j1 + d2 * k1,
+            e1: f2 * k1,
+            e2: -f2 * j1,
+            e3: f2 * i1,
+            e4: -f2 * h1,
+        }
+    }
+}
+
+impl Point {
+    pub const ORIGIN: Self = Self {
+        e0123: 0.0,
+        e0124: 0.0,
+        e0134: 0.0,
+        e0234: 0.0,
+        e1234: 1.0,
+    };
+
+    pub fn new(position: cgmath::Vector4<f32>) -> Self {
+        Self {
+            e0123: position.w,
+            e0124: position.z,
+            e0134: position.y,
+            e0234: position.x,
+            e1234: 1.0,
+        }
+    }
+
+    /// The point infinitely far away along `direction`
+    pub fn ideal(direction: cgmath::Vector4<f32>) -> Self {
+        Self {
+            e1234: 0.0,
+            ..Self::new(direction)
+        }
+    }
+
+    /// How much of the point there is, which is 0 for ideal points
+    pub fn weight(self) -> f32 {
+        self.e1234
+    }
+
+    /// Where the point is, ideal points dont have a position so this is infinite for them
+    pub fn position(self) -> cgmath::Vector4<f32> {
+        cgmath::vec4(self.e0234, self.e0134, self.e0124, self.e0123) / self.weight()
+    }
+
+    /// Makes the weight 1, which flips points with a negative weight
+    pub fn normalized(self) -> Self {
+        self * self.weight().recip()
+    }
+
+    pub fn transformed(self, transform: Transform) -> Self {
+        let Self {
+            e0123,
+            e0124,
+            e0134,
+            e0234,
+            e1234,
+        } = self;
+        let point = Transform {
+            e0123,
+            e0124,
+            e0134,
+            e0234,
+            e1234,
+            ..ZERO_TRANSFORM
+        };
+        let Transform {
+            e0123,
+            e0124,
+            e0134,
+            e0234,
+            e1234,
+            ..
+        } = transform * point * !transform;
+        Self {
+            e0123,
+            e0124,
+            e0134,
+            e0234,
+            e1234,
+        }
+    }
+}
+
+impl Mul<f32> for Hyperplane {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            e0: self.e0 * rhs,
+            e1: self.e1 * rhs,
+            e2: self.e2 * rhs,
+            e3: self.e3 * rhs,
+            e4: self.e4 * rhs,
+        }
+    }
+}
+
+impl Mul<f32> for Plane {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            e01: self.e01 * rhs,
+            e02: self.e02 * rhs,
+            e03: self.e03 * rhs,
+            e04: self.e04 * rhs,
+            e12: self.e12 * rhs,
+            e13: self.e13 * rhs,
+            e14: self.e14 * rhs,
+            e23: self.e23 * rhs,
+            e24: self.e24 * rhs,
+            e34: self.e34 * rhs,
+        }
+    }
+}
+
+impl Mul<f32> for Line {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            e012: self.e012 * rhs,
+            e013: self.e013 * rhs,
+            e014: self.e014 * rhs,
+            e023: self.e023 * rhs,
+            e024: self.e024 * rhs,
+            e034: self.e034 * rhs,
+            e123: self.e123 * rhs,
+            e124: self.e124 * rhs,
+            e134: self.e134 * rhs,
+            e234: self.e234 * rhs,
+        }
+    }
+}
+
+impl Mul<f32> for Point {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self {
+            e0123: self.e0123 * rhs,
+            e0124: self.e0124 * rhs,
+            e0134: self.e0134 * rhs,
+            e0234: self.e0234 * rhs,
+            e1234: self.e1234 * rhs,
+        }
+    }
+}
+
+// meeting flats
+
+impl BitXor<Hyperplane> for Hyperplane {
+    type Output = Plane;
+
+    fn bitxor(self, rhs: Hyperplane) -> Self::Output {
+        let Self {
+            e0: a1,
+            e1: b1,
+            e2: c1,
+            e3: d1,
+            e4: f1,
+        } = self;
+        let Hyperplane {
+            e0: a2,
+            e1: b2,
+            e2: c2,
+            e3: d2,
+            e4: f2,
+        } = rhs;
+        Plane {
+            e01: -a2 * b1 + a1 * b2,
+            e02: -a2 * c1 + a1 * c2,
+            e03: -a2 * d1 + a1 * d2,
+            e04: -a2 * f1 + a1 * f2,
+            e12: -b2 * c1 + b1 * c2,
+            e13: -b2 * d1 + b1 * d2,
+            e14: -b2 * f1 + b1 * f2,
+            e23: -c2 * d1 + c1 * d2,
+            e24: -c2 * f1 + c1 * f2,
+            e34: -d2 * f1 + d1 * f2,
+        }
+    }
+}
+
+impl BitXor<Plane> for Hyperplane {
+    type Output = Line;
+
+    fn bitxor(self, rhs: Plane) -> Self::Output {
+        let Self {
+            e0: a1,
+            e1: b1,
+            e2: c1,
+            e3: d1,
+            e4: f1,
+        } = self;
+        let Plane {
+            e01: a2,
+            e02: b2,
+            e03: c2,
+            e04: d2,
+            e12: f2,
+            e13: g2,
+            e14: h2,
+            e23: i2,
+            e24: j2,
+            e34: k2,
+        } = rhs;
+        Line {
+            e012: -b1 * b2 + a1 * f2 + a2 * c1,
+            e013: -b1 * c2 + a1 * g2 + a2 * d1,
+            e014: -b1 * d2 + a1 * h2 + a2 * f1,
+            e023: -c1 * c2 + a1 * i2 + b2 * d1,
+            e024: -c1 * d2 + a1 * j2 + b2 * f1,
+            e034: -d1 * d2 + a1 * k2 + c2 * f1,
+            e123: -c1 * g2 + b1 * i2 + d1 * f2,
+            e124: -c1 * h2 + b1 * j2 + f1 * f2,
+            e134: -d1 * h2 + b1 * k2 + f1 * g2,
+            e234: -d1 * j2 + c1 * k2 + f1 * i2,
+        }
+    }
+}
+
+impl BitXor<Hyperplane> for Plane {
+    type Output = Line;
+
+    fn bitxor(self, rhs: Hyperplane) -> Self::Output {
+        let Self {
+            e01: a1,
+            e02: b1,
+            e03: c1,
+            e04: d1,
+            e12: f1,
+            e13: g1,
+            e14: h1,
+            e23: i1,
+            e24: j1,
+            e34: k1,
+        } = self;
+        let Hyperplane {
+            e0: a2,
+            e1: b2,
+            e2: c2,
+            e3: d2,
+            e4: f2,
+        } = rhs;
+        Line {
+            e012: -b1 * b2 + a1 * c2 + a2 * f1,
+            e013: -b2 * c1 + a1 * d2 + a2 * g1,
+            e014: -b2 * d1 + a1 * f2 + a2 * h1,
+            e023: -c1 * c2 + a2 * i1 + b1 * d2,
+            e024: -c2 * d1 + a2 * j1 + b1 * f2,
+            e034: -d1 * d2 + a2 * k1 + c1 * f2,
+            e123: -c2 * g1 + b2 * i1 + d2 * f1,
+            e124: -c2 * h1 + b2 * j1 + f1 * f2,
+            e134: -d2 * h1 + b2 * k1 + f2 * g1,
+            e234: -d2 * j1 + c2 * k1 + f2 * i1,
+        }
+    }
+}
+
+impl BitXor<Line> for Hyperplane {
+    type Output = Point;
+
+    fn bitxor(self, rhs: Line) -> Self::Output {
+        let Self {
+            e0: a1,
+            e1: b1,
+            e2: c1,
+            e3: d1,
+            e4: f1,
+        } = self;
+        let Line {
+            e012: a2,
+            e013: b2,
+            e014: c2,
+            e023: d2,
+            e024: f2,
+            e034: g2,
+            e123: h2,
+            e124: i2,
+            e134: j2,
+            e234: k2,
+        } = rhs;
+        Point {
+            e0123: -a2 * d1 + -b1 * d2 + a1 * h2 + b2 * c1,
+            e0124: -a2 * f1 + -b1 * f2 + a1 * i2 + c1 * c2,
+            e0134: -b1 * g2 + -b2 * f1 + a1 * j2 + c2 * d1,
+            e0234: -c1 * g2 + -d2 * f1 + a1 * k2 + d1 * f2,
+            e1234: -c1 * j2 + -f1 * h2 + b1 * k2 + d1 * i2,
+        }
+    }
+}
+
+impl BitXor<Hyperplane> for Line {
+    type Output = Point;
+
+    fn bitxor(self, rhs: Hyperplane) -> Self::Output {
+        let Self {
+            e012: a1,
+            e013: b1,
+            e014: c1,
+            e023: d1,
+            e024: f1,
+            e034: g1,
+            e123: h1,
+            e124: i1,
+            e134: j1,
+            e234: k1,
+        } = self;
+        let Hyperplane {
+            e0: a2,
+            e1: b2,
+            e2: c2,
+            e3: d2,
+            e4: f2,
+        } = rhs;
+        Point {
+            e0123: -a2 * h1 + -b1 * c2 + a1 * d2 + b2 * d1,
+            e0124: -a2 * i1 + -c1 * c2 + a1 * f2 + b2 * f1,
+            e0134: -a2 * j1 + -c1 * d2 + b1 * f2 + b2 * g1,
+            e0234: -a2 * k1 + -d2 * f1 + c2 * g1 + d1 * f2,
+            e1234: -b2 * k1 + -d2 * i1 + c2 * j1 + f2 * h1,
+        }
+    }
+}
+
+impl BitXor<Plane> for Plane {
+    type Output = Point;
+
+    fn bitxor(self, rhs: Plane) -> Self::Output {
+        let Self {
+            e01: a1,
+            e02: b1,
+            e03: c1,
+            e04: d1,
+            e12: f1,
+            e13: g1,
+            e14: h1,
+            e23: i1,
+            e24: j1,
+            e34: k1,
+        } = self;
+        let Plane {
+            e01: a2,
+            e02: b2,
+            e03: c2,
+            e04: d2,
+            e12: f2,
+            e13: g2,
+            e14: h2,
+            e23: i2,
+            e24: j2,
+            e34: k2,
+        } = rhs;
+        Point {
+            e0123: -b1 * g2 + -b2 * g1 + a1 * i2 + a2 * i1 + c1 * f2 + c2 * f1,
+            e0124: -b1 * h2 + -b2 * h1 + a1 * j2 + a2 * j1 + d1 * f2 + d2 * f1,
+            e0134: -c1 * h2 + -c2 * h1 + a1 * k2 + a2 * k1 + d1 * g2 + d2 * g1,
+            e0234: -c1 * j2 + -c2 * j1 + b1 * k2 + b2 * k1 + d1 * i2 + d2 * i1,
+            e1234: -g1 * j2 + -g2 * j1 + f1 * k2 + f2 * k1 + h1 * i2 + h2 * i1,
+        }
+    }
+}
+
+// joining flats
+
+impl BitAnd<Point> for Point {
+    type Output = Line;
+
+    fn bitand(self, rhs: Point) -> Self::Output {
+        let Self {
+            e0123: a1,
+            e0124: b1,
+            e0134: c1,
+            e0234: d1,
+            e1234: f1,
+        } = self;
+        let Point {
+            e0123: a2,
+            e0124: b2,
+            e0134: c2,
+            e0234: d2,
+            e1234: f2,
+        } = rhs;
+        Line {
+            e012: -a2 * b1 + a1 * b2,
+            e013: -a2 * c1 + a1 * c2,
+            e014: -b2 * c1 + b1 * c2,
+            e023: -a2 * d1 + a1 * d2,
+            e024: -b2 * d1 + b1 * d2,
+            e034: -c2 * d1 + c1 * d2,
+            e123: -a2 * f1 + a1 * f2,
+            e124: -b2 * f1 + b1 * f2,
+            e134: -c2 * f1 + c1 * f2,
+            e234: -d2 * f1 + d1 * f2,
+        }
+    }
+}
+
+impl BitAnd<Line> for Point {
+    type Output = Plane;
+
+    fn bitand(self, rhs: Line) -> Self::Output {
+        let Self {
+            e0123: a1,
+            e0124: b1,
+            e0134: c1,
+            e0234: d1,
+            e1234: f1,
+        } = self;
+        let Line {
+            e012: a2,
+            e013: b2,
+            e014: c2,
+            e023: d2,
+            e024: f2,
+            e034: g2,
+            e123: h2,
+            e124: i2,
+            e134: j2,
+            e234: k2,
+        } = rhs;
+        Plane {
+            e01: -b1 * b2 + a1 * c2 + a2 * c1,
+            e02: -b1 * d2 + a1 * f2 + a2 * d1,
+            e03: -c1 * d2 + a1 * g2 + b2 * d1,
+            e04: -c1 * f2 + b1 * g2 + c2 * d1,
+            e12: -b1 * h2 + a1 * i2 + a2 * f1,
+            e13: -c1 * h2 + a1 * j2 + b2 * f1,
+            e14: -c1 * i2 + b1 * j2 + c2 * f1,
+            e23: -d1 * h2 + a1 * k2 + d2 * f1,
+            e24: -d1 * i2 + b1 * k2 + f1 * f2,
+            e34: -d1 * j2 + c1 * k2 + f1 * g2,
+        }
+    }
+}
+
+impl BitAnd<Point> for Line {
+    type Output = Plane;
+
+    fn bitand(self, rhs: Point) -> Self::Output {
+        let Self {
+            e012: a1,
+            e013: b1,
+            e014: c1,
+            e023: d1,
+            e024: f1,
+            e034: g1,
+            e123: h1,
+            e124: i1,
+            e134: j1,
+            e234: k1,
+        } = self;
+        let Point {
+            e0123: a2,
+            e0124: b2,
+            e0134: c2,
+            e0234: d2,
+            e1234: f2,
+        } = rhs;
+        Plane {
+            e01: -b1 * b2 + a1 * c2 + a2 * c1,
+            e02: -b2 * d1 + a1 * d2 + a2 * f1,
+            e03: -c2 * d1 + a2 * g1 + b1 * d2,
+            e04: -c2 * f1 + b2 * g1 + c1 * d2,
+            e12: -b2 * h1 + a1 * f2 + a2 * i1,
+            e13: -c2 * h1 + a2 * j1 + b1 * f2,
+            e14: -c2 * i1 + b2 * j1 + c1 * f2,
+            e23: -d2 * h1 + a2 * k1 + d1 * f2,
+            e24: -d2 * i1 + b2 * k1 + f1 * f2,
+            e34: -d2 * j1 + c2 * k1 + f2 * g1,
+        }
+    }
+}
+
+impl BitAnd<Plane> for Point {
+    type Output = Hyperplane;
+
+    fn bitand(self, rhs: Plane) -> Self::Output {
+        let Self {
+            e0123: a1,
+            e0124: b1,
+            e0134: c1,
+            e0234: d1,
+            e1234: f1,
+        } = self;
+        let Plane {
+            e01: a2,
+            e02: b2,
+            e03: c2,
+            e04: d2,
+            e12: f2,
+            e13: g2,
+            e14: h2,
+            e23: i2,
+            e24: j2,
+            e34: k2,
+        } = rhs;
+        Hyperplane {
+            e0: -a2 * d1 + -b1 * c2 + a1 * d2 + b2 * c1,
+            e1: -a2 * f1 + -b1 * g2 + a1 * h2 + c1 * f2,
+            e2: -b1 * i2 + -b2 * f1 + a1 * j2 + d1 * f2,
+            e3: -c1 * i2 + -c2 * f1 + a1 * k2 + d1 * g2,
+            e4: -c1 * j2 + -d2 * f1 + b1 * k2 + d1 * h2,
+        }
+    }
+}
+
+impl BitAnd<Point> for Plane {
+    type Output = Hyperplane;
+
+    fn bitand(self, rhs: Point) -> Self::Output {
+        let Self {
+            e01: a1,
+            e02: b1,
+            e03: c1,
+            e04: d1,
+            e12: f1,
+            e13: g1,
+            e14: h1,
+            e23: i1,
+            e24: j1,
+            e34: k1,
+        } = self;
+        let Point {
+            e0123: a2,
+            e0124: b2,
+            e0134: c2,
+            e0234: d2,
+            e1234: f2,
+        } = rhs;
+        Hyperplane {
+            e0: -a2 * d1 + -b1 * c2 + a1 * d2 + b2 * c1,
+            e1: -a2 * h1 + -c2 * f1 + a1 * f2 + b2 * g1,
+            e2: -a2 * j1 + -d2 * f1 + b1 * f2 + b2 * i1,
+            e3: -a2 * k1 + -d2 * g1 + c1 * f2 + c2 * i1,
+            e4: -b2 * k1 + -d2 * h1 + c2 * j1 + d1 * f2,
+        }
+    }
+}
+
+impl BitAnd<Line> for Line {
+    type Output = Hyperplane;
+
+    fn bitand(self, rhs: Line) -> Self::Output {
+        let Self {
+            e012: a1,
+            e013: b1,
+            e014: c1,
+            e023: d1,
+            e024: f1,
+            e034: g1,
+            e123: h1,
+            e124: i1,
+            e134: j1,
+            e234: k1,
+        } = self;
+        let Line {
+            e012: a2,
+            e013: b2,
+            e014: c2,
+            e023: d2,
+            e024: f2,
+            e034: g2,
+            e123: h2,
+            e124: i2,
+            e134: j2,
+            e234: k2,
+        } = rhs;
+        Hyperplane {
+            e0: -b1 * f2 + -b2 * f1 + a1 * g2 + a2 * g1 + c1 * d2 + c2 * d1,
+            e1: -b1 * i2 + -b2 * i1 + a1 * j2 + a2 * j1 + c1 * h2 + c2 * h1,
+            e2: -d1 * i2 + -d2 * i1 + a1 * k2 + a2 * k1 + f1 * h2 + f2 * h1,
+            e3: -d1 * j2 + -d2 * j1 + b1 * k2 + b2 * k1 + g1 * h2 + g2 * h1,
+            e4: -f1 * j2 + -f2 * j1 + c1 * k2 + c2 * k1 + g1 * i2 + g2 * i1,
+        }
+    }
+}
+
+impl Mul<Flector> for Transform {
+    type Output = Flector;
+
+    fn mul(self, rhs: Flector) -> Self::Output {
+        let Self {
+            s: a1,
+            e01: b1,
+            e02: c1,
+            e03: d1,
+            e04: f1,
+            e12: g1,
+            e13: h1,
+            e14: i1,
+            e23: j1,
+            e24: k1,
+            e34: l1,
+            e0123: m1,
+            e0124: n1,
+            e0134: o1,
+            e0234: p1,
+            e1234: q1,
+        } = self;
+        let Flector {
+            e0: a2,
+            e1: b2,
+            e2: c2,
+            e3: d2,
+            e4: f2,
+            e012: g2,
+            e013: h2,
+            e014: i2,
+            e023: j2,
+            e024: k2,
+            e034: l2,
+            e123: m2,
+            e124: n2,
+            e134: o2,
+            e234: p2,
+            e01234: q2,
+        } = rhs;
+        Flector {
+            e0: -g1 * g2
+                + -h1 * h2
+                + -i1 * i2
+                + -j1 * j2
+                + -k1 * k2
+                + -l1 * l2
+                + -m1 * m2
+                + -n1 * n2
+                + -o1 * o2
+                + -p1 * p2
+                + a1 * a2
+                + b1 * b2
+                + c1 * c2
+                + d1 * d2
+                + f1 * f2
+                + q1 * q2,
+            e1: -j1 * m2 + -k1 * n2 + -l1 * o2 + -p2 * q1 + a1 * b2 + c2 * g1 + d2 * h1 + f2 * i1,
+            e2: -b2 * g1 + -l1 * p2 + a1 * c2 + d2 * j1 + f2 * k1 + h1 * m2 + i1 * n2 + o2 * q1,
+            e3: -b2 * h1 + -c2 * j1 + -g1 * m2 + -n2 * q1 + a1 * d2 + f2 * l1 + i1 * o2 + k1 * p2,
+            e4: -b2 * i1 + -c2 * k1 + -d2 * l1 + -g1 * n2 + -h1 * o2 + -j1 * p2 + a1 * f2 + m2 * q1,
+            e012: -b2 * c1
+                + -h1 * j2
+                + -i1 * k2
+                + -l1 * q2
+                + -l2 * q1
+                + -o1 * p2
+                + a1 * g2
+                + a2 * g1
+                + b1 * c2
+                + d1 * m2
+                + d2 * m1
+                + f1 * n2
+                + f2 * n1
+                + h2 * j1
+                + i2 * k1
+                + o2 * p1,
+            e013: -b2 * d1
+                + -c1 * m2
+                + -c2 * m1
+                + -g2 * j1
+                + -i1 * l2
+                + -n2 * p1
+                + a1 * h2
+                + a2 * h1
+                + b1 * d2
+                + f1 * o2
+                + f2 * o1
+                + g1 * j2
+                + i2 * l1
+                + k1 * q2
+                + k2 * q1
+                + n1 * p2,
+            e014: -b2 * f1
+                + -c1 * n2
+                + -c2 * n1
+                + -d1 * o2
+                + -d2 * o1
+                + -g2 * k1
+                + -h2 * l1
+                + -j1 * q2
+                + -j2 * q1
+                + -m1 * p2
+                + a1 * i2
+                + a2 * i1
+                + b1 * f2
+                + g1 * k2
+                + h1 * l2
+                + m2 * p1,
+            e023: -c2 * d1
+                + -g1 * h2
+                + -i1 * q2
+                + -i2 * q1
+                + -k1 * l2
+                + -n1 * o2
+                + a1 * j2
+                + a2 * j1
+                + b1 * m2
+                + b2 * m1
+                + c1 * d2
+                + f1 * p2
+                + f2 * p1
+                + g2 * h1
+                + k2 * l1
+                + n2 * o1,
+            e024: -c2 * f1
+                + -d1 * p2
+                + -d2 * p1
+                + -g1 * i2
+                + -j2 * l1
+                + -m2 * o1
+                + a1 * k2
+                + a2 * k1
+                + b1 * n2
+                + b2 * n1
+                + c1 * f2
+                + g2 * i1
+                + h1 * q2
+                + h2 * q1
+                + j1 * l2
+                + m1 * o2,
+            e034: -d2 * f1
+                + -g1 * q2
+                + -g2 * q1
+                + -h1 * i2
+                + -j1 * k2
+                + -m1 * n2
+                + a1 * l2
+                + a2 * l1
+                + b1 * o2
+                + b2 * o1
+                + c1 * p2
+                + c2 * p1
+                + d1 * f2
+                + h2 * i1
+                + j2 * k1
+                + m2 * n1,
+            e123: -c2 * h1 + -k1 * o2 + a1 * m2 + b2 * j1 + d2 * g1 + f2 * q1 + i1 * p2 + l1 * n2,
+            e124: -c2 * i1 + -d2 * q1 + -h1 * p2 + -l1 * m2 + a1 * n2 + b2 * k1 + f2 * g1 + j1 * o2,
+            e134: -d2 * i1 + -j1 * n2 + a1 * o2 + b2 * l1 + c2 * q1 + f2 * h1 + g1 * p2 + k1 * m2,
+            e234: -b2 * q1 + -d2 * k1 + -g1 * o2 + -i1 * m2 + a1 * p2 + c2 * l1 + f2 * j1 + h1 * n2,
+            e01234: -b2 * p1
+                + -c1 * o2
+                + -d2 * n1
+                + -f1 * m2
+                + -h1 * k2
+                + -h2 * k1
+                + a1 * q2
+                + a2 * q1
+                + b1 * p2
+                + c2 * o1
+                + d1 * n2
+                + f2 * m1
+                + g1 * l2
+                + g2 * l1
+                + i1 * j2
+                + i2 * j1,
+        }
+    }
+}
+
+impl Mul<Transform> for Flector {
+    type Output = Flector;
+
+    fn mul(self, rhs: Transform) -> Self::Output {
+        let Self {
+            e0: a1,
+            e1: b1,
+            e2: c1,
+            e3: d1,
+            e4: f1,
+            e012: g1,
+            e013: h1,
+            e014: i1,
+            e023: j1,
+            e024: k1,
+            e034: l1,
+            e123: m1,
+            e124: n1,
+            e134: o1,
+            e234: p1,
+            e01234: q1,
+        } = self;
+        let Transform {
+            s: a2,
+            e01: b2,
+            e02: c2,
+            e03: d2,
+            e04: f2,
+            e12: g2,
+            e13: h2,
+            e14: i2,
+            e23: j2,
+            e24: k2,
+            e34: l2,
+            e0123: m2,
+            e0124: n2,
+            e0134: o2,
+            e0234: p2,
+            e1234: q2,
+        } = rhs;
+        Flector {
+            e0: -b1 * b2
+                + -c1 * c2
+                + -d1 * d2
+                + -f1 * f2
+                + -g1 * g2
+                + -h1 * h2
+                + -i1 * i2
+                + -j1 * j2
+                + -k1 * k2
+                + -l1 * l2
+                + a1 * a2
+                + m1 * m2
+                + n1 * n2
+                + o1 * o2
+                + p1 * p2
+                + q1 * q2,
+            e1: -c1 * g2 + -d1 * h2 + -f1 * i2 + -j2 * m1 + -k2 * n1 + -l2 * o1 + a2 * b1 + p1 * q2,
+            e2: -d1 * j2 + -f1 * k2 + -l2 * p1 + -o1 * q2 + a2 * c1 + b1 * g2 + h2 * m1 + i2 * n1,
+            e3: -f1 * l2 + -g2 * m1 + a2 * d1 + b1 * h2 + c1 * j2 + i2 * o1 + k2 * p1 + n1 * q2,
+            e4: -g2 * n1 + -h2 * o1 + -j2 * p1 + -m1 * q2 + a2 * f1 + b1 * i2 + c1 * k2 + d1 * l2,
+            e012: -b1 * c2
+                + -d1 * m2
+                + -d2 * m1
+                + -f1 * n2
+                + -f2 * n1
+                + -h1 * j2
+                + -i1 * k2
+                + -l1 * q2
+                + -l2 * q1
+                + -o2 * p1
+                + a1 * g2
+                + a2 * g1
+                + b2 * c1
+                + h2 * j1
+                + i2 * k1
+                + o1 * p2,
+            e013: -b1 * d2
+                + -f1 * o2
+                + -f2 * o1
+                + -g2 * j1
+                + -i1 * l2
+                + -n1 * p2
+                + a1 * h2
+                + a2 * h1
+                + b2 * d1
+                + c1 * m2
+                + c2 * m1
+                + g1 * j2
+                + i2 * l1
+                + k1 * q2
+                + k2 * q1
+                + n2 * p1,
+            e014: -b1 * f2
+                + -g2 * k1
+                + -h2 * l1
+                + -j1 * q2
+                + -j2 * q1
+                + -m2 * p1
+                + a1 * i2
+                + a2 * i1
+                + b2 * f1
+                + c1 * n2
+                + c2 * n1
+                + d1 * o2
+                + d2 * o1
+                + g1 * k2
+                + h1 * l2
+                + m1 * p2,
+            e023: -b1 * m2
+                + -b2 * m1
+                + -c1 * d2
+                + -f1 * p2
+                + -f2 * p1
+                + -g1 * h2
+                + -i1 * q2
+                + -i2 * q1
+                + -k1 * l2
+                + -n2 * o1
+                + a1 * j2
+                + a2 * j1
+                + c2 * d1
+                + g2 * h1
+                + k2 * l1
+                + n1 * o2,
+            e024: -b1 * n2
+                + -b2 * n1
+                + -c1 * f2
+                + -g1 * i2
+                + -j2 * l1
+                + -m1 * o2
+                + a1 * k2
+                + a2 * k1
+                + c2 * f1
+                + d1 * p2
+                + d2 * p1
+                + g2 * i1
+                + h1 * q2
+                + h2 * q1
+                + j1 * l2
+                + m2 * o1,
+            e034: -b1 * o2
+                + -b2 * o1
+                + -c1 * p2
+                + -c2 * p1
+                + -d1 * f2
+                + -g1 * q2
+                + -g2 * q1
+                + -h1 * i2
+                + -j1 * k2
+                + -m2 * n1
+                + a1 * l2
+                + a2 * l1
+                + d2 * f1
+                + h2 * i1
+                + j2 * k1
+                + m1 * n2,
+            e123: -c1 * h2 + -f1 * q2 + -i2 * p1 + -l2 * n1 + a2 * m1 + b1 * j2 + d1 * g2 + k2 * o1,
+            e124: -c1 * i2 + -j2 * o1 + a2 * n1 + b1 * k2 + d1 * q2 + f1 * g2 + h2 * p1 + l2 * m1,
+            e134: -c1 * q2 + -d1 * i2 + -g2 * p1 + -k2 * m1 + a2 * o1 + b1 * l2 + f1 * h2 + j2 * n1,
+            e234: -d1 * k2 + -h2 * n1 + a2 * p1 + b1 * q2 + c1 * l2 + f1 * j2 + g2 * o1 + i2 * m1,
+            e01234: -b1 * p2
+                + -c2 * o1
+                + -d1 * n2
+                + -f2 * m1
+                + -h1 * k2
+                + -h2 * k1
+                + a1 * q2
+                + a2 * q1
+                + b2 * p1
+                + c1 * o2
+                + d2 * n1
+                + f1 * m2
+                + g1 * l2
+                + g2 * l1
+                + i1 * j2
+                + i2 * j1,
+        }
+    }
+}
@@ -1,7 +1,36 @@
+use crate::math::Bivector;
+use cgmath::{InnerSpace, Zero};
 use encase::ShaderType;
-use std::ops::{Mul, Not};
+use serde::{Deserialize, Serialize};
+use std::ops::{Mul, Neg, Not};
 
-#[derive(Debug, Clone, Copy, ShaderType)]
+/// How close to parallel two directions can be before there isnt a plane between them
+const PARALLEL_TOLERANCE: f32 = 0.000001;
+
+/// How close two angles of a rotation have to be to count as the same when finding its `RotationKind`
+const ANGLE_TOLERANCE: f32 = 0.0001;
+
+/// The kinds of rotation there are in 4D, based on the angles in the two planes a rotation can be split into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationKind {
+    /// A rotation in one plane, which leaves the plane at right angles to it where it is (or no rotation at all)
+    Simple,
+    /// A rotation by the same angle in two planes, which turns every direction by that angle
+    Isoclinic,
+    /// A rotation by different angles in two planes
+    General,
+}
+
+/// A rotation in a single plane, as one of the two parts of a rotor from `Rotor::decompose`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SimpleRotation {
+    pub rotor: Rotor,
+    /// The unit bivector for the plane, which is zero when the angle is
+    pub plane: Bivector,
+    pub angle: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ShaderType, Serialize, Deserialize)]
 pub struct Rotor {
     pub s: f32,
     pub e12: f32,
@@ -79,6 +108,149 @@ impl Rotor {
         }
     }
 
+    /// The rotation in the plane of `from` and `to` that turns the direction of `from` into the direction of `to`
+    pub fn rotation_between(from: cgmath::Vector4<f32>, to: cgmath::Vector4<f32>) -> Self {
+        let (from, to) = (from.normalize(), to.normalize());
+        let plane = Bivector::wedge(from, to);
+        let sin = plane.magnitude();
+        let cos = from.dot(to);
+        if sin > PARALLEL_TOLERANCE {
+            Self::exp(plane * (sin.atan2(cos) / sin))
+        } else if cos > 0.0 {
+            Self::IDENTITY
+        } else {
+            // any half turn in a plane with `from` in it works, so use the axis furthest from `from` for the other direction
+            let axis = (0..4)
+                .min_by(|&a, &b| from[a].abs().total_cmp(&from[b].abs()))
+                .expect("there should be 4 axes");
+            let mut other = cgmath::Vector4::zero();
+            other[axis] = 1.0;
+            Self::rotation_in_plane(from, other, std::f32::consts::PI)
+        }
+    }
+
+    /// Rotates by `angle` in the plane of `a` and `b`, from `a` towards `b`, which shouldnt be parallel
+    pub fn rotation_in_plane(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>, angle: f32) -> Self {
+        Self::exp(Bivector::wedge(a, b).normalized() * angle)
+    }
+
+    /// Rotates by the size of the bivector in its plane, so `exp(Bivector { xy: angle, ..Bivector::ZERO })` is `rotation_xy(angle)`.
+    /// A bivector that isnt a single plane rotates two planes at right angles to each other at once
+    pub fn exp(bivector: Bivector) -> Self {
+        // the bivector is split into a part equal to its dual and a part equal to minus its dual,
+        // which each rotate two planes by the same angle and dont change each other
+        let dual = bivector.dual();
+        let magnitude_squared = bivector.magnitude_squared();
+        let pfaffian = bivector.pfaffian();
+        let self_dual_angle = (magnitude_squared + 2.0 * pfaffian).max(0.0).sqrt();
+        let anti_self_dual_angle = (magnitude_squared - 2.0 * pfaffian).max(0.0).sqrt();
+        let self_dual_cos = (self_dual_angle * 0.5).cos();
+        let anti_self_dual_cos = (anti_self_dual_angle * 0.5).cos();
+        Self::from_parts(
+            (self_dual_cos + anti_self_dual_cos) * 0.5,
+            (bivector + dual) * (half_angle_sinc(self_dual_angle) * 0.5)
+                + (bivector - dual) * (half_angle_sinc(anti_self_dual_angle) * 0.5),
+            (anti_self_dual_cos - self_dual_cos) * 0.5,
+        )
+    }
+
+    /// The bivector that `exp` turns back into this rotor, with the angles in each plane between 0 and 2 pi
+    pub fn log(self) -> Bivector {
+        let (s, bivector, e1234) = self.normalized().parts();
+        let dual = bivector.dual();
+        // the magnitudes of the self dual and anti self dual parts are the sines of the half angles over root 2
+        let part = |part: Bivector, cos: f32| {
+            let magnitude = part.magnitude();
+            if magnitude == 0.0 {
+                Bivector::ZERO
+            } else {
+                let sin = magnitude * std::f32::consts::SQRT_2;
+                part * (2.0 * sin.atan2(cos) / (std::f32::consts::SQRT_2 * magnitude))
+            }
+        };
+        part((bivector + dual) * 0.5, s - e1234) + part((bivector - dual) * 0.5, s + e1234)
+    }
+
+    /// The unit bivector for the plane of rotation and the angle in it, which is only one plane for rotors made by a single rotation
+    pub fn plane_and_angle(self) -> (Bivector, f32) {
+        let log = self.log();
+        let angle = log.magnitude();
+        if angle == 0.0 {
+            (Bivector::ZERO, 0.0)
+        } else {
+            (log * angle.recip(), angle)
+        }
+    }
+
+    /// Rotates from `self` at 0 to `other` at 1 the short way round, at a constant speed in both of the planes of the rotation between them
+    pub fn slerp(self, other: Self, t: f32) -> Self {
+        let relative = other * !self;
+        // a rotor and its negative are the same rotation, the one with a positive scalar part turns less to get there
+        let relative = if relative.s < 0.0 {
+            -relative
+        } else {
+            relative
+        };
+        Self::exp(relative.log() * t) * self
+    }
+
+    /// Splits the rotation into rotations in two planes at right angles to each other, which can be done in either order,
+    /// the first one having the biggest angle. Each angle is between 0 and pi, so together they are the same rotation but might be the negative rotor.
+    /// When both angles are the same, any pair of planes that makes the rotation is used
+    pub fn decompose(self) -> [SimpleRotation; 2] {
+        let rotor = self.normalized();
+        let rotor = if rotor.s < 0.0 { -rotor } else { rotor };
+        let (first, second) = rotor.log().split();
+        [first, second].map(|part| {
+            let angle = part.magnitude();
+            SimpleRotation {
+                rotor: Self::exp(part),
+                plane: if angle > 0.0 {
+                    part * angle.recip()
+                } else {
+                    Bivector::ZERO
+                },
+                angle,
+            }
+        })
+    }
+
+    pub fn kind(self) -> RotationKind {
+        let [first, second] = self.decompose();
+        if second.angle < ANGLE_TOLERANCE {
+            RotationKind::Simple
+        } else if first.angle - second.angle < ANGLE_TOLERANCE {
+            RotationKind::Isoclinic
+        } else {
+            RotationKind::General
+        }
+    }
+
+    fn from_parts(s: f32, bivector: Bivector, e1234: f32) -> Self {
+        Self {
+            s,
+            e12: bivector.xy,
+            e13: -bivector.xz,
+            e14: bivector.xw,
+            e23: bivector.yz,
+            e24: -bivector.yw,
+            e34: bivector.zw,
+            e1234,
+        }
+    }
+
+    fn parts(self) -> (f32, Bivector, f32) {
+        let bivector = Bivector {
+            xy: self.e12,
+            xz: -self.e13,
+            xw: self.e14,
+            yz: self.e23,
+            yw: -self.e24,
+            zw: self.e34,
+        };
+        (self.s, bivector, self.e1234)
+    }
+
     pub fn magnitude_squared(self) -> f32 {
         (!self * self).s
     }
@@ -156,6 +328,113 @@ impl Rotor {
         ];
         cgmath::Vector4 { x, y, z, w }
     }
+
+    /// The rotation matrix that does the same thing as `rotate`, for rotors with a magnitude of 1
+    pub fn to_matrix(self) -> cgmath::Matrix4<f32> {
+        let Self {
+            s: a,
+            e12: b,
+            e13: c,
+            e14: d,
+            e23: e,
+            e24: f,
+            e34: g,
+            e1234: h,
+        } = self;
+        cgmath::Matrix4::from_cols(
+            cgmath::vec4(
+                1.0 - 2.0 * (b * b + c * c + d * d + h * h),
+                2.0 * (a * b + c * e + d * f + g * h),
+                2.0 * (b * e - a * c - d * g + f * h),
+                2.0 * (a * d - b * f - c * g + e * h),
+            ),
+            cgmath::vec4(
+                2.0 * (c * e - a * b + d * f - g * h),
+                1.0 - 2.0 * (b * b + e * e + f * f + h * h),
+                2.0 * (a * e + b * c + d * h + f * g),
+                2.0 * (c * h - a * f - b * d + e * g),
+            ),
+            cgmath::vec4(
+                2.0 * (a * c + b * e - d * g - f * h),
+                2.0 * (b * c - a * e - d * h + f * g),
+                1.0 - 2.0 * (c * c + e * e + g * g + h * h),
+                2.0 * (a * g + b * h + c * d + e * f),
+            ),
+            cgmath::vec4(
+                -2.0 * (a * d + b * f + c * g + e * h),
+                2.0 * (a * f - b * d - c * h + e * g),
+                2.0 * (c * d - a * g - b * h + e * f),
+                1.0 - 2.0 * (d * d + f * f + g * g + h * h),
+            ),
+        )
+    }
+
+    /// The rotor for a rotation matrix, which should be orthonormal with a determinant of 1.
+    /// A rotor and its negative are the same rotation, this always gives the one with a positive scalar part
+    pub fn from_matrix(matrix: cgmath::Matrix4<f32>) -> Self {
+        let [x, y, z, w] = [matrix.x, matrix.y, matrix.z, matrix.w];
+        // a 4d rotation is made of two isoclinic rotations, which are each a unit quaternion,
+        // and this is the outer product of those quaternions (times 4)
+        let product = [
+            cgmath::vec4(
+                w.w + x.x + y.y + z.z,
+                w.z + x.y - y.x - z.w,
+                x.z - w.y + y.w - z.x,
+                x.w - w.x - y.z + z.y,
+            ),
+            cgmath::vec4(
+                x.y - w.z - y.x + z.w,
+                w.w - x.x - y.y + z.z,
+                w.x + x.w - y.z - z.y,
+                -w.y - x.z - y.w - z.x,
+            ),
+            cgmath::vec4(
+                w.y + x.z - y.w - z.x,
+                -w.x - x.w - y.z - z.y,
+                w.w - x.x + y.y - z.z,
+                x.y - w.z + y.x - z.w,
+            ),
+            cgmath::vec4(
+                x.w - w.x + y.z - z.y,
+                x.z - w.y - y.w + z.x,
+                -w.z - x.y - y.x - z.w,
+                y.y - w.w - x.x + z.z,
+            ),
+        ];
+        // every row is the second quaternion scaled by part of the first, so the biggest row is the most accurate
+        let right = product
+            .into_iter()
+            .max_by(|a, b| a.magnitude2().total_cmp(&b.magnitude2()))
+            .expect("there should be 4 rows")
+            .normalize();
+        let left = cgmath::Vector4::from(product.map(|row| row.dot(right))).normalize();
+        let rotor = Self {
+            s: (left.x + right.x) * 0.5,
+            e12: (left.y + right.y) * 0.5,
+            e13: (left.z + right.z) * -0.5,
+            e14: (left.w + right.w) * 0.5,
+            e23: (left.w - right.w) * 0.5,
+            e24: (left.z - right.z) * 0.5,
+            e34: (left.y - right.y) * 0.5,
+            e1234: (right.x - left.x) * 0.5,
+        };
+        if rotor.s < 0.0 { -rotor } else { rotor }
+    }
+}
+
+/// `sin(angle / 2) / angle`, which goes to a half as the angle goes to 0
+fn half_angle_sinc(angle: f32) -> f32 {
+    if angle < 0.0001 {
+        0.5
+    } else {
+        (angle * 0.5).sin() / angle
+    }
+}
+
+impl Default for Rotor {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
 }
 
 impl Not for Rotor {
@@ -185,6 +464,33 @@ impl Not for Rotor {
     }
 }
 
+impl Neg for Rotor {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        let Self {
+            s,
+            e12,
+            e13,
+            e14,
+            e23,
+            e24,
+            e34,
+            e1234,
+        } = self;
+        Self {
+            s: -s,
+            e12: -e12,
+            e13: -e13,
+            e14: -e14,
+            e23: -e23,
+            e24: -e24,
+            e34: -e34,
+            e1234: -e1234,
+        }
+    }
+}
+
 impl Mul<Self> for Rotor {
     type Output = Self;
 
@@ -1,8 +1,19 @@
-use crate::math::Rotor;
+use crate::math::{Bivector, Rotor};
+use cgmath::{InnerSpace, Zero};
 use encase::ShaderType;
+use serde::{Deserialize, Serialize};
 use std::ops::{Mul, Not};
 
-#[derive(Debug, Clone, Copy, ShaderType)]
+/// The smallest angle that is treated as turning, smaller angles in a plane move things in straight lines
+const ANGLE_TOLERANCE: f32 = 0.000001;
+
+/// The most times `Transform::blend` moves its guess of the average
+const BLEND_ITERATIONS: usize = 8;
+
+/// How close `Transform::blend` has to get to the average to stop
+const BLEND_TOLERANCE: f32 = 0.00001;
+
+#[derive(Debug, Clone, Copy, ShaderType, Serialize, Deserialize)]
 pub struct Transform {
     pub s: f32,
     pub e01: f32,
@@ -168,6 +179,83 @@ impl Transform {
         }
     }
 
+    /// The rotation around the origin that turns the direction of `from` into the direction of `to`, see `Rotor::rotation_between`
+    pub fn rotation_between(from: cgmath::Vector4<f32>, to: cgmath::Vector4<f32>) -> Self {
+        Self::from_rotor(Rotor::rotation_between(from, to))
+    }
+
+    /// Rotates around the origin by `angle` in the plane of `a` and `b`, see `Rotor::rotation_in_plane`
+    pub fn rotation_in_plane(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>, angle: f32) -> Self {
+        Self::from_rotor(Rotor::rotation_in_plane(a, b, angle))
+    }
+
+    /// The screw motion after one unit of time, turning with `rotation` as the angular velocity and moving with `velocity`,
+    /// so `exp(Bivector::ZERO, offset)` is `translation(offset)`
+    pub fn exp(rotation: Bivector, velocity: cgmath::Vector4<f32>) -> Self {
+        // adding up the velocity as it turns with the rotation
+        let offset = scale_in_planes(
+            rotation,
+            velocity,
+            |angle| angle.sin() / angle,
+            |angle| (1.0 - angle.cos()) / angle,
+        );
+        Self::translation(offset) * Self::from_rotor(Rotor::exp(rotation))
+    }
+
+    /// The angular velocity and velocity that `exp` turns back into this transform, taking the shortest way round
+    pub fn log(self) -> (Bivector, cgmath::Vector4<f32>) {
+        let transform = self.normalized();
+        let rotor = transform.rotor_part();
+        let rotor = if rotor.s < 0.0 { -rotor } else { rotor };
+        let rotation = rotor.log();
+        let offset = transform.transform(cgmath::Vector4::zero());
+        let velocity = scale_in_planes(
+            rotation,
+            offset,
+            |angle| angle * 0.5 / (angle * 0.5).tan(),
+            |angle| -angle * 0.5,
+        );
+        (rotation, velocity)
+    }
+
+    /// Moves from `self` at 0 to `other` at 1 along the screw motion between them, turning and moving at a constant speed
+    pub fn interpolate(self, other: Self, t: f32) -> Self {
+        let (rotation, velocity) = (other * !self).log();
+        Self::exp(rotation * t, velocity * t) * self
+    }
+
+    /// The average of the transforms weighted by how much each one counts, which is the same as `interpolate` for two transforms
+    pub fn blend(transforms: &[(Self, f32)]) -> Self {
+        let total_weight = transforms.iter().map(|&(_, weight)| weight).sum::<f32>();
+        let Some(&(mut average, _)) = transforms.iter().max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            return Self::IDENTITY;
+        };
+        if total_weight <= 0.0 {
+            return Self::IDENTITY;
+        }
+
+        // averaging the logs of the transforms from the guess, then moving the guess to that average
+        for _ in 0..BLEND_ITERATIONS {
+            let (rotation, velocity) = transforms.iter().fold(
+                (Bivector::ZERO, cgmath::Vector4::zero()),
+                |(rotation, velocity), &(transform, weight)| {
+                    let (transform_rotation, transform_velocity) = (transform * !average).log();
+                    let weight = weight / total_weight;
+                    (
+                        rotation + transform_rotation * weight,
+                        velocity + transform_velocity * weight,
+                    )
+                },
+            );
+            average = (Self::exp(rotation, velocity) * average).normalized();
+            if rotation.magnitude() + velocity.magnitude() < BLEND_TOLERANCE {
+                break;
+            }
+        }
+        average
+    }
+
     pub fn magnitude_squared(self) -> f32 {
         (!self * self).s
     }
@@ -294,6 +382,46 @@ impl Transform {
         let [x, y, z, w] = result;
         cgmath::Vector4 { x, y, z, w }
     }
+
+    /// The rotation matrix and offset that do the same thing as `transform`, so it is `matrix * point + offset`
+    pub fn to_matrix(self) -> (cgmath::Matrix4<f32>, cgmath::Vector4<f32>) {
+        let transform = self.normalized();
+        let rotor = transform.rotor_part();
+        // taking the rotation off leaves just the translation
+        let translation = transform * !Self::from_rotor(rotor);
+        let offset = cgmath::vec4(
+            translation.e01 * 2.0,
+            translation.e02 * -2.0,
+            translation.e03 * 2.0,
+            translation.e04 * -2.0,
+        );
+        (rotor.to_matrix(), offset)
+    }
+
+    /// The transform that multiplies by a rotation matrix and then adds the offset, see `Rotor::from_matrix`
+    pub fn from_matrix(matrix: cgmath::Matrix4<f32>, offset: cgmath::Vector4<f32>) -> Self {
+        Self::translation(offset) * Self::from_rotor(Rotor::from_matrix(matrix))
+    }
+}
+
+/// Scales the parts of `vector` in each of the planes of `rotation` by `scale(angle)`,
+/// and adds `turn(angle)` of those parts turned a quarter turn in their plane
+fn scale_in_planes(
+    rotation: Bivector,
+    vector: cgmath::Vector4<f32>,
+    scale: impl Fn(f32) -> f32,
+    turn: impl Fn(f32) -> f32,
+) -> cgmath::Vector4<f32> {
+    let (first, second) = rotation.split();
+    [first, second].into_iter().fold(vector, |result, part| {
+        let angle = part.magnitude();
+        if angle < ANGLE_TOLERANCE {
+            return result;
+        }
+        let plane = part * angle.recip();
+        let in_plane = -plane.velocity(plane.velocity(vector));
+        result + in_plane * (scale(angle) - 1.0) + plane.velocity(in_plane) * turn(angle)
+    })
 }
 
 impl Not for Transform {
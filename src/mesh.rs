@@ -0,0 +1,291 @@
+//! Meshes made from tetrahedra, the 4d version of triangle meshes. The cells of a mesh are the boundary of a solid
+//! in the same way that the triangles of a 3d mesh are the surface of one
+
+use crate::{
+    bvh::{Aabb, Bvh, BvhNodeKind},
+    ray::Ray,
+};
+use cgmath::{ElementWise, InnerSpace};
+use serde::{Deserialize, Serialize};
+
+/// How far outside of a tetrahedron a ray can go through it and still count as crossing it,
+/// so rays that go exactly through the edge between two cells dont slip between them
+const CROSSING_TOLERANCE: f32 = 0.00001;
+
+/// The vector perpendicular to `a`, `b` and `c`, the 4d version of the cross product.
+/// Its length is the volume of the parallelepiped made by the three vectors, and `cross(x, y, z)` is `w`
+pub fn cross(
+    a: cgmath::Vector4<f32>,
+    b: cgmath::Vector4<f32>,
+    c: cgmath::Vector4<f32>,
+) -> cgmath::Vector4<f32> {
+    // the determinant of the 3x3 matrix made from the parts of the vectors along 3 of the axes
+    let minor = |i: usize, j: usize, k: usize| {
+        a[i] * (b[j] * c[k] - b[k] * c[j]) - a[j] * (b[i] * c[k] - b[k] * c[i])
+            + a[k] * (b[i] * c[j] - b[j] * c[i])
+    };
+    cgmath::vec4(
+        -minor(1, 2, 3),
+        minor(0, 2, 3),
+        -minor(0, 1, 3),
+        minor(0, 1, 2),
+    )
+}
+
+/// One of the cells of a `Mesh`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tetrahedron {
+    pub vertices: [cgmath::Vector4<f32>; 4],
+}
+
+impl Tetrahedron {
+    /// The edges going from the first vertex to each of the others
+    pub fn edges(&self) -> [cgmath::Vector4<f32>; 3] {
+        let [first, rest @ ..] = self.vertices;
+        rest.map(|vertex| vertex - first)
+    }
+
+    /// `cross` of the edges, so it points out of the solid when the cell is oriented the way `Mesh` expects.
+    /// Its length is 6 times the volume of the tetrahedron
+    pub fn unnormalized_normal(&self) -> cgmath::Vector4<f32> {
+        let [a, b, c] = self.edges();
+        cross(a, b, c)
+    }
+
+    /// The normalized normal, this is zero for tetrahedra that are flat
+    pub fn normal(&self) -> cgmath::Vector4<f32> {
+        let normal = self.unnormalized_normal();
+        let length = normal.magnitude();
+        if length > 0.0 {
+            normal / length
+        } else {
+            normal
+        }
+    }
+
+    /// The vectors that give how far along each edge a point in the tetrahedron is, by taking the dot product
+    /// with the offset of the point from the first vertex. The point is inside when all of those are positive
+    /// and add up to at most 1
+    pub fn dual_edges(&self) -> [cgmath::Vector4<f32>; 3] {
+        let [a, b, c] = self.edges();
+        let normal = cross(a, b, c);
+        let duals = [
+            cross(b, c, normal),
+            cross(a, c, normal),
+            cross(a, b, normal),
+        ];
+        [0, 1, 2].map(|edge| duals[edge] / duals[edge].dot([a, b, c][edge]))
+    }
+
+    pub fn bounds(&self) -> Aabb {
+        Aabb::from_points(self.vertices).expect("a tetrahedron always has vertices")
+    }
+
+    /// How far along the ray it goes through the tetrahedron, this can be behind the origin of the ray
+    pub fn crossing(&self, ray: Ray) -> Option<f32> {
+        let normal = self.unnormalized_normal();
+        let denominator = ray.direction.dot(normal);
+        if denominator == 0.0 {
+            return None;
+        }
+
+        let distance = (self.vertices[0] - ray.origin).dot(normal) / denominator;
+        let offset = ray.origin + ray.direction * distance - self.vertices[0];
+        let [a, b, c] = self.dual_edges().map(|dual| dual.dot(offset));
+        let tolerance = -CROSSING_TOLERANCE;
+        (a >= tolerance && b >= tolerance && c >= tolerance && a + b + c <= 1.0 - tolerance)
+            .then_some(distance)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshError {
+    NoCells,
+    VertexOutOfRange {
+        cell: usize,
+        vertex: u32,
+        vertex_count: usize,
+    },
+}
+
+impl std::fmt::Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshError::NoCells => write!(f, "a mesh needs at least one cell"),
+            MeshError::VertexOutOfRange {
+                cell,
+                vertex,
+                vertex_count,
+            } => write!(
+                f,
+                "cell {cell} uses vertex {vertex}, but there are only {vertex_count} vertices"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MeshError {}
+
+/// How meshes are written in scene files, everything else is worked out when they are loaded
+#[derive(Serialize, Deserialize)]
+struct MeshData {
+    vertices: Vec<cgmath::Vector4<f32>>,
+    cells: Vec<[u32; 4]>,
+}
+
+/// Tetrahedra that share vertices, along with a bvh over them so rays only have to be tested against the cells near them.
+///
+/// The cells should be the boundary of a solid, with their vertices in the order that makes `Tetrahedron::normal`
+/// point out of it. Meshes that arent closed still render, but only as surfaces that cant be used in csg
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "MeshData", into = "MeshData")]
+pub struct Mesh {
+    vertices: Vec<cgmath::Vector4<f32>>,
+    cells: Vec<[u32; 4]>,
+    normals: Vec<cgmath::Vector4<f32>>,
+    bvh: Bvh,
+}
+
+impl Mesh {
+    pub fn new(
+        vertices: Vec<cgmath::Vector4<f32>>,
+        cells: Vec<[u32; 4]>,
+    ) -> Result<Self, MeshError> {
+        if cells.is_empty() {
+            return Err(MeshError::NoCells);
+        }
+        for (cell, indices) in cells.iter().enumerate() {
+            if let Some(&vertex) = indices
+                .iter()
+                .find(|&&vertex| vertex as usize >= vertices.len())
+            {
+                return Err(MeshError::VertexOutOfRange {
+                    cell,
+                    vertex,
+                    vertex_count: vertices.len(),
+                });
+            }
+        }
+
+        let mut mesh = Self {
+            vertices,
+            cells,
+            normals: vec![],
+            bvh: Bvh::default(),
+        };
+        mesh.normals = (0..mesh.cells.len())
+            .map(|cell| mesh.cell(cell).normal())
+            .collect();
+        mesh.bvh = Bvh::from_leaves(
+            (0..mesh.cells.len())
+                .map(|cell| (cell, mesh.cell(cell).bounds()))
+                .collect(),
+        );
+        Ok(mesh)
+    }
+
+    pub fn vertices(&self) -> &[cgmath::Vector4<f32>] {
+        &self.vertices
+    }
+
+    /// The indices of the vertices of each cell
+    pub fn cells(&self) -> &[[u32; 4]] {
+        &self.cells
+    }
+
+    /// The normal of each cell, see `Tetrahedron::normal`
+    pub fn normals(&self) -> &[cgmath::Vector4<f32>] {
+        &self.normals
+    }
+
+    /// The bvh over the cells, its leaves are the indices of cells and the root is always the first node
+    pub fn bvh(&self) -> &Bvh {
+        &self.bvh
+    }
+
+    pub fn cell(&self, index: usize) -> Tetrahedron {
+        Tetrahedron {
+            vertices: self.cells[index].map(|vertex| self.vertices[vertex as usize]),
+        }
+    }
+
+    /// The bounds of all the cells
+    pub fn bounds(&self) -> Aabb {
+        self.bvh.nodes[0].bounds
+    }
+
+    /// The closest cell that the ray goes through in front of its origin, along with how far along the ray it is
+    pub fn closest_crossing(&self, ray: Ray) -> Option<(f32, usize)> {
+        let mut closest = None;
+        // only crossings closer than the last one are visited
+        self.visit_crossings(ray, |distance, cell| {
+            closest = Some((distance, cell));
+            distance
+        });
+        closest
+    }
+
+    /// Every cell that the ray goes through in front of its origin along with how far along the ray it is, closest first
+    pub fn crossings(&self, ray: Ray) -> Vec<(f32, usize)> {
+        let mut crossings = vec![];
+        self.visit_crossings(ray, |distance, cell| {
+            crossings.push((distance, cell));
+            f32::INFINITY
+        });
+        crossings.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+        crossings
+    }
+
+    /// Calls `visit` with each crossing in front of the ray origin, it returns the distance past which crossings dont matter anymore
+    fn visit_crossings(&self, ray: Ray, mut visit: impl FnMut(f32, usize) -> f32) {
+        let inverse_direction = cgmath::vec4(1.0, 1.0, 1.0, 1.0).div_element_wise(ray.direction);
+        let mut max_distance = f32::INFINITY;
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.bvh.nodes[index];
+            if !node.bounds.hit_by(ray, inverse_direction, max_distance) {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Interior { right_child } => {
+                    stack.push(right_child);
+                    stack.push(index + 1);
+                }
+                BvhNodeKind::Leaf { object: cell } => {
+                    if let Some(distance) = self.cell(cell).crossing(ray)
+                        && distance > 0.0
+                        && distance < max_distance
+                    {
+                        max_distance = visit(distance, cell);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<MeshData> for Mesh {
+    type Error = MeshError;
+
+    fn try_from(data: MeshData) -> Result<Self, Self::Error> {
+        Self::new(data.vertices, data.cells)
+    }
+}
+
+impl From<Mesh> for MeshData {
+    fn from(mesh: Mesh) -> Self {
+        Self {
+            vertices: mesh.vertices,
+            cells: mesh.cells,
+        }
+    }
+}
+
+/// Everything else is worked out from the vertices and cells
+impl PartialEq for Mesh {
+    fn eq(&self, other: &Self) -> bool {
+        // objects share their mesh through an `Arc`, and the editor compares them every frame,
+        // so big meshes shouldnt be compared cell by cell when they are the same one
+        std::ptr::eq(self, other) || self.vertices == other.vertices && self.cells == other.cells
+    }
+}
@@ -1,24 +1,298 @@
+use crate::{bvh::Aabb, math::Rotor, mesh::Mesh, sdf::SdfShape};
+use cgmath::{ElementWise, InnerSpace};
 use enum_dispatch::enum_dispatch;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HyperSphere {
     pub position: cgmath::Vector4<f32>,
     pub radius: f32,
     pub material: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct HyperPlane {
     pub position: cgmath::Vector4<f32>,
     pub normal: cgmath::Vector4<f32>,
     pub material: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HyperCube {
+    pub position: cgmath::Vector4<f32>,
+    pub half_extents: cgmath::Vector4<f32>,
+    pub rotation: Rotor,
+    pub material: u32,
+}
+
+/// The shapes of a `HyperCylinder`, each one is centered on the origin before it is rotated
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HyperCylinderShape {
+    /// A ball in xyz times a line segment along w
+    Spherinder { radius: f32, half_height: f32 },
+    /// A disk in xy times a square in zw
+    Cubinder {
+        radius: f32,
+        half_extents: cgmath::Vector2<f32>,
+    },
+    /// A disk in xy times a disk in zw
+    Duocylinder { radii: cgmath::Vector2<f32> },
+    /// A ball in xyz that goes on forever along w, so it is the same sphere in every slice
+    SphereThroughW { radius: f32 },
+    /// A cylinder along z (a disk in xy times a line segment along z) that goes on forever along w
+    CylinderThroughW { radius: f32, half_height: f32 },
+}
+
+/// One of the shapes a hyper cylinder is the product of, everything where the part of the point along `axes`
+/// is within `radius` of the origin. A single axis is a line segment, two are a disk and three are a ball
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HyperCylinderFactor {
+    /// 1 for each axis the factor is in, and 0 for the others
+    pub axes: cgmath::Vector4<f32>,
+    pub radius: f32,
+}
+
+impl HyperCylinderFactor {
+    /// A factor that every point is inside of, for shapes that are the product of less than 3 factors
+    pub const EVERYWHERE: Self = Self {
+        axes: cgmath::Vector4::new(0.0, 0.0, 0.0, 0.0),
+        radius: 1.0,
+    };
+
+    fn new(axes: [f32; 4], radius: f32) -> Self {
+        Self {
+            axes: axes.into(),
+            radius,
+        }
+    }
+
+    /// The direction pointing out of the factor at a point on its surface, in the local space of the hyper cylinder
+    pub fn normal(&self, local_position: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        local_position.mul_element_wise(self.axes) / self.radius
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HyperCylinder {
+    pub position: cgmath::Vector4<f32>,
+    #[serde(default)]
+    pub rotation: Rotor,
+    pub shape: HyperCylinderShape,
+    pub material: u32,
+}
+
+impl HyperCylinder {
+    /// The shapes that the hyper cylinder is the product of, a point is inside of it when it is inside of all of them
+    pub fn factors(&self) -> [HyperCylinderFactor; 3] {
+        type Factor = HyperCylinderFactor;
+        match self.shape {
+            HyperCylinderShape::Spherinder {
+                radius,
+                half_height,
+            } => [
+                Factor::new([1.0, 1.0, 1.0, 0.0], radius),
+                Factor::new([0.0, 0.0, 0.0, 1.0], half_height),
+                Factor::EVERYWHERE,
+            ],
+            HyperCylinderShape::Cubinder {
+                radius,
+                half_extents,
+            } => [
+                Factor::new([1.0, 1.0, 0.0, 0.0], radius),
+                Factor::new([0.0, 0.0, 1.0, 0.0], half_extents.x),
+                Factor::new([0.0, 0.0, 0.0, 1.0], half_extents.y),
+            ],
+            HyperCylinderShape::Duocylinder { radii } => [
+                Factor::new([1.0, 1.0, 0.0, 0.0], radii.x),
+                Factor::new([0.0, 0.0, 1.0, 1.0], radii.y),
+                Factor::EVERYWHERE,
+            ],
+            HyperCylinderShape::SphereThroughW { radius } => [
+                Factor::new([1.0, 1.0, 1.0, 0.0], radius),
+                Factor::EVERYWHERE,
+                Factor::EVERYWHERE,
+            ],
+            HyperCylinderShape::CylinderThroughW {
+                radius,
+                half_height,
+            } => [
+                Factor::new([1.0, 1.0, 0.0, 0.0], radius),
+                Factor::new([0.0, 0.0, 1.0, 0.0], half_height),
+                Factor::EVERYWHERE,
+            ],
+        }
+    }
+}
+
+/// The shapes of a `HyperTorus`, each one is centered on the origin before it is rotated
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum HyperTorusShape {
+    /// Everything within `minor_radius` of the surface made from a circle in xy with radius `major_radii.x`
+    /// and a circle in zw with radius `major_radii.y`
+    Tiger {
+        major_radii: cgmath::Vector2<f32>,
+        minor_radius: f32,
+    },
+    /// Everything within `minor_radius` of the surface of a torus in xyz
+    Ditorus {
+        major_radius: f32,
+        middle_radius: f32,
+        minor_radius: f32,
+    },
+    /// Everything within `minor_radius` of a circle in xy
+    Spheritorus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// Everything within `minor_radius` of a sphere in xyz
+    Torisphere {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+}
+
+/// One of the 4d tori, these dont have a closed form intersection so they are rendered the same way as `Sdf` objects
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HyperTorus {
+    pub position: cgmath::Vector4<f32>,
+    #[serde(default)]
+    pub rotation: Rotor,
+    pub shape: HyperTorusShape,
+    pub material: u32,
+}
+
+impl HyperTorus {
+    /// The signed distance field of the torus, which is exact for all of the shapes
+    pub fn sdf(&self) -> Sdf {
+        let shape = match self.shape {
+            HyperTorusShape::Tiger {
+                major_radii,
+                minor_radius,
+            } => SdfShape::Tiger {
+                major_radii,
+                minor_radius,
+            },
+            HyperTorusShape::Ditorus {
+                major_radius,
+                middle_radius,
+                minor_radius,
+            } => SdfShape::Ditorus {
+                major_radius,
+                middle_radius,
+                minor_radius,
+            },
+            HyperTorusShape::Spheritorus {
+                major_radius,
+                minor_radius,
+            } => SdfShape::Spheritorus {
+                major_radius,
+                minor_radius,
+            },
+            HyperTorusShape::Torisphere {
+                major_radius,
+                minor_radius,
+            } => SdfShape::Torisphere {
+                major_radius,
+                minor_radius,
+            },
+        };
+        Sdf {
+            position: self.position,
+            rotation: self.rotation,
+            shape,
+            material: self.material,
+        }
+    }
+}
+
+/// A solid made from the tetrahedra in `mesh`, moved into place by `rotation` and then `position`.
+/// The mesh is shared between copies of the object, since it can have a lot of cells
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TetrahedronMesh {
+    pub position: cgmath::Vector4<f32>,
+    #[serde(default)]
+    pub rotation: Rotor,
+    pub mesh: Arc<Mesh>,
+    pub material: u32,
+    /// The material of each cell of the mesh, cells past the end of this use `material`
+    #[serde(default)]
+    pub cell_materials: Vec<u32>,
+}
+
+impl TetrahedronMesh {
+    pub fn cell_material(&self, cell: usize) -> u32 {
+        self.cell_materials
+            .get(cell)
+            .copied()
+            .unwrap_or(self.material)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    /// Everything in `left` that isnt in `right`
+    Difference,
+}
+
+/// Combines two objects as solids, where hyper planes are the half space behind their normal.
+///
+/// The shader only keeps the first 8 parts of the ray that are inside the result, and it can only render
+/// trees where operations are nested at most 3 deep through their `right` side (`left` can be nested as deep as you want)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Csg {
+    pub operation: CsgOperation,
+    pub left: Box<Object>,
+    pub right: Box<Object>,
+}
+
+/// A shape described by a signed distance field, rendered by stepping rays towards its surface.
+///
+/// The shader can only evaluate shapes where `Combine`s are nested at most 7 deep through their `right` side,
+/// and `Transformed` and `Repeat` are nested at most 4 deep
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sdf {
+    pub position: cgmath::Vector4<f32>,
+    #[serde(default)]
+    pub rotation: Rotor,
+    pub shape: SdfShape,
+    pub material: u32,
+}
+
+impl Sdf {
+    /// How far to move from a point on each axis when working out the normal
+    const NORMAL_OFFSET: f32 = 0.0005;
+
+    /// The distance from a point to the surface of the object, negative for points inside of it
+    pub fn distance(&self, point: cgmath::Vector4<f32>) -> f32 {
+        self.shape
+            .distance((!self.rotation).rotate(point - self.position))
+    }
+
+    /// The direction pointing out of the surface at a point near it
+    pub fn normal(&self, point: cgmath::Vector4<f32>) -> cgmath::Vector4<f32> {
+        let gradient = [0, 1, 2, 3].map(|axis| {
+            let mut offset = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+            offset[axis] = Self::NORMAL_OFFSET;
+            self.distance(point + offset) - self.distance(point - offset)
+        });
+        cgmath::Vector4::from(gradient).normalize()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[enum_dispatch(RayIntersect)]
 pub enum Object {
     HyperSphere(HyperSphere),
     HyperPlane(HyperPlane),
+    HyperCube(HyperCube),
+    HyperCylinder(HyperCylinder),
+    HyperTorus(HyperTorus),
+    TetrahedronMesh(TetrahedronMesh),
+    Csg(Csg),
+    Sdf(Sdf),
 }
 
 impl Object {
@@ -26,6 +300,26 @@ impl Object {
         match self {
             Object::HyperSphere(hyper_sphere) => hyper_sphere.position,
             Object::HyperPlane(hyper_plane) => hyper_plane.position,
+            Object::HyperCube(hyper_cube) => hyper_cube.position,
+            Object::HyperCylinder(hyper_cylinder) => hyper_cylinder.position,
+            Object::HyperTorus(hyper_torus) => hyper_torus.position,
+            Object::TetrahedronMesh(tetrahedron_mesh) => tetrahedron_mesh.position,
+            // the left object is usually the one that is being carved, so it makes the most sense to move it around
+            Object::Csg(csg) => csg.left.position(),
+            Object::Sdf(sdf) => sdf.position,
+        }
+    }
+
+    /// The rotation of the object, hyper spheres and hyper planes only have a position and a normal, and csg objects
+    /// have a rotation for each of the objects in them
+    pub fn rotation(&self) -> Option<Rotor> {
+        match self {
+            Object::HyperSphere(_) | Object::HyperPlane(_) | Object::Csg(_) => None,
+            Object::HyperCube(hyper_cube) => Some(hyper_cube.rotation),
+            Object::HyperCylinder(hyper_cylinder) => Some(hyper_cylinder.rotation),
+            Object::HyperTorus(hyper_torus) => Some(hyper_torus.rotation),
+            Object::TetrahedronMesh(tetrahedron_mesh) => Some(tetrahedron_mesh.rotation),
+            Object::Sdf(sdf) => Some(sdf.rotation),
         }
     }
 
@@ -33,6 +327,134 @@ impl Object {
         match self {
             Object::HyperSphere(hyper_sphere) => hyper_sphere.position += offset,
             Object::HyperPlane(hyper_plane) => hyper_plane.position += offset,
+            Object::HyperCube(hyper_cube) => hyper_cube.position += offset,
+            Object::HyperCylinder(hyper_cylinder) => hyper_cylinder.position += offset,
+            Object::HyperTorus(hyper_torus) => hyper_torus.position += offset,
+            Object::TetrahedronMesh(tetrahedron_mesh) => tetrahedron_mesh.position += offset,
+            Object::Csg(csg) => {
+                csg.left.move_position(offset);
+                csg.right.move_position(offset);
+            }
+            Object::Sdf(sdf) => sdf.position += offset,
+        }
+    }
+
+    /// The bounding box of the object, hyper planes are infinite so they dont have one (and neither does anything
+    /// made from them that isnt cut down by something finite)
+    pub fn bounds(&self) -> Option<Aabb> {
+        match self {
+            Object::HyperSphere(hyper_sphere) => {
+                let radius = cgmath::vec4(1.0, 1.0, 1.0, 1.0) * hyper_sphere.radius;
+                Some(Aabb {
+                    min: hyper_sphere.position - radius,
+                    max: hyper_sphere.position + radius,
+                })
+            }
+            Object::HyperPlane(_) => None,
+            Object::HyperCube(hyper_cube) => Some(
+                Aabb {
+                    min: -hyper_cube.half_extents,
+                    max: hyper_cube.half_extents,
+                }
+                .transformed(hyper_cube.rotation, hyper_cube.position),
+            ),
+            Object::HyperCylinder(hyper_cylinder) => {
+                let factors = hyper_cylinder.factors();
+                // the shapes that go on forever along w dont have a factor limiting every axis
+                let limited_axes = factors
+                    .iter()
+                    .map(|factor| factor.axes)
+                    .sum::<cgmath::Vector4<f32>>();
+                if (0..4).any(|axis| limited_axes[axis] == 0.0) {
+                    return None;
+                }
+                let half_extents = factors
+                    .iter()
+                    .map(|factor| factor.axes * factor.radius)
+                    .sum::<cgmath::Vector4<f32>>();
+                Some(
+                    Aabb {
+                        min: -half_extents,
+                        max: half_extents,
+                    }
+                    .transformed(hyper_cylinder.rotation, hyper_cylinder.position),
+                )
+            }
+            Object::HyperTorus(hyper_torus) => Object::Sdf(hyper_torus.sdf()).bounds(),
+            Object::TetrahedronMesh(tetrahedron_mesh) => Some(
+                tetrahedron_mesh
+                    .mesh
+                    .bounds()
+                    .transformed(tetrahedron_mesh.rotation, tetrahedron_mesh.position),
+            ),
+            Object::Csg(csg) => match csg.operation {
+                CsgOperation::Union => Some(csg.left.bounds()?.union(csg.right.bounds()?)),
+                CsgOperation::Intersection => match (csg.left.bounds(), csg.right.bounds()) {
+                    (Some(left), Some(right)) => Some(left.intersection(right)),
+                    (bounds, None) | (None, bounds) => bounds,
+                },
+                CsgOperation::Difference => csg.left.bounds(),
+            },
+            Object::Sdf(sdf) => Some(sdf.shape.bounds()?.transformed(sdf.rotation, sdf.position)),
+        }
+    }
+
+    /// Rotates the object around a point, moving its position along with it
+    pub fn rotate_around(&mut self, rotation: Rotor, point: cgmath::Vector4<f32>) {
+        let position = self.position();
+        self.move_position(point + rotation.rotate(position - point) - position);
+        self.rotate(rotation);
+    }
+
+    /// Rotates the object around its position, rotation doesnt do anything for hyper spheres
+    pub fn rotate(&mut self, rotation: Rotor) {
+        match self {
+            Object::HyperSphere(_) => {}
+            Object::HyperPlane(hyper_plane) => {
+                hyper_plane.normal = rotation.rotate(hyper_plane.normal);
+            }
+            Object::HyperCube(hyper_cube) => {
+                hyper_cube.rotation = (rotation * hyper_cube.rotation).normalized();
+            }
+            Object::HyperCylinder(hyper_cylinder) => {
+                hyper_cylinder.rotation = (rotation * hyper_cylinder.rotation).normalized();
+            }
+            Object::HyperTorus(hyper_torus) => {
+                hyper_torus.rotation = (rotation * hyper_torus.rotation).normalized();
+            }
+            Object::TetrahedronMesh(tetrahedron_mesh) => {
+                tetrahedron_mesh.rotation = (rotation * tetrahedron_mesh.rotation).normalized();
+            }
+            Object::Csg(csg) => {
+                let position = csg.left.position();
+                csg.left.rotate_around(rotation, position);
+                csg.right.rotate_around(rotation, position);
+            }
+            Object::Sdf(sdf) => {
+                sdf.rotation = (rotation * sdf.rotation).normalized();
+            }
+        }
+    }
+
+    /// Every material index the object uses, including the objects inside csg objects and each cell of a mesh
+    pub fn materials(&self) -> Vec<u32> {
+        match self {
+            Object::HyperSphere(hyper_sphere) => vec![hyper_sphere.material],
+            Object::HyperPlane(hyper_plane) => vec![hyper_plane.material],
+            Object::HyperCube(hyper_cube) => vec![hyper_cube.material],
+            Object::HyperCylinder(hyper_cylinder) => vec![hyper_cylinder.material],
+            Object::HyperTorus(hyper_torus) => vec![hyper_torus.material],
+            Object::TetrahedronMesh(tetrahedron_mesh) => {
+                let mut materials = vec![tetrahedron_mesh.material];
+                materials.extend(&tetrahedron_mesh.cell_materials);
+                materials
+            }
+            Object::Csg(csg) => {
+                let mut materials = csg.left.materials();
+                materials.extend(csg.right.materials());
+                materials
+            }
+            Object::Sdf(sdf) => vec![sdf.material],
         }
     }
 }
@@ -0,0 +1,122 @@
+use crate::{image::Image, state::State};
+
+/// Renders a [`State`] into a texture that isnt attached to any window and reads the result back to the cpu,
+/// without the crosshair, gizmos or editor drawn on top
+pub struct OffscreenRenderer {
+    texture: wgpu::Texture,
+    readback_buffer: wgpu::Buffer,
+    padded_bytes_per_row: u32,
+}
+
+impl OffscreenRenderer {
+    const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Bgra8Unorm;
+    const BYTES_PER_PIXEL: u32 = 4;
+
+    pub fn new(device: &wgpu::Device, width: u32, height: u32) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::FORMAT,
+            usage: wgpu::TextureUsages::COPY_DST | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        let padded_bytes_per_row =
+            (width * Self::BYTES_PER_PIXEL).next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: padded_bytes_per_row as wgpu::BufferAddress * height as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        Self {
+            texture,
+            readback_buffer,
+            padded_bytes_per_row,
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.texture.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.texture.height()
+    }
+
+    pub fn render(&self, state: &mut State, device: &wgpu::Device, queue: &wgpu::Queue) -> Image {
+        self.render_frames(state, device, queue, 1)
+    }
+
+    /// Renders `frames` frames before reading the last one back, so path tracing has time to accumulate samples
+    pub fn render_frames(
+        &self,
+        state: &mut State,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        frames: u32,
+    ) -> Image {
+        let (width, height) = (self.width(), self.height());
+        state.resize(device, width, height);
+        for _ in 0..frames {
+            state.render(device, queue, &self.texture, false);
+        }
+
+        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Offscreen Readback Encoder"),
+        });
+        command_encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &self.readback_buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(self.padded_bytes_per_row),
+                    rows_per_image: Some(height),
+                },
+            },
+            self.texture.size(),
+        );
+        queue.submit(std::iter::once(command_encoder.finish()));
+
+        let slice = self.readback_buffer.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .expect("the map callback should have been called after waiting")
+            .expect("the readback buffer should be mapped successfully");
+
+        let mut image = Image::new(width, height);
+        {
+            let data = slice.get_mapped_range();
+            for (y, row) in data
+                .chunks_exact(self.padded_bytes_per_row as usize)
+                .enumerate()
+            {
+                for (x, &[b, g, r, a]) in row[..(width * Self::BYTES_PER_PIXEL) as usize]
+                    .as_chunks::<4>()
+                    .0
+                    .iter()
+                    .enumerate()
+                {
+                    image.set(x as u32, y as u32, [r, g, b, a]);
+                }
+            }
+        }
+        self.readback_buffer.unmap();
+
+        image
+    }
+}
@@ -0,0 +1,550 @@
+//! Convex 4d polytopes made from polyhedral cells, like the regular polychora. They are rendered by splitting
+//! their cells into tetrahedra and making a tetrahedron mesh from them
+
+use crate::{
+    math::Rotor,
+    mesh::{Mesh, MeshError, Tetrahedron},
+    objects::TetrahedronMesh,
+};
+use cgmath::InnerSpace;
+use std::{path::Path, sync::Arc};
+
+/// How close vertices have to be to the hyper plane of a cell to be on it, for polytopes with a circumradius of 1
+const FACET_TOLERANCE: f32 = 0.0001;
+
+/// The six convex regular polytopes in 4d
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegularPolychoron {
+    /// 5 tetrahedral cells
+    FiveCell,
+    /// 8 cubic cells
+    Tesseract,
+    /// 16 tetrahedral cells
+    SixteenCell,
+    /// 24 octahedral cells
+    TwentyFourCell,
+    /// 120 dodecahedral cells
+    OneHundredTwentyCell,
+    /// 600 tetrahedral cells
+    SixHundredCell,
+}
+
+impl RegularPolychoron {
+    pub const ALL: [RegularPolychoron; 6] = [
+        RegularPolychoron::FiveCell,
+        RegularPolychoron::Tesseract,
+        RegularPolychoron::SixteenCell,
+        RegularPolychoron::TwentyFourCell,
+        RegularPolychoron::OneHundredTwentyCell,
+        RegularPolychoron::SixHundredCell,
+    ];
+
+    /// The polychoron centered on the origin, with all of its vertices at a distance of 1 from it
+    pub fn polytope(self) -> Polytope {
+        // the cells of a regular polytope face towards the vertices of its dual
+        let (vertices, cell_normals) = match self {
+            RegularPolychoron::FiveCell => {
+                let vertices = five_cell_vertices();
+                let cell_normals = vertices.iter().map(|&vertex| -vertex).collect();
+                (vertices, cell_normals)
+            }
+            RegularPolychoron::Tesseract => (tesseract_vertices(), sixteen_cell_vertices()),
+            RegularPolychoron::SixteenCell => (sixteen_cell_vertices(), tesseract_vertices()),
+            RegularPolychoron::TwentyFourCell => {
+                let cell_normals = [sixteen_cell_vertices(), tesseract_vertices()].concat();
+                (twenty_four_cell_vertices(), cell_normals)
+            }
+            RegularPolychoron::OneHundredTwentyCell => {
+                let six_hundred_cell = six_hundred_cell_vertices();
+                (simplex_centers(&six_hundred_cell), six_hundred_cell)
+            }
+            RegularPolychoron::SixHundredCell => {
+                let vertices = six_hundred_cell_vertices();
+                let cell_normals = simplex_centers(&vertices);
+                (vertices, cell_normals)
+            }
+        };
+        Polytope::from_cell_normals(vertices, &cell_normals)
+    }
+}
+
+fn five_cell_vertices() -> Vec<cgmath::Vector4<f32>> {
+    let w = 1.0 / 5.0f32.sqrt();
+    [
+        cgmath::vec4(1.0, 1.0, 1.0, -w),
+        cgmath::vec4(1.0, -1.0, -1.0, -w),
+        cgmath::vec4(-1.0, 1.0, -1.0, -w),
+        cgmath::vec4(-1.0, -1.0, 1.0, -w),
+        cgmath::vec4(0.0, 0.0, 0.0, 4.0 * w),
+    ]
+    .map(|vertex| vertex * 5.0f32.sqrt() / 4.0)
+    .to_vec()
+}
+
+/// Every combination of signs of (1/2, 1/2, 1/2, 1/2)
+fn tesseract_vertices() -> Vec<cgmath::Vector4<f32>> {
+    (0..16)
+        .map(|signs: u32| {
+            cgmath::Vector4::from(
+                [0, 1, 2, 3].map(|axis| if signs & (1 << axis) == 0 { 0.5 } else { -0.5 }),
+            )
+        })
+        .collect()
+}
+
+/// The unit vectors along each axis, both ways
+fn sixteen_cell_vertices() -> Vec<cgmath::Vector4<f32>> {
+    (0..8)
+        .map(|index| {
+            let mut vertex = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+            vertex[index / 2] = if index % 2 == 0 { 1.0 } else { -1.0 };
+            vertex
+        })
+        .collect()
+}
+
+/// The midpoints of the edges of a 16-cell, pushed out onto the unit hyper sphere
+fn twenty_four_cell_vertices() -> Vec<cgmath::Vector4<f32>> {
+    let vertices = sixteen_cell_vertices();
+    let mut result = vec![];
+    for (i, &a) in vertices.iter().enumerate() {
+        for &b in &vertices[i + 1..] {
+            // opposite vertices arent joined by an edge
+            if a.dot(b) == 0.0 {
+                result.push((a + b).normalize());
+            }
+        }
+    }
+    result
+}
+
+/// The vertices of a 24-cell and a tesseract, along with the even permutations of (φ, 1, 1/φ, 0) / 2 with every combination of signs
+fn six_hundred_cell_vertices() -> Vec<cgmath::Vector4<f32>> {
+    let phi = (1.0 + 5.0f32.sqrt()) / 2.0;
+    let mut vertices = [sixteen_cell_vertices(), tesseract_vertices()].concat();
+    // every way of putting the 4 values on the 4 axes, only keeping the permutations that have an even number of pairs out of order
+    let permutations =
+        (0..4usize.pow(4)).map(|index| [0, 1, 2, 3].map(|i| index / 4usize.pow(i) % 4));
+    let even_permutations = permutations.filter(|permutation| {
+        let mut swapped_pairs = 0;
+        for i in 0..4 {
+            for j in i + 1..4 {
+                if permutation[i] == permutation[j] {
+                    return false;
+                }
+                swapped_pairs += (permutation[i] > permutation[j]) as u32;
+            }
+        }
+        swapped_pairs % 2 == 0
+    });
+    for permutation in even_permutations {
+        for signs in 0..8 {
+            let values = [phi, 1.0, 1.0 / phi].map(|value| value / 2.0);
+            let mut vertex = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+            for (i, value) in values.into_iter().enumerate() {
+                vertex[permutation[i]] = if signs & (1 << i) == 0 { value } else { -value };
+            }
+            vertices.push(vertex);
+        }
+    }
+    vertices
+}
+
+/// The directions to the middle of each tetrahedral cell of a polytope with only tetrahedral cells,
+/// found by looking for groups of 4 vertices that are all joined by the shortest edges
+fn simplex_centers(vertices: &[cgmath::Vector4<f32>]) -> Vec<cgmath::Vector4<f32>> {
+    let edge_length = vertices
+        .iter()
+        .enumerate()
+        .flat_map(|(i, &a)| vertices[i + 1..].iter().map(move |&b| (a - b).magnitude()))
+        .min_by(f32::total_cmp)
+        .unwrap_or(0.0);
+    let neighbours = vertices
+        .iter()
+        .map(|&a| {
+            (0..vertices.len())
+                .filter(|&b| ((a - vertices[b]).magnitude() - edge_length).abs() < FACET_TOLERANCE)
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+
+    let mut centers = vec![];
+    for a in 0..vertices.len() {
+        for &b in neighbours[a].iter().filter(|&&b| b > a) {
+            for &c in neighbours[b]
+                .iter()
+                .filter(|&&c| c > b && neighbours[a].contains(&c))
+            {
+                for &d in neighbours[c]
+                    .iter()
+                    .filter(|&&d| d > c && neighbours[a].contains(&d) && neighbours[b].contains(&d))
+                {
+                    centers
+                        .push((vertices[a] + vertices[b] + vertices[c] + vertices[d]).normalize());
+                }
+            }
+        }
+    }
+    centers
+}
+
+#[derive(Debug)]
+pub enum PolytopeError {
+    Io(std::io::Error),
+    /// A problem with the contents of a 4OFF file, `line` starts from 1
+    Off {
+        line: usize,
+        message: String,
+    },
+    NoCells,
+    TooFewVertices {
+        face: usize,
+    },
+    VertexOutOfRange {
+        face: usize,
+        vertex: u32,
+        vertex_count: usize,
+    },
+    FaceOutOfRange {
+        cell: usize,
+        face: u32,
+        face_count: usize,
+    },
+}
+
+impl std::fmt::Display for PolytopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolytopeError::Io(error) => write!(f, "{error}"),
+            PolytopeError::Off { line, message } => write!(f, "{line}: {message}"),
+            PolytopeError::NoCells => write!(f, "a polytope needs at least one cell"),
+            PolytopeError::TooFewVertices { face } => {
+                write!(f, "face {face} has less than 3 vertices")
+            }
+            PolytopeError::VertexOutOfRange {
+                face,
+                vertex,
+                vertex_count,
+            } => write!(
+                f,
+                "face {face} uses vertex {vertex}, but there are only {vertex_count} vertices"
+            ),
+            PolytopeError::FaceOutOfRange {
+                cell,
+                face,
+                face_count,
+            } => write!(
+                f,
+                "cell {cell} uses face {face}, but there are only {face_count} faces"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PolytopeError {}
+
+impl From<std::io::Error> for PolytopeError {
+    fn from(error: std::io::Error) -> Self {
+        PolytopeError::Io(error)
+    }
+}
+
+/// A convex polytope, made from polygonal faces that are joined into polyhedral cells
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polytope {
+    vertices: Vec<cgmath::Vector4<f32>>,
+    faces: Vec<Vec<u32>>,
+    cells: Vec<Vec<u32>>,
+}
+
+impl Polytope {
+    /// `faces` are the indices of their vertices in order around the polygon,
+    /// and `cells` are the indices of the faces around the polyhedron
+    pub fn new(
+        vertices: Vec<cgmath::Vector4<f32>>,
+        faces: Vec<Vec<u32>>,
+        cells: Vec<Vec<u32>>,
+    ) -> Result<Self, PolytopeError> {
+        if cells.is_empty() {
+            return Err(PolytopeError::NoCells);
+        }
+        for (face, indices) in faces.iter().enumerate() {
+            if indices.len() < 3 {
+                return Err(PolytopeError::TooFewVertices { face });
+            }
+            if let Some(&vertex) = indices
+                .iter()
+                .find(|&&vertex| vertex as usize >= vertices.len())
+            {
+                return Err(PolytopeError::VertexOutOfRange {
+                    face,
+                    vertex,
+                    vertex_count: vertices.len(),
+                });
+            }
+        }
+        for (cell, indices) in cells.iter().enumerate() {
+            if let Some(&face) = indices.iter().find(|&&face| face as usize >= faces.len()) {
+                return Err(PolytopeError::FaceOutOfRange {
+                    cell,
+                    face,
+                    face_count: faces.len(),
+                });
+            }
+        }
+        Ok(Self {
+            vertices,
+            faces,
+            cells,
+        })
+    }
+
+    /// Builds the convex hull of `vertices` from the directions that each of its cells face,
+    /// each cell is the vertices furthest along its normal
+    fn from_cell_normals(
+        vertices: Vec<cgmath::Vector4<f32>>,
+        cell_normals: &[cgmath::Vector4<f32>],
+    ) -> Self {
+        let cell_vertices = cell_normals
+            .iter()
+            .map(|&normal| {
+                let furthest = vertices
+                    .iter()
+                    .map(|vertex| vertex.dot(normal))
+                    .fold(f32::NEG_INFINITY, f32::max);
+                (0..vertices.len() as u32)
+                    .filter(|&vertex| {
+                        vertices[vertex as usize].dot(normal) > furthest - FACET_TOLERANCE
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // two cells share a face when they have at least 3 vertices in common, otherwise they only share an edge or a vertex
+        let mut faces = vec![];
+        let mut cells = vec![vec![]; cell_vertices.len()];
+        for a in 0..cell_vertices.len() {
+            for b in a + 1..cell_vertices.len() {
+                let shared = cell_vertices[a]
+                    .iter()
+                    .copied()
+                    .filter(|vertex| cell_vertices[b].contains(vertex))
+                    .collect::<Vec<_>>();
+                if shared.len() >= 3 {
+                    cells[a].push(faces.len() as u32);
+                    cells[b].push(faces.len() as u32);
+                    faces.push(polygon_order(&vertices, shared));
+                }
+            }
+        }
+        Self {
+            vertices,
+            faces,
+            cells,
+        }
+    }
+
+    /// Reads a polytope in the 4OFF format: a `4OFF` line, then the numbers of vertices, faces, edges and cells,
+    /// then a line with the coordinates of each vertex, a line for each face with its number of vertices followed by their indices,
+    /// and a line for each cell with its number of faces followed by their indices. Anything after a `#` is a comment,
+    /// and any numbers after the indices of a face or cell (like colors) are ignored
+    pub fn from_off(source: &str) -> Result<Self, PolytopeError> {
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                let line = line.split('#').next().unwrap_or_default();
+                (index + 1, line.split_whitespace().collect::<Vec<_>>())
+            })
+            .filter(|(_, words)| !words.is_empty());
+
+        let (line, words) = lines.next().ok_or(PolytopeError::Off {
+            line: 1,
+            message: "the file is empty".into(),
+        })?;
+        if words[0] != "4OFF" {
+            return Err(PolytopeError::Off {
+                line,
+                message: format!("expected 4OFF, found {}", words[0]),
+            });
+        }
+        // the counts can be on the same line as 4OFF
+        let (line, words) = if words.len() > 1 {
+            (line, words[1..].to_vec())
+        } else {
+            lines.next().ok_or(PolytopeError::Off {
+                line,
+                message: "expected the numbers of vertices, faces, edges and cells".into(),
+            })?
+        };
+        let counts = parse_numbers::<usize>(line, &words)?;
+        let &[vertex_count, face_count, _, cell_count, ..] = counts.as_slice() else {
+            return Err(PolytopeError::Off {
+                line,
+                message: "expected the numbers of vertices, faces, edges and cells".into(),
+            });
+        };
+
+        let mut next_line = |expected: &str| {
+            lines.next().ok_or_else(|| PolytopeError::Off {
+                line: source.lines().count(),
+                message: format!("the file ended before {expected}"),
+            })
+        };
+        let mut vertices = vec![];
+        for vertex in 0..vertex_count {
+            let (line, words) = next_line(&format!("vertex {vertex}"))?;
+            let &[x, y, z, w, ..] = parse_numbers::<f32>(line, &words)?.as_slice() else {
+                return Err(PolytopeError::Off {
+                    line,
+                    message: "a vertex needs 4 coordinates".into(),
+                });
+            };
+            vertices.push(cgmath::vec4(x, y, z, w));
+        }
+        let mut read_indices = |expected: &str| {
+            let (line, words) = next_line(expected)?;
+            let count = parse_numbers::<usize>(line, &words[..1])?[0];
+            let indices = count
+                .checked_add(1)
+                .and_then(|end| words.get(1..end))
+                .ok_or(PolytopeError::Off {
+                    line,
+                    message: format!("expected {count} indices"),
+                })?;
+            parse_numbers::<u32>(line, indices)
+        };
+        let faces = (0..face_count)
+            .map(|face| read_indices(&format!("face {face}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        let cells = (0..cell_count)
+            .map(|cell| read_indices(&format!("cell {cell}")))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(vertices, faces, cells)
+    }
+
+    pub fn load_off(path: impl AsRef<Path>) -> Result<Self, PolytopeError> {
+        Self::from_off(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn vertices(&self) -> &[cgmath::Vector4<f32>] {
+        &self.vertices
+    }
+
+    /// The indices of the vertices of each face, in order around it
+    pub fn faces(&self) -> &[Vec<u32>] {
+        &self.faces
+    }
+
+    /// The indices of the faces of each cell
+    pub fn cells(&self) -> &[Vec<u32>] {
+        &self.cells
+    }
+
+    /// The same polytope with every vertex multiplied by `scale`
+    pub fn scaled(mut self, scale: f32) -> Self {
+        for vertex in &mut self.vertices {
+            *vertex *= scale;
+        }
+        self
+    }
+
+    /// Splits each cell into tetrahedra facing out of the polytope, returning the tetrahedra
+    /// along with the cell of the polytope that each of them came from
+    pub fn tetrahedra(&self) -> (Vec<[u32; 4]>, Vec<usize>) {
+        let center =
+            self.vertices.iter().sum::<cgmath::Vector4<f32>>() / self.vertices.len() as f32;
+        let mut tetrahedra = vec![];
+        let mut tetrahedron_cells = vec![];
+        for (cell, faces) in self.cells.iter().enumerate() {
+            let Some(&apex) = faces.first().map(|&face| &self.faces[face as usize][0]) else {
+                continue;
+            };
+            // every face that doesnt touch the apex is the base of a cone of tetrahedra that fills the cell
+            for &face in faces {
+                let face = &self.faces[face as usize];
+                if face.contains(&apex) {
+                    continue;
+                }
+                for i in 1..face.len() - 1 {
+                    let mut indices = [apex, face[0], face[i], face[i + 1]];
+                    let tetrahedron = Tetrahedron {
+                        vertices: indices.map(|vertex| self.vertices[vertex as usize]),
+                    };
+                    let tetrahedron_center =
+                        tetrahedron.vertices.iter().sum::<cgmath::Vector4<f32>>() / 4.0;
+                    if tetrahedron
+                        .unnormalized_normal()
+                        .dot(tetrahedron_center - center)
+                        < 0.0
+                    {
+                        indices.swap(1, 2);
+                    }
+                    tetrahedra.push(indices);
+                    tetrahedron_cells.push(cell);
+                }
+            }
+        }
+        (tetrahedra, tetrahedron_cells)
+    }
+
+    /// A renderable object for the polytope, where the tetrahedra of each cell use `cell_material(cell)`
+    pub fn tetrahedron_mesh(
+        &self,
+        position: cgmath::Vector4<f32>,
+        cell_material: impl Fn(usize) -> u32,
+    ) -> Result<TetrahedronMesh, MeshError> {
+        let (tetrahedra, tetrahedron_cells) = self.tetrahedra();
+        Ok(TetrahedronMesh {
+            position,
+            rotation: Rotor::IDENTITY,
+            mesh: Arc::new(Mesh::new(self.vertices.clone(), tetrahedra)?),
+            material: cell_material(0),
+            cell_materials: tetrahedron_cells.into_iter().map(cell_material).collect(),
+        })
+    }
+}
+
+/// Sorts the vertices of a flat convex polygon so they go around it
+fn polygon_order(vertices: &[cgmath::Vector4<f32>], mut polygon: Vec<u32>) -> Vec<u32> {
+    let position = |vertex: u32| vertices[vertex as usize];
+    let center = polygon
+        .iter()
+        .map(|&vertex| position(vertex))
+        .sum::<cgmath::Vector4<f32>>()
+        / polygon.len() as f32;
+
+    // two perpendicular directions in the plane of the polygon, the second one is from the vertex furthest from the line of the first
+    let u = (position(polygon[0]) - center).normalize();
+    let v = polygon
+        .iter()
+        .map(|&vertex| {
+            let offset = position(vertex) - center;
+            offset - u * offset.dot(u)
+        })
+        .max_by(|a, b| a.magnitude2().total_cmp(&b.magnitude2()))
+        .expect("a polygon has vertices")
+        .normalize();
+
+    let angle = |vertex: u32| {
+        let offset = position(vertex) - center;
+        offset.dot(v).atan2(offset.dot(u))
+    };
+    polygon.sort_by(|&a, &b| angle(a).total_cmp(&angle(b)));
+    polygon
+}
+
+fn parse_numbers<T: std::str::FromStr>(
+    line: usize,
+    words: &[&str],
+) -> Result<Vec<T>, PolytopeError> {
+    words
+        .iter()
+        .map(|word| {
+            word.parse().map_err(|_| PolytopeError::Off {
+                line,
+                message: format!("{word} isnt a valid number"),
+            })
+        })
+        .collect()
+}
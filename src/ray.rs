@@ -1,5 +1,8 @@
-use crate::objects::{HyperPlane, HyperSphere, Object};
-use cgmath::InnerSpace;
+use crate::objects::{
+    Csg, CsgOperation, HyperCube, HyperCylinder, HyperCylinderFactor, HyperPlane, HyperSphere,
+    HyperTorus, Object, Sdf, TetrahedronMesh,
+};
+use cgmath::{ElementWise, InnerSpace, Zero};
 use enum_dispatch::enum_dispatch;
 
 #[derive(Debug, Clone, Copy)]
@@ -12,13 +15,195 @@ pub struct Ray {
 pub struct Hit {
     pub distance: f32,
     pub position: cgmath::Vector4<f32>,
+    /// Always points back towards the side the ray came from
     pub normal: cgmath::Vector4<f32>,
+    /// Whether the ray hit the outside of the object, rather than hitting it from the inside
+    pub front_face: bool,
     pub material: u32,
 }
 
+/// One end of a `Span`
+#[derive(Debug, Clone, Copy)]
+pub struct SpanBoundary {
+    pub distance: f32,
+    /// Points out of the solid, this is zero for the ends of the ray
+    pub normal: cgmath::Vector4<f32>,
+    pub material: u32,
+}
+
+impl SpanBoundary {
+    const RAY_START: SpanBoundary = SpanBoundary {
+        distance: 0.0,
+        normal: cgmath::Vector4::new(0.0, 0.0, 0.0, 0.0),
+        material: 0,
+    };
+    const RAY_END: SpanBoundary = SpanBoundary {
+        distance: f32::INFINITY,
+        normal: cgmath::Vector4::new(0.0, 0.0, 0.0, 0.0),
+        material: 0,
+    };
+
+    /// The same boundary for the solid on the other side of it
+    fn flipped(self) -> SpanBoundary {
+        SpanBoundary {
+            normal: -self.normal,
+            ..self
+        }
+    }
+}
+
+/// A part of a ray that is inside of a solid
+#[derive(Debug, Clone, Copy)]
+pub struct Span {
+    pub start: SpanBoundary,
+    pub end: SpanBoundary,
+}
+
 #[enum_dispatch]
 pub trait RayIntersect {
     fn intersect(&self, ray: Ray) -> Option<Hit>;
+
+    /// The parts of the ray in front of its origin that are inside the object, in order.
+    /// This treats hyper planes as the half space behind their normal
+    fn spans(&self, ray: Ray) -> Vec<Span>;
+}
+
+/// Adds the part of the span that is in front of the ray origin, if there is any of it
+fn push_span_in_front(spans: &mut Vec<Span>, start: SpanBoundary, end: SpanBoundary) {
+    let start = if start.distance < 0.0 {
+        SpanBoundary::RAY_START
+    } else {
+        start
+    };
+    push_span(spans, start, end);
+}
+
+fn push_span(spans: &mut Vec<Span>, start: SpanBoundary, end: SpanBoundary) {
+    if start.distance < end.distance {
+        spans.push(Span { start, end });
+    }
+}
+
+/// The parts of the ray in front of its origin that arent in `spans`
+fn complement(spans: &[Span]) -> Vec<Span> {
+    let mut result = vec![];
+    let mut start = SpanBoundary::RAY_START;
+    for span in spans {
+        push_span(&mut result, start, span.start.flipped());
+        start = span.end.flipped();
+    }
+    push_span(&mut result, start, SpanBoundary::RAY_END);
+    result
+}
+
+fn intersection(a: &[Span], b: &[Span]) -> Vec<Span> {
+    let mut result = vec![];
+    for a in a {
+        for b in b {
+            let start = if a.start.distance >= b.start.distance {
+                a.start
+            } else {
+                b.start
+            };
+            let end = if a.end.distance <= b.end.distance {
+                a.end
+            } else {
+                b.end
+            };
+            push_span(&mut result, start, end);
+        }
+    }
+    result
+}
+
+/// Where the ray goes into and out of a hyper cylinder, along with the index of the factor that each of those is on the surface of.
+/// The ray needs to be in the local space of the hyper cylinder
+fn hyper_cylinder_interval(
+    factors: &[HyperCylinderFactor; 3],
+    ray: Ray,
+) -> Option<((f32, usize), (f32, usize))> {
+    let mut near = (f32::NEG_INFINITY, 0);
+    let mut far = (f32::INFINITY, 0);
+    for (index, factor) in factors.iter().enumerate() {
+        // the same as a hyper sphere, but only along the axes of the factor
+        let origin = ray.origin.mul_element_wise(factor.axes);
+        let direction = ray.direction.mul_element_wise(factor.axes);
+        let a = direction.dot(direction);
+        let h = -direction.dot(origin);
+        let c = origin.dot(origin) - factor.radius * factor.radius;
+        if a == 0.0 {
+            // the ray is parallel to the factor, so it is either all inside or all outside
+            if c > 0.0 {
+                return None;
+            }
+            continue;
+        }
+
+        let discriminant = h * h - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let factor_near = (h - discriminant.sqrt()) / a;
+        let factor_far = (h + discriminant.sqrt()) / a;
+        if factor_near > near.0 {
+            near = (factor_near, index);
+        }
+        if factor_far < far.0 {
+            far = (factor_far, index);
+        }
+    }
+    (near.0 <= far.0).then_some((near, far))
+}
+
+/// How many steps a ray can take through a signed distance field looking for its surface, the same as `ray_tracing.wgsl`
+const SDF_MAX_STEPS: u32 = 256;
+/// Rays stop looking for the surface of a signed distance field once they get this far
+const SDF_MAX_DISTANCE: f32 = 1000.0;
+/// The smallest step a ray takes through a signed distance field, so it gets through the surface instead of only getting closer
+const SDF_MIN_STEP: f32 = 0.0001;
+
+/// Steps along the ray from `distance` until it goes through the surface of the sdf, returning how far along the ray that was.
+/// `direction` needs to be normalized
+fn sdf_crossing(
+    sdf: &Sdf,
+    origin: cgmath::Vector4<f32>,
+    direction: cgmath::Vector4<f32>,
+    mut distance: f32,
+    inside: bool,
+) -> Option<f32> {
+    for _ in 0..SDF_MAX_STEPS {
+        let sdf_distance = sdf.distance(origin + direction * distance);
+        if (sdf_distance < 0.0) != inside {
+            return Some(distance);
+        }
+        distance += sdf_distance.abs().max(SDF_MIN_STEP);
+        if distance > SDF_MAX_DISTANCE {
+            return None;
+        }
+    }
+    None
+}
+
+/// Returns the index of the closest object hit by the ray, along with the hit
+pub fn closest_hit(objects: &[Object], ray: Ray) -> Option<(usize, Hit)> {
+    objects
+        .iter()
+        .enumerate()
+        .fold(None, |current_hit, (index, object)| {
+            let hit = object.intersect(ray);
+            match (current_hit, hit) {
+                (None, None) => None,
+                (None, Some(hit)) => Some((index, hit)),
+                (Some(_), None) => current_hit,
+                (Some((current_index, current_hit)), Some(hit)) => {
+                    if current_hit.distance < hit.distance {
+                        Some((current_index, current_hit))
+                    } else {
+                        Some((index, hit))
+                    }
+                }
+            }
+        })
 }
 
 impl RayIntersect for HyperSphere {
@@ -34,26 +219,65 @@ impl RayIntersect for HyperSphere {
             return None;
         }
 
-        let distance = (h - discriminant.sqrt()) / a;
+        let mut distance = (h - discriminant.sqrt()) / a;
+        let mut front_face = true;
+        if distance <= 0.0 {
+            // the ray might have started inside the hyper sphere
+            distance = (h + discriminant.sqrt()) / a;
+            front_face = false;
+        }
         if distance <= 0.0 {
             return None;
         }
 
         let position = ray.origin + ray.direction * distance;
-        let normal = (position - self.position) / self.radius;
+        let outward_normal = (position - self.position) / self.radius;
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
         let material = self.material;
         Some(Hit {
             distance,
             position,
             normal,
+            front_face,
             material,
         })
     }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let oc = self.position - ray.origin;
+        let a = ray.direction.dot(ray.direction);
+        let h = ray.direction.dot(oc);
+        let c = oc.dot(oc) - self.radius * self.radius;
+        let discriminant = h * h - a * c;
+
+        let mut spans = vec![];
+        if discriminant >= 0.0 {
+            let boundary = |distance: f32| SpanBoundary {
+                distance,
+                normal: (ray.origin + ray.direction * distance - self.position) / self.radius,
+                material: self.material,
+            };
+            push_span_in_front(
+                &mut spans,
+                boundary((h - discriminant.sqrt()) / a),
+                boundary((h + discriminant.sqrt()) / a),
+            );
+        }
+        spans
+    }
 }
 
 impl RayIntersect for HyperPlane {
     fn intersect(&self, ray: Ray) -> Option<Hit> {
         let denom = self.normal.dot(ray.direction);
+        if denom.abs() <= 0.00001 {
+            return None;
+        }
+
         let distance = (self.position - ray.origin).dot(self.normal) / denom;
         if distance <= 0.0 {
             return None;
@@ -61,12 +285,369 @@ impl RayIntersect for HyperPlane {
 
         let position = ray.origin + ray.direction * distance;
         let normal = self.normal * -denom.signum();
+        let front_face = denom < 0.0;
+        let material = self.material;
+        Some(Hit {
+            distance,
+            position,
+            normal,
+            front_face,
+            material,
+        })
+    }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let denom = self.normal.dot(ray.direction);
+        let offset = (self.position - ray.origin).dot(self.normal);
+
+        let mut spans = vec![];
+        if denom.abs() <= 0.00001 {
+            // the ray is parallel to the hyper plane, so it is either all inside or all outside
+            if offset >= 0.0 {
+                push_span(&mut spans, SpanBoundary::RAY_START, SpanBoundary::RAY_END);
+            }
+        } else {
+            let boundary = SpanBoundary {
+                distance: offset / denom,
+                normal: self.normal,
+                material: self.material,
+            };
+            if denom < 0.0 {
+                push_span_in_front(&mut spans, boundary, SpanBoundary::RAY_END);
+            } else {
+                push_span_in_front(&mut spans, SpanBoundary::RAY_START, boundary);
+            }
+        }
+        spans
+    }
+}
+
+impl RayIntersect for HyperCube {
+    fn intersect(&self, ray: Ray) -> Option<Hit> {
+        // do the intersection in the local space of the hyper cube, where it is axis aligned
+        let inverse_rotation = !self.rotation;
+        let origin = inverse_rotation.rotate(ray.origin - self.position);
+        let direction = inverse_rotation.rotate(ray.direction);
+
+        let mut near = f32::NEG_INFINITY;
+        let mut far = f32::INFINITY;
+        let mut near_axis = 0;
+        let mut far_axis = 0;
+        for axis in 0..4 {
+            let inverse_direction = direction[axis].recip();
+            let a = (-self.half_extents[axis] - origin[axis]) * inverse_direction;
+            let b = (self.half_extents[axis] - origin[axis]) * inverse_direction;
+            let (axis_near, axis_far) = if a < b { (a, b) } else { (b, a) };
+            if axis_near > near {
+                near = axis_near;
+                near_axis = axis;
+            }
+            if axis_far < far {
+                far = axis_far;
+                far_axis = axis;
+            }
+        }
+
+        if near > far {
+            return None;
+        }
+
+        // if the ray started inside the hyper cube, it hits the far side from the inside
+        let (distance, axis, front_face) = if near > 0.0 {
+            (near, near_axis, true)
+        } else {
+            (far, far_axis, false)
+        };
+        if distance <= 0.0 {
+            return None;
+        }
+
+        let position = ray.origin + ray.direction * distance;
+        let mut normal = cgmath::Vector4::zero();
+        normal[axis] = -direction[axis].signum();
+        let normal = self.rotation.rotate(normal);
+        let material = self.material;
+        Some(Hit {
+            distance,
+            position,
+            normal,
+            front_face,
+            material,
+        })
+    }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let inverse_rotation = !self.rotation;
+        let origin = inverse_rotation.rotate(ray.origin - self.position);
+        let direction = inverse_rotation.rotate(ray.direction);
+
+        let mut near = (f32::NEG_INFINITY, 0);
+        let mut far = (f32::INFINITY, 0);
+        for axis in 0..4 {
+            let inverse_direction = direction[axis].recip();
+            let a = (-self.half_extents[axis] - origin[axis]) * inverse_direction;
+            let b = (self.half_extents[axis] - origin[axis]) * inverse_direction;
+            let (axis_near, axis_far) = if a < b { (a, b) } else { (b, a) };
+            if axis_near > near.0 {
+                near = (axis_near, axis);
+            }
+            if axis_far < far.0 {
+                far = (axis_far, axis);
+            }
+        }
+
+        let mut spans = vec![];
+        if near.0 <= far.0 {
+            let boundary = |(distance, axis): (f32, usize), sign: f32| {
+                let mut normal = cgmath::Vector4::zero();
+                normal[axis] = sign * direction[axis].signum();
+                SpanBoundary {
+                    distance,
+                    normal: self.rotation.rotate(normal),
+                    material: self.material,
+                }
+            };
+            push_span_in_front(&mut spans, boundary(near, -1.0), boundary(far, 1.0));
+        }
+        spans
+    }
+}
+
+impl RayIntersect for HyperCylinder {
+    fn intersect(&self, ray: Ray) -> Option<Hit> {
+        // do the intersection in the local space of the hyper cylinder, where its factors are along the axes
+        let inverse_rotation = !self.rotation;
+        let local_ray = Ray {
+            origin: inverse_rotation.rotate(ray.origin - self.position),
+            direction: inverse_rotation.rotate(ray.direction),
+        };
+        let factors = self.factors();
+        let (near, far) = hyper_cylinder_interval(&factors, local_ray)?;
+
+        // if the ray started inside the hyper cylinder, it hits the far side from the inside
+        let ((distance, factor), front_face) = if near.0 > 0.0 {
+            (near, true)
+        } else {
+            (far, false)
+        };
+        if distance <= 0.0 || distance == f32::INFINITY {
+            return None;
+        }
+
+        let position = ray.origin + ray.direction * distance;
+        let outward_normal = self
+            .rotation
+            .rotate(factors[factor].normal(local_ray.origin + local_ray.direction * distance));
+        let normal = if front_face {
+            outward_normal
+        } else {
+            -outward_normal
+        };
         let material = self.material;
         Some(Hit {
             distance,
             position,
             normal,
+            front_face,
             material,
         })
     }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let inverse_rotation = !self.rotation;
+        let local_ray = Ray {
+            origin: inverse_rotation.rotate(ray.origin - self.position),
+            direction: inverse_rotation.rotate(ray.direction),
+        };
+        let factors = self.factors();
+
+        let mut spans = vec![];
+        if let Some((near, far)) = hyper_cylinder_interval(&factors, local_ray) {
+            let boundary = |(distance, factor): (f32, usize)| SpanBoundary {
+                distance,
+                normal: self.rotation.rotate(
+                    factors[factor].normal(local_ray.origin + local_ray.direction * distance),
+                ),
+                material: self.material,
+            };
+            // rays going along w can stay inside the shapes that go on forever along it
+            let end = if far.0 == f32::INFINITY {
+                SpanBoundary::RAY_END
+            } else {
+                boundary(far)
+            };
+            push_span_in_front(&mut spans, boundary(near), end);
+        }
+        spans
+    }
+}
+
+impl RayIntersect for HyperTorus {
+    fn intersect(&self, ray: Ray) -> Option<Hit> {
+        self.sdf().intersect(ray)
+    }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        self.sdf().spans(ray)
+    }
+}
+
+impl TetrahedronMesh {
+    /// The ray in the local space of the mesh, where its cells are
+    fn local_ray(&self, ray: Ray) -> Ray {
+        let inverse_rotation = !self.rotation;
+        Ray {
+            origin: inverse_rotation.rotate(ray.origin - self.position),
+            direction: inverse_rotation.rotate(ray.direction),
+        }
+    }
+}
+
+impl RayIntersect for TetrahedronMesh {
+    fn intersect(&self, ray: Ray) -> Option<Hit> {
+        let local_ray = self.local_ray(ray);
+        let (distance, cell) = self.mesh.closest_crossing(local_ray)?;
+
+        // the cells face out of the mesh, so the ray is going into it if it is going against the normal
+        let local_normal = self.mesh.normals()[cell];
+        let front_face = local_ray.direction.dot(local_normal) < 0.0;
+        let outward_normal = self.rotation.rotate(local_normal);
+        Some(Hit {
+            distance,
+            position: ray.origin + ray.direction * distance,
+            normal: if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            },
+            front_face,
+            material: self.cell_material(cell),
+        })
+    }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let local_ray = self.local_ray(ray);
+        let crossings = self.mesh.crossings(local_ray);
+        let entering = |cell: usize| local_ray.direction.dot(self.mesh.normals()[cell]) < 0.0;
+
+        let mut spans = vec![];
+        // the ray started inside if the first cell it goes through is on its way out
+        let mut start = match crossings.first() {
+            Some(&(_, cell)) if !entering(cell) => Some(SpanBoundary::RAY_START),
+            _ => None,
+        };
+        for (distance, cell) in crossings {
+            let boundary = SpanBoundary {
+                distance,
+                normal: self.rotation.rotate(self.mesh.normals()[cell]),
+                material: self.cell_material(cell),
+            };
+            match (start, entering(cell)) {
+                (None, true) => start = Some(boundary),
+                (Some(span_start), false) => {
+                    push_span(&mut spans, span_start, boundary);
+                    start = None;
+                }
+                // going through the edge between two cells crosses both of them,
+                // and meshes that arent closed can be crossed the same way twice in a row
+                _ => {}
+            }
+        }
+        if let Some(start) = start {
+            push_span(&mut spans, start, SpanBoundary::RAY_END);
+        }
+        spans
+    }
+}
+
+impl RayIntersect for Csg {
+    fn intersect(&self, ray: Ray) -> Option<Hit> {
+        let span = self.spans(ray).into_iter().next()?;
+        let (boundary, front_face) = if span.start.distance > 0.0 {
+            (span.start, true)
+        } else {
+            (span.end, false)
+        };
+        if boundary.distance == f32::INFINITY {
+            // the ray started inside something infinite, and never left it
+            return None;
+        }
+
+        Some(Hit {
+            distance: boundary.distance,
+            position: ray.origin + ray.direction * boundary.distance,
+            normal: if front_face {
+                boundary.normal
+            } else {
+                -boundary.normal
+            },
+            front_face,
+            material: boundary.material,
+        })
+    }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let left = self.left.spans(ray);
+        let right = self.right.spans(ray);
+        match self.operation {
+            CsgOperation::Union => {
+                complement(&intersection(&complement(&left), &complement(&right)))
+            }
+            CsgOperation::Intersection => intersection(&left, &right),
+            CsgOperation::Difference => intersection(&left, &complement(&right)),
+        }
+    }
+}
+
+impl RayIntersect for Sdf {
+    fn intersect(&self, ray: Ray) -> Option<Hit> {
+        // the distance field is measured in world units, so the ray needs to be too
+        let length = ray.direction.magnitude();
+        let direction = ray.direction / length;
+
+        let inside = self.distance(ray.origin) < 0.0;
+        let distance = sdf_crossing(self, ray.origin, direction, 0.0, inside)? / length;
+
+        let position = ray.origin + ray.direction * distance;
+        let outward_normal = self.normal(position);
+        Some(Hit {
+            distance,
+            position,
+            normal: if inside {
+                -outward_normal
+            } else {
+                outward_normal
+            },
+            front_face: !inside,
+            material: self.material,
+        })
+    }
+
+    fn spans(&self, ray: Ray) -> Vec<Span> {
+        let length = ray.direction.magnitude();
+        let direction = ray.direction / length;
+
+        let mut spans = vec![];
+        let mut inside = self.distance(ray.origin) < 0.0;
+        let mut start = SpanBoundary::RAY_START;
+        let mut distance = 0.0;
+        while let Some(crossing) = sdf_crossing(self, ray.origin, direction, distance, inside) {
+            let boundary = SpanBoundary {
+                distance: crossing / length,
+                normal: self.normal(ray.origin + direction * crossing),
+                material: self.material,
+            };
+            if inside {
+                push_span(&mut spans, start, boundary);
+            } else {
+                start = boundary;
+            }
+            inside = !inside;
+            distance = crossing;
+        }
+        if inside {
+            push_span(&mut spans, start, SpanBoundary::RAY_END);
+        }
+        spans
+    }
 }
@@ -0,0 +1,211 @@
+use crate::{
+    camera::Camera,
+    material::Material,
+    math::Rotor,
+    objects::{HyperCube, HyperPlane, HyperSphere, Object},
+};
+use ron::{extensions::Extensions, ser::PrettyConfig};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Lighting {
+    pub sun_direction: cgmath::Vector4<f32>,
+    pub sun_color: cgmath::Vector3<f32>,
+    pub sun_light_color: cgmath::Vector3<f32>,
+    pub ambient_light_color: cgmath::Vector3<f32>,
+    pub up_sky_color: cgmath::Vector3<f32>,
+    pub down_sky_color: cgmath::Vector3<f32>,
+}
+
+impl Lighting {
+    pub fn from_camera(camera: &Camera) -> Self {
+        let Camera {
+            sun_direction,
+            sun_color,
+            sun_light_color,
+            ambient_light_color,
+            up_sky_color,
+            down_sky_color,
+            ..
+        } = *camera;
+        Self {
+            sun_direction,
+            sun_color,
+            sun_light_color,
+            ambient_light_color,
+            up_sky_color,
+            down_sky_color,
+        }
+    }
+
+    pub fn apply_to_camera(&self, camera: &mut Camera) {
+        let Self {
+            sun_direction,
+            sun_color,
+            sun_light_color,
+            ambient_light_color,
+            up_sky_color,
+            down_sky_color,
+        } = *self;
+        camera.sun_direction = sun_direction;
+        camera.sun_color = sun_color;
+        camera.sun_light_color = sun_light_color;
+        camera.ambient_light_color = ambient_light_color;
+        camera.up_sky_color = up_sky_color;
+        camera.down_sky_color = down_sky_color;
+    }
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self::from_camera(&Camera::default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Scene {
+    #[serde(default)]
+    pub lighting: Lighting,
+    pub materials: Vec<Material>,
+    pub objects: Vec<Object>,
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// An object uses a material index past the end of the materials in the scene
+    MissingMaterial {
+        object: usize,
+        material: u32,
+        material_count: usize,
+    },
+    Serialize(ron::Error),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SceneError::Io(error) => write!(f, "{error}"),
+            SceneError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "{line}:{column}: {message}"),
+            SceneError::MissingMaterial {
+                object,
+                material,
+                material_count,
+            } => write!(
+                f,
+                "object {object} uses material {material}, but there are only {material_count} materials",
+            ),
+            SceneError::Serialize(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<std::io::Error> for SceneError {
+    fn from(error: std::io::Error) -> Self {
+        SceneError::Io(error)
+    }
+}
+
+impl Scene {
+    fn ron_options() -> ron::Options {
+        ron::Options::default().with_default_extension(Extensions::UNWRAP_VARIANT_NEWTYPES)
+    }
+
+    pub fn from_ron(source: &str) -> Result<Self, SceneError> {
+        let scene: Self =
+            Self::ron_options()
+                .from_str(source)
+                .map_err(|error| SceneError::Parse {
+                    line: error.position.line,
+                    column: error.position.col,
+                    message: error.code.to_string(),
+                })?;
+
+        // the renderers index the materials without checking, so a bad index has to be caught here
+        let material_count = scene.materials.len();
+        for (index, object) in scene.objects.iter().enumerate() {
+            if let Some(material) = object
+                .materials()
+                .into_iter()
+                .find(|&material| material as usize >= material_count)
+            {
+                return Err(SceneError::MissingMaterial {
+                    object: index,
+                    material,
+                    material_count,
+                });
+            }
+        }
+        Ok(scene)
+    }
+
+    pub fn to_ron(&self) -> Result<String, SceneError> {
+        Self::ron_options()
+            .to_string_pretty(self, PrettyConfig::default())
+            .map_err(SceneError::Serialize)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SceneError> {
+        Self::from_ron(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SceneError> {
+        std::fs::write(path, self.to_ron()?)?;
+        Ok(())
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self {
+            lighting: Lighting::default(),
+            materials: vec![
+                Material::diffuse(cgmath::vec3(0.1, 0.6, 0.2)),
+                Material::diffuse(cgmath::vec3(0.8, 0.2, 0.1)),
+                Material::diffuse(cgmath::vec3(0.2, 0.8, 0.3)),
+                Material::diffuse(cgmath::vec3(0.1, 0.2, 0.8)),
+                Material::diffuse(cgmath::vec3(0.8, 0.7, 0.2)),
+            ],
+            objects: vec![
+                Object::HyperPlane(HyperPlane {
+                    position: cgmath::vec4(0.0, -1.0, 0.0, 0.0),
+                    normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                    material: 0,
+                }),
+                Object::HyperSphere(HyperSphere {
+                    position: cgmath::vec4(3.0, 0.0, 0.0, 0.0),
+                    radius: 1.0,
+                    material: 1,
+                }),
+                Object::HyperSphere(HyperSphere {
+                    position: cgmath::vec4(3.0, 0.0, 2.0, 0.0),
+                    radius: 1.0,
+                    material: 2,
+                }),
+                Object::HyperSphere(HyperSphere {
+                    position: cgmath::vec4(3.0, 0.0, -2.0, 2.0),
+                    radius: 1.0,
+                    material: 3,
+                }),
+                Object::HyperCube(HyperCube {
+                    position: cgmath::vec4(5.0, 0.0, 0.0, -2.0),
+                    half_extents: cgmath::vec4(0.5, 1.0, 0.5, 0.5),
+                    rotation: Rotor::rotation_xz(0.5) * Rotor::rotation_yw(0.3),
+                    material: 4,
+                }),
+            ],
+        }
+    }
+}
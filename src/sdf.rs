@@ -0,0 +1,366 @@
+//! Signed distance fields, for shapes that are much easier to describe by how far away a point is from them
+//! than by where a ray hits them. `ray_tracing.wgsl` has the same functions for rendering them on the gpu
+
+use crate::{bvh::Aabb, math::Rotor, objects::CsgOperation};
+use cgmath::InnerSpace;
+use serde::{Deserialize, Serialize};
+
+/// A shape centered on the origin, described by its distance function
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SdfShape {
+    HyperSphere {
+        radius: f32,
+    },
+    /// A hyper cube with its edges and corners rounded off by `radius`, this doesnt change its size
+    RoundedBox {
+        half_extents: cgmath::Vector4<f32>,
+        #[serde(default)]
+        radius: f32,
+    },
+    /// A ball in xyz stretched along w
+    Spherinder {
+        radius: f32,
+        half_height: f32,
+    },
+    /// A disk in xy stretched along z and w
+    Cubinder {
+        radius: f32,
+        half_extents: cgmath::Vector2<f32>,
+    },
+    /// A disk in xy times a disk in zw
+    Duocylinder {
+        radii: cgmath::Vector2<f32>,
+    },
+    /// Everything within `minor_radius` of a circle in xy
+    Spheritorus {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// Everything within `minor_radius` of a sphere in xyz
+    Torisphere {
+        major_radius: f32,
+        minor_radius: f32,
+    },
+    /// Everything within `minor_radius` of the surface made from a circle in xy with radius `major_radii.x`
+    /// and a circle in zw with radius `major_radii.y`
+    Tiger {
+        major_radii: cgmath::Vector2<f32>,
+        minor_radius: f32,
+    },
+    /// Everything within `minor_radius` of the surface of a torus in xyz
+    Ditorus {
+        major_radius: f32,
+        middle_radius: f32,
+        minor_radius: f32,
+    },
+    /// Combines two shapes as solids, blending them together over a distance of `smoothness`
+    Combine {
+        operation: CsgOperation,
+        left: Box<SdfShape>,
+        right: Box<SdfShape>,
+        #[serde(default)]
+        smoothness: f32,
+    },
+    /// Moves and rotates a shape away from the origin
+    Transformed {
+        position: cgmath::Vector4<f32>,
+        #[serde(default)]
+        rotation: Rotor,
+        shape: Box<SdfShape>,
+    },
+    /// Repeats a shape forever, once every `period` along each axis (an axis with a period of 0 isnt repeated).
+    /// The shape has to fit inside a single period, otherwise parts of it will be missing
+    Repeat {
+        period: cgmath::Vector4<f32>,
+        shape: Box<SdfShape>,
+    },
+}
+
+impl SdfShape {
+    /// The distance from the point to the surface of the shape, negative for points inside of it.
+    /// This can be an underestimate, but never an overestimate, so rays can always step this far without missing anything
+    pub fn distance(&self, point: cgmath::Vector4<f32>) -> f32 {
+        match *self {
+            SdfShape::HyperSphere { radius } => hyper_sphere_distance(point, radius),
+            SdfShape::RoundedBox {
+                half_extents,
+                radius,
+            } => rounded_box_distance(point, half_extents, radius),
+            SdfShape::Spherinder {
+                radius,
+                half_height,
+            } => spherinder_distance(point, radius, half_height),
+            SdfShape::Cubinder {
+                radius,
+                half_extents,
+            } => cubinder_distance(point, radius, half_extents),
+            SdfShape::Duocylinder { radii } => duocylinder_distance(point, radii),
+            SdfShape::Spheritorus {
+                major_radius,
+                minor_radius,
+            } => spheritorus_distance(point, major_radius, minor_radius),
+            SdfShape::Torisphere {
+                major_radius,
+                minor_radius,
+            } => torisphere_distance(point, major_radius, minor_radius),
+            SdfShape::Tiger {
+                major_radii,
+                minor_radius,
+            } => tiger_distance(point, major_radii, minor_radius),
+            SdfShape::Ditorus {
+                major_radius,
+                middle_radius,
+                minor_radius,
+            } => ditorus_distance(point, major_radius, middle_radius, minor_radius),
+            SdfShape::Combine {
+                operation,
+                ref left,
+                ref right,
+                smoothness,
+            } => combine_distances(
+                operation,
+                left.distance(point),
+                right.distance(point),
+                smoothness,
+            ),
+            SdfShape::Transformed {
+                position,
+                rotation,
+                ref shape,
+            } => shape.distance((!rotation).rotate(point - position)),
+            SdfShape::Repeat { period, ref shape } => shape.distance(repeat_point(point, period)),
+        }
+    }
+
+    /// The bounding box of the shape, repeated shapes go on forever so they dont have one
+    pub fn bounds(&self) -> Option<Aabb> {
+        let symmetric = |half_extents: cgmath::Vector4<f32>| Aabb {
+            min: -half_extents,
+            max: half_extents,
+        };
+        Some(match *self {
+            SdfShape::HyperSphere { radius } => {
+                symmetric(cgmath::vec4(1.0, 1.0, 1.0, 1.0) * radius)
+            }
+            SdfShape::RoundedBox { half_extents, .. } => symmetric(half_extents),
+            SdfShape::Spherinder {
+                radius,
+                half_height,
+            } => symmetric(cgmath::vec4(radius, radius, radius, half_height)),
+            SdfShape::Cubinder {
+                radius,
+                half_extents,
+            } => symmetric(cgmath::vec4(radius, radius, half_extents.x, half_extents.y)),
+            SdfShape::Duocylinder { radii } => {
+                symmetric(cgmath::vec4(radii.x, radii.x, radii.y, radii.y))
+            }
+            SdfShape::Spheritorus {
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + minor_radius;
+                symmetric(cgmath::vec4(outer, outer, minor_radius, minor_radius))
+            }
+            SdfShape::Torisphere {
+                major_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + minor_radius;
+                symmetric(cgmath::vec4(outer, outer, outer, minor_radius))
+            }
+            SdfShape::Tiger {
+                major_radii,
+                minor_radius,
+            } => {
+                let outer = major_radii + cgmath::vec2(minor_radius, minor_radius);
+                symmetric(cgmath::vec4(outer.x, outer.x, outer.y, outer.y))
+            }
+            SdfShape::Ditorus {
+                major_radius,
+                middle_radius,
+                minor_radius,
+            } => {
+                let outer = major_radius + middle_radius + minor_radius;
+                symmetric(cgmath::vec4(
+                    outer,
+                    outer,
+                    middle_radius + minor_radius,
+                    minor_radius,
+                ))
+            }
+            SdfShape::Combine {
+                operation,
+                ref left,
+                ref right,
+                smoothness,
+            } => match operation {
+                CsgOperation::Union => {
+                    // blending only ever adds to the shapes, and never by more than a quarter of the smoothness
+                    let margin = cgmath::vec4(1.0, 1.0, 1.0, 1.0) * (smoothness.max(0.0) * 0.25);
+                    let bounds = left.bounds()?.union(right.bounds()?);
+                    Aabb {
+                        min: bounds.min - margin,
+                        max: bounds.max + margin,
+                    }
+                }
+                CsgOperation::Intersection => match (left.bounds(), right.bounds()) {
+                    (Some(left), Some(right)) => left.intersection(right),
+                    (bounds, None) | (None, bounds) => bounds?,
+                },
+                CsgOperation::Difference => left.bounds()?,
+            },
+            SdfShape::Transformed {
+                position,
+                rotation,
+                ref shape,
+            } => shape.bounds()?.transformed(rotation, position),
+            SdfShape::Repeat { .. } => return None,
+        })
+    }
+}
+
+pub fn hyper_sphere_distance(point: cgmath::Vector4<f32>, radius: f32) -> f32 {
+    point.magnitude() - radius
+}
+
+pub fn rounded_box_distance(
+    point: cgmath::Vector4<f32>,
+    half_extents: cgmath::Vector4<f32>,
+    radius: f32,
+) -> f32 {
+    let radius = radius.clamp(
+        0.0,
+        half_extents
+            .x
+            .min(half_extents.y)
+            .min(half_extents.z)
+            .min(half_extents.w),
+    );
+    let offset = cgmath::vec4(
+        point.x.abs() - half_extents.x + radius,
+        point.y.abs() - half_extents.y + radius,
+        point.z.abs() - half_extents.z + radius,
+        point.w.abs() - half_extents.w + radius,
+    );
+    let outside = cgmath::vec4(
+        offset.x.max(0.0),
+        offset.y.max(0.0),
+        offset.z.max(0.0),
+        offset.w.max(0.0),
+    );
+    let inside = offset.x.max(offset.y).max(offset.z).max(offset.w).min(0.0);
+    outside.magnitude() + inside - radius
+}
+
+pub fn spherinder_distance(point: cgmath::Vector4<f32>, radius: f32, half_height: f32) -> f32 {
+    extruded_distance(
+        point.truncate().magnitude() - radius,
+        point.w.abs() - half_height,
+    )
+}
+
+pub fn cubinder_distance(
+    point: cgmath::Vector4<f32>,
+    radius: f32,
+    half_extents: cgmath::Vector2<f32>,
+) -> f32 {
+    let disk = cgmath::vec2(point.x, point.y).magnitude() - radius;
+    let z = point.z.abs() - half_extents.x;
+    let w = point.w.abs() - half_extents.y;
+    let outside = cgmath::vec3(disk.max(0.0), z.max(0.0), w.max(0.0));
+    outside.magnitude() + disk.max(z).max(w).min(0.0)
+}
+
+pub fn duocylinder_distance(point: cgmath::Vector4<f32>, radii: cgmath::Vector2<f32>) -> f32 {
+    extruded_distance(
+        cgmath::vec2(point.x, point.y).magnitude() - radii.x,
+        cgmath::vec2(point.z, point.w).magnitude() - radii.y,
+    )
+}
+
+pub fn spheritorus_distance(
+    point: cgmath::Vector4<f32>,
+    major_radius: f32,
+    minor_radius: f32,
+) -> f32 {
+    cgmath::vec3(
+        cgmath::vec2(point.x, point.y).magnitude() - major_radius,
+        point.z,
+        point.w,
+    )
+    .magnitude()
+        - minor_radius
+}
+
+pub fn torisphere_distance(
+    point: cgmath::Vector4<f32>,
+    major_radius: f32,
+    minor_radius: f32,
+) -> f32 {
+    cgmath::vec2(point.truncate().magnitude() - major_radius, point.w).magnitude() - minor_radius
+}
+
+pub fn tiger_distance(
+    point: cgmath::Vector4<f32>,
+    major_radii: cgmath::Vector2<f32>,
+    minor_radius: f32,
+) -> f32 {
+    cgmath::vec2(
+        cgmath::vec2(point.x, point.y).magnitude() - major_radii.x,
+        cgmath::vec2(point.z, point.w).magnitude() - major_radii.y,
+    )
+    .magnitude()
+        - minor_radius
+}
+
+pub fn ditorus_distance(
+    point: cgmath::Vector4<f32>,
+    major_radius: f32,
+    middle_radius: f32,
+    minor_radius: f32,
+) -> f32 {
+    let torus = cgmath::vec2(
+        cgmath::vec2(point.x, point.y).magnitude() - major_radius,
+        point.z,
+    )
+    .magnitude()
+        - middle_radius;
+    cgmath::vec2(torus, point.w).magnitude() - minor_radius
+}
+
+/// The distance to the shape made of every point that is inside of two shapes in perpendicular subspaces,
+/// given the distances to each of them
+fn extruded_distance(a: f32, b: f32) -> f32 {
+    cgmath::vec2(a.max(0.0), b.max(0.0)).magnitude() + a.max(b).min(0.0)
+}
+
+/// A minimum that blends the two distances together when they are within `smoothness` of each other
+pub fn smooth_min(a: f32, b: f32, smoothness: f32) -> f32 {
+    if smoothness <= 0.0 {
+        return a.min(b);
+    }
+    let blend = (smoothness - (a - b).abs()).max(0.0) / smoothness;
+    a.min(b) - blend * blend * smoothness * 0.25
+}
+
+pub fn combine_distances(operation: CsgOperation, left: f32, right: f32, smoothness: f32) -> f32 {
+    match operation {
+        CsgOperation::Union => smooth_min(left, right, smoothness),
+        CsgOperation::Intersection => -smooth_min(-left, -right, smoothness),
+        CsgOperation::Difference => -smooth_min(-left, right, smoothness),
+    }
+}
+
+/// Moves the point into the period around the origin, along each axis with a period that isnt 0
+pub fn repeat_point(
+    point: cgmath::Vector4<f32>,
+    period: cgmath::Vector4<f32>,
+) -> cgmath::Vector4<f32> {
+    let mut point = point;
+    for axis in 0..4 {
+        if period[axis] > 0.0 {
+            point[axis] -= period[axis] * (point[axis] / period[axis] + 0.5).floor();
+        }
+    }
+    point
+}
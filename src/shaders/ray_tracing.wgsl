@@ -1,24 +1,52 @@
 @group(0) @binding(0)
 var output_texture: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(1)
+var previous_accumulation_texture: texture_2d<f32>;
+@group(0) @binding(2)
+var accumulation_texture: texture_storage_2d<rgba32float, write>;
 
 struct Camera {
     position: vec4<f32>,
     forward: vec4<f32>,
     up: vec4<f32>,
     right: vec4<f32>,
+    ana: vec4<f32>,
+    // already flattened into the slice
     sun_direction: vec4<f32>,
     sun_color: vec3<f32>,
     sun_light_color: vec3<f32>,
     ambient_light_color: vec3<f32>,
     up_sky_color: vec3<f32>,
     down_sky_color: vec3<f32>,
+    slice_percentage: f32,
 }
 
 @group(1) @binding(0)
 var<uniform> camera: Camera;
 
+struct RenderSettings {
+    path_tracing: u32,
+    accumulated_frames: u32,
+    max_bounces: u32,
+    selected_object: u32,
+}
+
+const NOTHING_SELECTED: u32 = 0xffffffffu;
+const SELECTION_COLOR: vec3<f32> = vec3<f32>(1.0, 0.6, 0.1);
+
+@group(3) @binding(0)
+var<uniform> render_settings: RenderSettings;
+
+const MATERIAL_DIFFUSE: u32 = 0;
+const MATERIAL_METAL: u32 = 1;
+const MATERIAL_DIELECTRIC: u32 = 2;
+
 struct Material {
     color: vec3<f32>,
+    kind: u32,
+    roughness: f32,
+    index_of_refraction: f32,
+    emission_strength: f32,
 }
 
 struct Materials {
@@ -32,6 +60,7 @@ struct HyperSphere {
     position: vec4<f32>,
     radius: f32,
     material: u32,
+    id: u32,
 }
 
 struct HyperSpheres {
@@ -46,6 +75,7 @@ struct HyperPlane {
     normal: vec4<f32>,
     distance: f32,
     material: u32,
+    id: u32,
 }
 
 struct HyperPlanes {
@@ -56,6 +86,219 @@ struct HyperPlanes {
 @group(2) @binding(2)
 var<storage, read> hyper_planes: HyperPlanes;
 
+struct Rotor {
+    s: f32,
+    e12: f32,
+    e13: f32,
+    e14: f32,
+    e23: f32,
+    e24: f32,
+    e34: f32,
+    e1234: f32,
+}
+
+fn rotor_reverse(rotor: Rotor) -> Rotor {
+    var result = rotor;
+    result.e12 = -rotor.e12;
+    result.e13 = -rotor.e13;
+    result.e14 = -rotor.e14;
+    result.e23 = -rotor.e23;
+    result.e24 = -rotor.e24;
+    result.e34 = -rotor.e34;
+    return result;
+}
+
+fn rotor_rotate(rotor: Rotor, direction: vec4<f32>) -> vec4<f32> {
+    let a = rotor.s;
+    let b = rotor.e12;
+    let c = rotor.e13;
+    let d = rotor.e14;
+    let e = rotor.e23;
+    let f = rotor.e24;
+    let g = rotor.e34;
+    let h = rotor.e1234;
+    let p3 = direction.x;
+    let p2 = direction.y;
+    let p1 = direction.z;
+    let p0 = direction.w;
+    let s0 = e * p1 - a * p2 - b * p3 - f * p0;
+    let s1 = a * p3 + c * p1 - b * p2 - d * p0;
+    let s2 = a * p1 + e * p2 - g * p0 - c * p3;
+    let s3 = f * p2 - a * p0 - g * p1 - d * p3;
+    return vec4<f32>(
+        p3 + 2.0 * (d * s3 + c * s2 + b * s0 - h * (g * p2 + h * p3 + e * p0 + f * p1)),
+        p2 + 2.0 * (h * (g * p3 - h * p2 - c * p0 - d * p1) + b * s1 - f * s3 - e * s2),
+        p1 + 2.0 * (h * (d * p2 + f * p3 - h * p1 - b * p0) + g * s3 - e * s0 - c * s1),
+        p0 + 2.0 * (h * (b * p1 + c * p2 + e * p3 - h * p0) + f * s0 + d * s1 + g * s2),
+    );
+}
+
+struct HyperCube {
+    position: vec4<f32>,
+    half_extents: vec4<f32>,
+    rotation: Rotor,
+    material: u32,
+    id: u32,
+}
+
+struct HyperCubes {
+    length: u32,
+    data: array<HyperCube>,
+}
+
+@group(2) @binding(3)
+var<storage, read> hyper_cubes: HyperCubes;
+
+const BVH_INTERIOR: u32 = 0;
+const BVH_HYPER_SPHERE: u32 = 1;
+const BVH_HYPER_CUBE: u32 = 2;
+const BVH_CSG: u32 = 3;
+const BVH_SDF: u32 = 4;
+const BVH_HYPER_CYLINDER: u32 = 5;
+// the bvh of the mesh comes after the nodes of the scene, so this only says where to carry on
+const BVH_TETRAHEDRON_MESH: u32 = 6;
+const BVH_TETRAHEDRON: u32 = 7;
+
+struct BvhNode {
+    min: vec4<f32>,
+    max: vec4<f32>,
+    kind: u32,
+    // the right child for interior nodes (the left child is the next node), otherwise the index of the object
+    // (or of the root node for csg objects and tetrahedron meshes, and the first node for sdf objects)
+    index: u32,
+}
+
+struct BvhNodes {
+    length: u32,
+    data: array<BvhNode>,
+}
+
+@group(2) @binding(4)
+var<storage, read> bvh_nodes: BvhNodes;
+
+const CSG_UNION: u32 = 0;
+const CSG_INTERSECTION: u32 = 1;
+const CSG_DIFFERENCE: u32 = 2;
+const CSG_HYPER_SPHERE: u32 = 3;
+const CSG_HYPER_PLANE: u32 = 4;
+const CSG_HYPER_CUBE: u32 = 5;
+const CSG_SDF: u32 = 6;
+const CSG_HYPER_CYLINDER: u32 = 7;
+const CSG_TETRAHEDRON_MESH: u32 = 8;
+
+// csg trees are stored in post order, so children always come before their parent
+struct CsgNode {
+    position: vec4<f32>,
+    // the radius in x for hyper spheres, the normal for hyper planes and the half extents for hyper cubes
+    parameters: vec4<f32>,
+    rotation: Rotor,
+    kind: u32,
+    // how many nodes are in the subtree that ends with this node, including itself
+    subtree_size: u32,
+    material: u32,
+    id: u32,
+    // for the primitives that dont fit in a csg node, the index of the hyper cylinder in hyper_cylinders,
+    // the first node of the object in sdf_nodes or the root bvh node of a tetrahedron mesh
+    index: u32,
+}
+
+struct CsgNodes {
+    // the trees of csg objects without bounds come first, they arent in the bvh so every ray is tested against them
+    unbounded_length: u32,
+    length: u32,
+    data: array<CsgNode>,
+}
+
+@group(2) @binding(5)
+var<storage, read> csg_nodes: CsgNodes;
+
+const SDF_OBJECT: u32 = 0;
+const SDF_UNION: u32 = 1;
+const SDF_INTERSECTION: u32 = 2;
+const SDF_DIFFERENCE: u32 = 3;
+// subtracts the radius from the last distance
+const SDF_ROUND: u32 = 4;
+// saves the current point and then moves it
+const SDF_TRANSFORM: u32 = 5;
+// saves the current point and then moves it into the period around the origin
+const SDF_REPEAT: u32 = 6;
+// goes back to the last saved point
+const SDF_RESTORE_POINT: u32 = 7;
+const SDF_HYPER_SPHERE: u32 = 8;
+const SDF_BOX: u32 = 9;
+const SDF_SPHERINDER: u32 = 10;
+const SDF_CUBINDER: u32 = 11;
+const SDF_DUOCYLINDER: u32 = 12;
+const SDF_SPHERITORUS: u32 = 13;
+const SDF_TORISPHERE: u32 = 14;
+const SDF_TIGER: u32 = 15;
+const SDF_DITORUS: u32 = 16;
+
+// each sdf object is a node of kind SDF_OBJECT followed by the program that works out the distance to its shape,
+// shapes are in post order with the nodes that move the point before them and SDF_RESTORE_POINT after them
+struct SdfNode {
+    // the position of the object, or the offset for transform nodes
+    position: vec4<f32>,
+    // the parameters of shapes, the smoothness in x for combining nodes, the radius in x for rounding and the period for repeating
+    parameters: vec4<f32>,
+    rotation: Rotor,
+    kind: u32,
+    // how many nodes of the shape come after an object node
+    length: u32,
+    material: u32,
+    id: u32,
+}
+
+struct SdfNodes {
+    // the sdf objects without bounds come first, they arent in the bvh so every ray is tested against them
+    unbounded_length: u32,
+    length: u32,
+    data: array<SdfNode>,
+}
+
+@group(2) @binding(6)
+var<storage, read> sdf_nodes: SdfNodes;
+
+// the product of up to 3 factors, each one is everything where the part of the point along its axes is within its radius
+struct HyperCylinder {
+    position: vec4<f32>,
+    rotation: Rotor,
+    // 1 for each axis the factor is in, and 0 for the others
+    factor_axes: array<vec4<f32>, 3>,
+    factor_radii: vec3<f32>,
+    material: u32,
+    id: u32,
+}
+
+struct HyperCylinders {
+    // the hyper cylinders that go on forever come first, they arent in the bvh so every ray is tested against them
+    unbounded_length: u32,
+    length: u32,
+    data: array<HyperCylinder>,
+}
+
+@group(2) @binding(7)
+var<storage, read> hyper_cylinders: HyperCylinders;
+
+// a cell of a tetrahedron mesh, already moved into the same place as the mesh
+struct Tetrahedron {
+    vertex: vec4<f32>,
+    // points out of the mesh, its length is 6 times the volume of the tetrahedron
+    normal: vec4<f32>,
+    // the dot product of these with the offset of a point from the vertex is how far along each edge the point is
+    dual_edges: array<vec4<f32>, 3>,
+    material: u32,
+    id: u32,
+}
+
+struct Tetrahedra {
+    length: u32,
+    data: array<Tetrahedron>,
+}
+
+@group(2) @binding(8)
+var<storage, read> tetrahedra: Tetrahedra;
+
 struct Ray {
     origin: vec4<f32>,
     direction: vec4<f32>,
@@ -66,7 +309,9 @@ struct Hit {
     position: vec4<f32>,
     normal: vec4<f32>,
     distance: f32,
+    front_face: bool,
     material: u32,
+    object: u32,
 }
 
 fn hyper_sphere_hit(ray: Ray, hyper_sphere: HyperSphere) -> Hit {
@@ -82,11 +327,21 @@ fn hyper_sphere_hit(ray: Ray, hyper_sphere: HyperSphere) -> Hit {
 
     if discriminant >= 0.0 {
         hit.distance = (h - sqrt(discriminant)) / a;
+        hit.front_face = true;
+        if hit.distance <= 0.0 {
+            // the ray might have started inside the hyper sphere
+            hit.distance = (h + sqrt(discriminant)) / a;
+            hit.front_face = false;
+        }
         if hit.distance > 0.0 {
             hit.hit = true;
             hit.position = ray.origin + ray.direction * hit.distance;
             hit.normal = (hit.position - hyper_sphere.position) / hyper_sphere.radius;
+            if !hit.front_face {
+                hit.normal = -hit.normal;
+            }
             hit.material = hyper_sphere.material;
+            hit.object = hyper_sphere.id;
         }
     }
 
@@ -106,24 +361,836 @@ fn hyper_plane_hit(ray: Ray, hyper_plane: HyperPlane) -> Hit {
             hit.hit = true;
             hit.position = ray.origin + ray.direction * hit.distance;
             hit.normal = hyper_plane.normal * - sign(denom);
+            hit.front_face = denom < 0.0;
             hit.material = hyper_plane.material;
+            hit.object = hyper_plane.id;
         }
     }
 
     return hit;
 }
 
-fn ray_hit(ray: Ray) -> Hit {
+fn hyper_cube_hit(ray: Ray, hyper_cube: HyperCube) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+
+    // do the intersection in the local space of the hyper cube, where it is axis aligned
+    let inverse_rotation = rotor_reverse(hyper_cube.rotation);
+    let origin = rotor_rotate(inverse_rotation, ray.origin - hyper_cube.position);
+    let direction = rotor_rotate(inverse_rotation, ray.direction);
+
+    let inverse_direction = 1.0 / direction;
+    let a = (-hyper_cube.half_extents - origin) * inverse_direction;
+    let b = (hyper_cube.half_extents - origin) * inverse_direction;
+    let near = min(a, b);
+    let far = max(a, b);
+
+    var near_axis = 0u;
+    var far_axis = 0u;
+    for (var axis = 1u; axis < 4u; axis += 1u) {
+        if near[axis] > near[near_axis] {
+            near_axis = axis;
+        }
+        if far[axis] < far[far_axis] {
+            far_axis = axis;
+        }
+    }
+
+    let near_distance = near[near_axis];
+    let far_distance = far[far_axis];
+    if near_distance <= far_distance {
+        // if the ray started inside the hyper cube, it hits the far side from the inside
+        var axis = near_axis;
+        hit.distance = near_distance;
+        hit.front_face = true;
+        if near_distance <= 0.0 {
+            axis = far_axis;
+            hit.distance = far_distance;
+            hit.front_face = false;
+        }
+
+        if hit.distance > 0.0 {
+            hit.hit = true;
+            hit.position = ray.origin + ray.direction * hit.distance;
+            var normal = vec4<f32>(0.0);
+            normal[axis] = -sign(direction[axis]);
+            hit.normal = rotor_rotate(hyper_cube.rotation, normal);
+            hit.material = hyper_cube.material;
+            hit.object = hyper_cube.id;
+        }
+    }
+
+    return hit;
+}
+
+struct HyperCylinderInterval {
+    hit: bool,
+    near: f32,
+    far: f32,
+    // the factors that the ray goes into and out of the hyper cylinder through
+    near_factor: u32,
+    far_factor: u32,
+}
+
+// where the ray goes into and out of a hyper cylinder, the ray needs to be in the local space of the hyper cylinder
+fn hyper_cylinder_interval(ray: Ray, hyper_cylinder: HyperCylinder) -> HyperCylinderInterval {
+    var interval: HyperCylinderInterval;
+    interval.hit = false;
+    interval.near = -3.402823e38;
+    interval.far = 3.402823e38;
+    interval.near_factor = 0u;
+    interval.far_factor = 0u;
+
+    for (var factor = 0u; factor < 3u; factor += 1u) {
+        // the same as a hyper sphere, but only along the axes of the factor
+        let axes = hyper_cylinder.factor_axes[factor];
+        let radius = hyper_cylinder.factor_radii[factor];
+        let origin = ray.origin * axes;
+        let direction = ray.direction * axes;
+        let a = dot(direction, direction);
+        let h = -dot(direction, origin);
+        let c = dot(origin, origin) - radius * radius;
+        if a == 0.0 {
+            // the ray is parallel to the factor, so it is either all inside or all outside
+            if c > 0.0 {
+                return interval;
+            }
+            continue;
+        }
+
+        let discriminant = h * h - a * c;
+        if discriminant < 0.0 {
+            return interval;
+        }
+        let factor_near = (h - sqrt(discriminant)) / a;
+        let factor_far = (h + sqrt(discriminant)) / a;
+        if factor_near > interval.near {
+            interval.near = factor_near;
+            interval.near_factor = factor;
+        }
+        if factor_far < interval.far {
+            interval.far = factor_far;
+            interval.far_factor = factor;
+        }
+    }
+
+    interval.hit = interval.near <= interval.far;
+    return interval;
+}
+
+fn hyper_cylinder_local_ray(ray: Ray, hyper_cylinder: HyperCylinder) -> Ray {
+    let inverse_rotation = rotor_reverse(hyper_cylinder.rotation);
+    var local_ray: Ray;
+    local_ray.origin = rotor_rotate(inverse_rotation, ray.origin - hyper_cylinder.position);
+    local_ray.direction = rotor_rotate(inverse_rotation, ray.direction);
+    return local_ray;
+}
+
+// the outwards facing normal of a factor of a hyper cylinder at a point on its surface
+fn hyper_cylinder_factor_normal(hyper_cylinder: HyperCylinder, factor: u32, local_position: vec4<f32>) -> vec4<f32> {
+    let normal = local_position * hyper_cylinder.factor_axes[factor] / hyper_cylinder.factor_radii[factor];
+    return rotor_rotate(hyper_cylinder.rotation, normal);
+}
+
+fn hyper_cylinder_hit(ray: Ray, hyper_cylinder: HyperCylinder) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+
+    // do the intersection in the local space of the hyper cylinder, where its factors are along the axes
+    let local_ray = hyper_cylinder_local_ray(ray, hyper_cylinder);
+    let interval = hyper_cylinder_interval(local_ray, hyper_cylinder);
+    if interval.hit {
+        // if the ray started inside the hyper cylinder, it hits the far side from the inside
+        var factor = interval.near_factor;
+        hit.distance = interval.near;
+        hit.front_face = true;
+        if interval.near <= 0.0 {
+            factor = interval.far_factor;
+            hit.distance = interval.far;
+            hit.front_face = false;
+        }
+
+        // rays going along w can stay inside the shapes that go on forever along it
+        if hit.distance > 0.0 && hit.distance < 3.402823e38 {
+            hit.hit = true;
+            hit.position = ray.origin + ray.direction * hit.distance;
+            hit.normal = hyper_cylinder_factor_normal(hyper_cylinder, factor, local_ray.origin + local_ray.direction * hit.distance);
+            if !hit.front_face {
+                hit.normal = -hit.normal;
+            }
+            hit.material = hyper_cylinder.material;
+            hit.object = hyper_cylinder.id;
+        }
+    }
+
+    return hit;
+}
+
+// how far outside of a tetrahedron a ray can go through it and still count as hitting it,
+// so rays that go exactly through the edge between two cells dont slip between them
+const TETRAHEDRON_TOLERANCE: f32 = 0.00001;
+
+// how far along the ray it goes through the tetrahedron, this can be behind the origin of the ray.
+// returns a negative number if the ray misses
+fn tetrahedron_crossing(ray: Ray, tetrahedron: Tetrahedron) -> f32 {
+    let denominator = dot(ray.direction, tetrahedron.normal);
+    if denominator == 0.0 {
+        return -1.0;
+    }
+
+    let distance = dot(tetrahedron.vertex - ray.origin, tetrahedron.normal) / denominator;
+    let offset = ray.origin + ray.direction * distance - tetrahedron.vertex;
+    let a = dot(tetrahedron.dual_edges[0], offset);
+    let b = dot(tetrahedron.dual_edges[1], offset);
+    let c = dot(tetrahedron.dual_edges[2], offset);
+    let tolerance = -TETRAHEDRON_TOLERANCE;
+    if a >= tolerance && b >= tolerance && c >= tolerance && a + b + c <= 1.0 - tolerance {
+        return distance;
+    }
+    return -1.0;
+}
+
+fn tetrahedron_hit(ray: Ray, tetrahedron: Tetrahedron) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+
+    let distance = tetrahedron_crossing(ray, tetrahedron);
+    if distance <= 0.0 {
+        return hit;
+    }
+
+    // the cells face out of the mesh, so the ray is going into it if it is going against the normal
+    let outward_normal = normalize(tetrahedron.normal);
+    hit.hit = true;
+    hit.distance = distance;
+    hit.position = ray.origin + ray.direction * distance;
+    hit.front_face = dot(ray.direction, outward_normal) < 0.0;
+    hit.normal = outward_normal;
+    if !hit.front_face {
+        hit.normal = -outward_normal;
+    }
+    hit.material = tetrahedron.material;
+    hit.object = tetrahedron.id;
+    return hit;
+}
+
+// how many steps a ray can take through a signed distance field looking for its surface
+const SDF_MAX_STEPS: u32 = 256;
+// rays stop looking for the surface of a signed distance field once they get this far
+const SDF_MAX_DISTANCE: f32 = 1000.0;
+// the smallest step a ray takes, so it gets through the surface instead of only getting closer
+const SDF_MIN_STEP: f32 = 0.0001;
+// how far to move from a point on each axis when working out the normal
+const SDF_NORMAL_OFFSET: f32 = 0.0005;
+// how many distances an sdf can need to keep around at once, this only grows when the right side of a combination is another combination
+const SDF_STACK_SIZE: u32 = 8;
+// how many transforms and repeats can be nested inside of each other
+const SDF_POINT_STACK_SIZE: u32 = 4;
+// returned for shapes that cant be evaluated, so rays go straight past them
+const SDF_TOO_COMPLEX: f32 = 3.402823e38;
+
+// the distance to the shape made of every point that is inside of two shapes in perpendicular subspaces
+fn extruded_distance(a: f32, b: f32) -> f32 {
+    return length(max(vec2<f32>(a, b), vec2<f32>(0.0))) + min(max(a, b), 0.0);
+}
+
+// a minimum that blends the two distances together when they are within smoothness of each other
+fn smooth_min(a: f32, b: f32, smoothness: f32) -> f32 {
+    if smoothness <= 0.0 {
+        return min(a, b);
+    }
+    let blend = max(smoothness - abs(a - b), 0.0) / smoothness;
+    return min(a, b) - blend * blend * smoothness * 0.25;
+}
+
+fn sdf_primitive_distance(node: SdfNode, point: vec4<f32>) -> f32 {
+    let parameters = node.parameters;
+    switch node.kind {
+        case SDF_HYPER_SPHERE: {
+            return length(point) - parameters.x;
+        }
+        case SDF_BOX: {
+            let offset = abs(point) - parameters;
+            return length(max(offset, vec4<f32>(0.0))) + min(max(max(offset.x, offset.y), max(offset.z, offset.w)), 0.0);
+        }
+        case SDF_SPHERINDER: {
+            return extruded_distance(length(point.xyz) - parameters.x, abs(point.w) - parameters.y);
+        }
+        case SDF_CUBINDER: {
+            let offset = vec3<f32>(length(point.xy) - parameters.x, abs(point.zw) - parameters.yz);
+            return length(max(offset, vec3<f32>(0.0))) + min(max(offset.x, max(offset.y, offset.z)), 0.0);
+        }
+        case SDF_DUOCYLINDER: {
+            return extruded_distance(length(point.xy) - parameters.x, length(point.zw) - parameters.y);
+        }
+        case SDF_SPHERITORUS: {
+            return length(vec3<f32>(length(point.xy) - parameters.x, point.zw)) - parameters.y;
+        }
+        case SDF_TORISPHERE: {
+            return length(vec2<f32>(length(point.xyz) - parameters.x, point.w)) - parameters.y;
+        }
+        case SDF_TIGER: {
+            return length(vec2<f32>(length(point.xy) - parameters.x, length(point.zw) - parameters.y)) - parameters.z;
+        }
+        case SDF_DITORUS: {
+            let torus = length(vec2<f32>(length(point.xy) - parameters.x, point.z)) - parameters.y;
+            return length(vec2<f32>(torus, point.w)) - parameters.z;
+        }
+        default: {
+            return SDF_TOO_COMPLEX;
+        }
+    }
+}
+
+// the distance from a point to the surface of an sdf object, negative for points inside of it
+fn sdf_distance(object: u32, position: vec4<f32>) -> f32 {
+    let header = sdf_nodes.data[object];
+    var point = rotor_rotate(rotor_reverse(header.rotation), position - header.position);
+
+    var points: array<vec4<f32>, SDF_POINT_STACK_SIZE>;
+    var point_count = 0u;
+    var distances: array<f32, SDF_STACK_SIZE>;
+    var distance_count = 0u;
+    for (var i = object + 1u; i <= object + header.length; i += 1u) {
+        let node = sdf_nodes.data[i];
+        if node.kind == SDF_UNION || node.kind == SDF_INTERSECTION || node.kind == SDF_DIFFERENCE {
+            let right = distances[distance_count - 1u];
+            let left = distances[distance_count - 2u];
+            distance_count -= 1u;
+
+            let smoothness = node.parameters.x;
+            if node.kind == SDF_UNION {
+                distances[distance_count - 1u] = smooth_min(left, right, smoothness);
+            } else if node.kind == SDF_INTERSECTION {
+                distances[distance_count - 1u] = -smooth_min(-left, -right, smoothness);
+            } else {
+                distances[distance_count - 1u] = -smooth_min(-left, right, smoothness);
+            }
+        } else if node.kind == SDF_ROUND {
+            distances[distance_count - 1u] -= node.parameters.x;
+        } else if node.kind == SDF_TRANSFORM || node.kind == SDF_REPEAT {
+            if point_count == SDF_POINT_STACK_SIZE {
+                return SDF_TOO_COMPLEX;
+            }
+            points[point_count] = point;
+            point_count += 1u;
+
+            if node.kind == SDF_TRANSFORM {
+                point = rotor_rotate(rotor_reverse(node.rotation), point - node.position);
+            } else {
+                for (var axis = 0u; axis < 4u; axis += 1u) {
+                    let period = node.parameters[axis];
+                    if period > 0.0 {
+                        point[axis] -= period * floor(point[axis] / period + 0.5);
+                    }
+                }
+            }
+        } else if node.kind == SDF_RESTORE_POINT {
+            point_count -= 1u;
+            point = points[point_count];
+        } else {
+            if distance_count == SDF_STACK_SIZE {
+                return SDF_TOO_COMPLEX;
+            }
+            distances[distance_count] = sdf_primitive_distance(node, point);
+            distance_count += 1u;
+        }
+    }
+    return distances[0];
+}
+
+// the direction pointing out of the surface of an sdf object at a point near it
+fn sdf_normal(object: u32, position: vec4<f32>) -> vec4<f32> {
+    var gradient: vec4<f32>;
+    for (var axis = 0u; axis < 4u; axis += 1u) {
+        var offset = vec4<f32>(0.0);
+        offset[axis] = SDF_NORMAL_OFFSET;
+        gradient[axis] = sdf_distance(object, position + offset) - sdf_distance(object, position - offset);
+    }
+    return normalize(gradient);
+}
+
+// steps along the ray from distance until it goes through the surface of an sdf object, returning how far along the ray that was,
+// or a negative number if it never does. direction needs to be normalized
+fn sdf_crossing(object: u32, origin: vec4<f32>, direction: vec4<f32>, start: f32, inside: bool) -> f32 {
+    var distance = start;
+    for (var step = 0u; step < SDF_MAX_STEPS; step += 1u) {
+        let surface_distance = sdf_distance(object, origin + direction * distance);
+        if (surface_distance < 0.0) != inside {
+            return distance;
+        }
+        distance += max(abs(surface_distance), SDF_MIN_STEP);
+        if distance > SDF_MAX_DISTANCE {
+            break;
+        }
+    }
+    return -1.0;
+}
+
+fn sdf_hit(ray: Ray, object: u32) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+
+    // the distance field is measured in world units, so the ray needs to be too
+    let ray_length = length(ray.direction);
+    let direction = ray.direction / ray_length;
+
+    let inside = sdf_distance(object, ray.origin) < 0.0;
+    let crossing = sdf_crossing(object, ray.origin, direction, 0.0, inside);
+    if crossing < 0.0 {
+        return hit;
+    }
+
+    let header = sdf_nodes.data[object];
+    hit.hit = true;
+    hit.distance = crossing / ray_length;
+    hit.position = ray.origin + ray.direction * hit.distance;
+    hit.normal = sdf_normal(object, hit.position);
+    hit.front_face = !inside;
+    if inside {
+        hit.normal = -hit.normal;
+    }
+    hit.material = header.material;
+    hit.object = header.id;
+    return hit;
+}
+
+const NO_CSG_NODE: u32 = 0xffffffffu;
+// the most spans a csg object can have along a ray, any further away than that are dropped
+const MAX_CSG_SPANS: u32 = 8;
+// how many results a csg tree can need to keep around at once, this only grows when the right child of an operation is another operation
+const CSG_STACK_SIZE: u32 = 4;
+
+struct CsgBoundary {
+    distance: f32,
+    // the primitive node the boundary is on the surface of, or NO_CSG_NODE for the ends of the ray
+    node: u32,
+    // whether the solid is on the outside of the primitive instead of the inside
+    flipped: bool,
+}
+
+// a part of a ray that is inside of a solid
+struct CsgSpan {
+    start: CsgBoundary,
+    end: CsgBoundary,
+}
+
+struct CsgSpans {
+    count: u32,
+    spans: array<CsgSpan, MAX_CSG_SPANS>,
+}
+
+fn ray_start_boundary() -> CsgBoundary {
+    return CsgBoundary(0.0, NO_CSG_NODE, false);
+}
+
+fn ray_end_boundary() -> CsgBoundary {
+    return CsgBoundary(3.402823e38, NO_CSG_NODE, false);
+}
+
+fn flipped_boundary(boundary: CsgBoundary) -> CsgBoundary {
+    return CsgBoundary(boundary.distance, boundary.node, !boundary.flipped);
+}
+
+fn push_csg_span(spans: ptr<function, CsgSpans>, start: CsgBoundary, end: CsgBoundary) {
+    if start.distance < end.distance && (*spans).count < MAX_CSG_SPANS {
+        (*spans).spans[(*spans).count] = CsgSpan(start, end);
+        (*spans).count += 1u;
+    }
+}
+
+// adds the part of the span that is in front of the ray origin, if there is any of it
+fn push_csg_span_in_front(spans: ptr<function, CsgSpans>, start: CsgBoundary, end: CsgBoundary) {
+    if start.distance < 0.0 {
+        push_csg_span(spans, ray_start_boundary(), end);
+    } else {
+        push_csg_span(spans, start, end);
+    }
+}
+
+// the most cells of a tetrahedron mesh in a csg object that are kept along a ray, any further away than that are dropped
+const MAX_MESH_CROSSINGS: u32 = 16;
+// how far a point can be outside of a cell and still be treated as being on it when working out normals
+const MESH_NORMAL_TOLERANCE: f32 = 0.001;
+
+struct MeshCrossing {
+    distance: f32,
+    entering: bool,
+}
+
+// the spans of a tetrahedron mesh, from going through the cells of its bvh in order
+fn tetrahedron_mesh_spans(ray: Ray, node_index: u32, root: u32) -> CsgSpans {
+    var crossings: array<MeshCrossing, MAX_MESH_CROSSINGS>;
+    var crossing_count = 0u;
+
+    let inverse_direction = 1.0 / ray.direction;
+    var stack: array<u32, BVH_STACK_SIZE>;
+    stack[0] = root;
+    var stack_size = 1u;
+    while stack_size > 0u {
+        stack_size -= 1u;
+        let bvh_node_index = stack[stack_size];
+        let bvh_node = bvh_nodes.data[bvh_node_index];
+
+        // once there is no room for more crossings, only ones closer than the furthest matter
+        var max_distance = 3.402823e38;
+        if crossing_count == MAX_MESH_CROSSINGS {
+            max_distance = crossings[MAX_MESH_CROSSINGS - 1u].distance;
+        }
+        if !bounds_hit(ray, inverse_direction, bvh_node, max_distance) {
+            continue;
+        }
+
+        if bvh_node.kind == BVH_INTERIOR {
+            if stack_size + 2u <= BVH_STACK_SIZE {
+                stack[stack_size] = bvh_node.index;
+                stack[stack_size + 1u] = bvh_node_index + 1u;
+                stack_size += 2u;
+            }
+            continue;
+        }
+
+        let tetrahedron = tetrahedra.data[bvh_node.index];
+        let distance = tetrahedron_crossing(ray, tetrahedron);
+        if distance <= 0.0 || distance >= max_distance {
+            continue;
+        }
+        // keep the crossings sorted, dropping the furthest one if there isnt room for this one
+        var i = min(crossing_count, MAX_MESH_CROSSINGS - 1u);
+        while i > 0u && crossings[i - 1u].distance > distance {
+            crossings[i] = crossings[i - 1u];
+            i -= 1u;
+        }
+        crossings[i] = MeshCrossing(distance, dot(ray.direction, tetrahedron.normal) < 0.0);
+        crossing_count = min(crossing_count + 1u, MAX_MESH_CROSSINGS);
+    }
+
+    var spans: CsgSpans;
+    spans.count = 0u;
+    // the ray started inside if the first cell it goes through is on its way out
+    var inside = crossing_count > 0u && !crossings[0].entering;
+    var start = ray_start_boundary();
+    for (var i = 0u; i < crossing_count; i += 1u) {
+        let boundary = CsgBoundary(crossings[i].distance, node_index, false);
+        // going through the edge between two cells crosses both of them, so crossings that dont change anything are skipped
+        if !inside && crossings[i].entering {
+            start = boundary;
+            inside = true;
+        } else if inside && !crossings[i].entering {
+            push_csg_span(&spans, start, boundary);
+            inside = false;
+        }
+    }
+    if inside {
+        push_csg_span(&spans, start, ray_end_boundary());
+    }
+    return spans;
+}
+
+// the index of the tetrahedron of a mesh that a point is on
+fn tetrahedron_mesh_cell(root: u32, position: vec4<f32>) -> u32 {
+    var cell = 0u;
+    var closest = 3.402823e38;
+
+    var stack: array<u32, BVH_STACK_SIZE>;
+    stack[0] = root;
+    var stack_size = 1u;
+    while stack_size > 0u {
+        stack_size -= 1u;
+        let bvh_node_index = stack[stack_size];
+        let bvh_node = bvh_nodes.data[bvh_node_index];
+        if any(position < bvh_node.min - MESH_NORMAL_TOLERANCE) || any(position > bvh_node.max + MESH_NORMAL_TOLERANCE) {
+            continue;
+        }
+
+        if bvh_node.kind == BVH_INTERIOR {
+            if stack_size + 2u <= BVH_STACK_SIZE {
+                stack[stack_size] = bvh_node.index;
+                stack[stack_size + 1u] = bvh_node_index + 1u;
+                stack_size += 2u;
+            }
+            continue;
+        }
+
+        // the point is on the cell that it is closest to the hyper plane of, out of the cells that it is over
+        let tetrahedron = tetrahedra.data[bvh_node.index];
+        let offset = position - tetrahedron.vertex;
+        let a = dot(tetrahedron.dual_edges[0], offset);
+        let b = dot(tetrahedron.dual_edges[1], offset);
+        let c = dot(tetrahedron.dual_edges[2], offset);
+        let tolerance = -MESH_NORMAL_TOLERANCE;
+        if a < tolerance || b < tolerance || c < tolerance || a + b + c > 1.0 - tolerance {
+            continue;
+        }
+        let distance = abs(dot(offset, normalize(tetrahedron.normal)));
+        if distance < closest {
+            closest = distance;
+            cell = bvh_node.index;
+        }
+    }
+    return cell;
+}
+
+fn csg_primitive_spans(ray: Ray, node_index: u32) -> CsgSpans {
+    let node = csg_nodes.data[node_index];
+    var spans: CsgSpans;
+    spans.count = 0u;
+
+    if node.kind == CSG_HYPER_SPHERE {
+        let oc = node.position - ray.origin;
+        let a = dot(ray.direction, ray.direction);
+        let h = dot(ray.direction, oc);
+        let c = dot(oc, oc) - node.parameters.x * node.parameters.x;
+        let discriminant = h * h - a * c;
+        if discriminant >= 0.0 {
+            let start = CsgBoundary((h - sqrt(discriminant)) / a, node_index, false);
+            let end = CsgBoundary((h + sqrt(discriminant)) / a, node_index, false);
+            push_csg_span_in_front(&spans, start, end);
+        }
+    } else if node.kind == CSG_HYPER_PLANE {
+        // hyper planes are the half space behind their normal
+        let denom = dot(node.parameters, ray.direction);
+        let offset = dot(node.position - ray.origin, node.parameters);
+        if abs(denom) <= 0.00001 {
+            // the ray is parallel to the hyper plane, so it is either all inside or all outside
+            if offset >= 0.0 {
+                push_csg_span(&spans, ray_start_boundary(), ray_end_boundary());
+            }
+        } else {
+            let boundary = CsgBoundary(offset / denom, node_index, false);
+            if denom < 0.0 {
+                push_csg_span_in_front(&spans, boundary, ray_end_boundary());
+            } else {
+                push_csg_span_in_front(&spans, ray_start_boundary(), boundary);
+            }
+        }
+    } else if node.kind == CSG_HYPER_CUBE {
+        let inverse_rotation = rotor_reverse(node.rotation);
+        let origin = rotor_rotate(inverse_rotation, ray.origin - node.position);
+        let direction = rotor_rotate(inverse_rotation, ray.direction);
+
+        let inverse_direction = 1.0 / direction;
+        let a = (-node.parameters - origin) * inverse_direction;
+        let b = (node.parameters - origin) * inverse_direction;
+        let near = min(a, b);
+        let far = max(a, b);
+        let near_distance = max(max(near.x, near.y), max(near.z, near.w));
+        let far_distance = min(min(far.x, far.y), min(far.z, far.w));
+        if near_distance <= far_distance {
+            let start = CsgBoundary(near_distance, node_index, false);
+            let end = CsgBoundary(far_distance, node_index, false);
+            push_csg_span_in_front(&spans, start, end);
+        }
+    } else if node.kind == CSG_HYPER_CYLINDER {
+        let hyper_cylinder = hyper_cylinders.data[node.index];
+        let interval = hyper_cylinder_interval(hyper_cylinder_local_ray(ray, hyper_cylinder), hyper_cylinder);
+        if interval.hit {
+            var end = CsgBoundary(interval.far, node_index, false);
+            if interval.far >= 3.402823e38 {
+                end = ray_end_boundary();
+            }
+            push_csg_span_in_front(&spans, CsgBoundary(interval.near, node_index, false), end);
+        }
+    } else if node.kind == CSG_SDF {
+        let ray_length = length(ray.direction);
+        let direction = ray.direction / ray_length;
+
+        var inside = sdf_distance(node.index, ray.origin) < 0.0;
+        var start = ray_start_boundary();
+        var distance = 0.0;
+        while spans.count < MAX_CSG_SPANS {
+            let crossing = sdf_crossing(node.index, ray.origin, direction, distance, inside);
+            if crossing < 0.0 {
+                break;
+            }
+
+            let boundary = CsgBoundary(crossing / ray_length, node_index, false);
+            if inside {
+                push_csg_span(&spans, start, boundary);
+            } else {
+                start = boundary;
+            }
+            inside = !inside;
+            distance = crossing;
+        }
+        if inside {
+            push_csg_span(&spans, start, ray_end_boundary());
+        }
+    } else if node.kind == CSG_TETRAHEDRON_MESH {
+        spans = tetrahedron_mesh_spans(ray, node_index, node.index);
+    }
+
+    return spans;
+}
+
+// the parts of the ray in front of its origin that arent in the spans
+fn csg_complement(spans: CsgSpans) -> CsgSpans {
+    var input = spans;
+    var result: CsgSpans;
+    result.count = 0u;
+
+    var start = ray_start_boundary();
+    for (var i = 0u; i < input.count; i += 1u) {
+        push_csg_span(&result, start, flipped_boundary(input.spans[i].start));
+        start = flipped_boundary(input.spans[i].end);
+    }
+    push_csg_span(&result, start, ray_end_boundary());
+    return result;
+}
+
+fn csg_intersection(a: CsgSpans, b: CsgSpans) -> CsgSpans {
+    var a_spans = a;
+    var b_spans = b;
+    var result: CsgSpans;
+    result.count = 0u;
+
+    for (var i = 0u; i < a_spans.count; i += 1u) {
+        for (var j = 0u; j < b_spans.count; j += 1u) {
+            let a_span = a_spans.spans[i];
+            let b_span = b_spans.spans[j];
+            var start = b_span.start;
+            if a_span.start.distance >= b_span.start.distance {
+                start = a_span.start;
+            }
+            var end = b_span.end;
+            if a_span.end.distance <= b_span.end.distance {
+                end = a_span.end;
+            }
+            push_csg_span(&result, start, end);
+        }
+    }
+    return result;
+}
+
+// the outwards facing normal of a primitive csg node at a point on its surface
+fn csg_node_normal(node: CsgNode, position: vec4<f32>) -> vec4<f32> {
+    if node.kind == CSG_HYPER_SPHERE {
+        return (position - node.position) / node.parameters.x;
+    } else if node.kind == CSG_HYPER_PLANE {
+        return node.parameters;
+    } else if node.kind == CSG_SDF {
+        return sdf_normal(node.index, position);
+    } else if node.kind == CSG_TETRAHEDRON_MESH {
+        return normalize(tetrahedra.data[tetrahedron_mesh_cell(node.index, position)].normal);
+    } else if node.kind == CSG_HYPER_CYLINDER {
+        // the factor that the point is on is the one it is furthest out along, relative to the radius of the factor
+        let hyper_cylinder = hyper_cylinders.data[node.index];
+        let local_position = rotor_rotate(rotor_reverse(hyper_cylinder.rotation), position - hyper_cylinder.position);
+        var factor = 0u;
+        var furthest = 0.0;
+        for (var i = 0u; i < 3u; i += 1u) {
+            let along_factor = length(local_position * hyper_cylinder.factor_axes[i]) / hyper_cylinder.factor_radii[i];
+            if along_factor > furthest {
+                factor = i;
+                furthest = along_factor;
+            }
+        }
+        return hyper_cylinder_factor_normal(hyper_cylinder, factor, local_position);
+    }
+
+    // the face of the hyper cube that the point is on is the one it is furthest along, relative to the size of the hyper cube
+    let local_position = rotor_rotate(rotor_reverse(node.rotation), position - node.position) / node.parameters;
+    var axis = 0u;
+    for (var i = 1u; i < 4u; i += 1u) {
+        if abs(local_position[i]) > abs(local_position[axis]) {
+            axis = i;
+        }
+    }
+    var normal = vec4<f32>(0.0);
+    normal[axis] = sign(local_position[axis]);
+    return rotor_rotate(node.rotation, normal);
+}
+
+fn csg_hit(ray: Ray, root: u32) -> Hit {
     var hit: Hit;
     hit.hit = false;
 
-    for (var i = 0u; i < hyper_spheres.length; i += 1u) {
-        let hyper_sphere_hit = hyper_sphere_hit(ray, hyper_spheres.data[i]);
-        if hyper_sphere_hit.hit && (!hit.hit || hyper_sphere_hit.distance < hit.distance) {
-            hit = hyper_sphere_hit;
+    var stack: array<CsgSpans, CSG_STACK_SIZE>;
+    var stack_size = 0u;
+    let first = root + 1u - csg_nodes.data[root].subtree_size;
+    for (var i = first; i <= root; i += 1u) {
+        let kind = csg_nodes.data[i].kind;
+        if kind == CSG_UNION || kind == CSG_INTERSECTION || kind == CSG_DIFFERENCE {
+            let right = stack[stack_size - 1u];
+            let left = stack[stack_size - 2u];
+            stack_size -= 2u;
+
+            var result: CsgSpans;
+            if kind == CSG_UNION {
+                result = csg_complement(csg_intersection(csg_complement(left), csg_complement(right)));
+            } else if kind == CSG_INTERSECTION {
+                result = csg_intersection(left, right);
+            } else {
+                result = csg_intersection(left, csg_complement(right));
+            }
+            stack[stack_size] = result;
+            stack_size += 1u;
+        } else {
+            if stack_size == CSG_STACK_SIZE {
+                // the tree is too deep to render
+                return hit;
+            }
+            stack[stack_size] = csg_primitive_spans(ray, i);
+            stack_size += 1u;
         }
     }
 
+    let spans = stack[0];
+    if spans.count == 0u {
+        return hit;
+    }
+
+    var boundary = spans.spans[0].start;
+    hit.front_face = true;
+    if boundary.distance <= 0.0 {
+        boundary = spans.spans[0].end;
+        hit.front_face = false;
+    }
+    if boundary.node == NO_CSG_NODE {
+        // the ray started inside something infinite, and never left it
+        return hit;
+    }
+
+    let node = csg_nodes.data[boundary.node];
+    hit.hit = true;
+    hit.distance = boundary.distance;
+    hit.position = ray.origin + ray.direction * hit.distance;
+    hit.normal = csg_node_normal(node, hit.position);
+    if boundary.flipped == hit.front_face {
+        hit.normal = -hit.normal;
+    }
+    hit.material = node.material;
+    if node.kind == CSG_TETRAHEDRON_MESH {
+        // each cell of a mesh can have its own material
+        hit.material = tetrahedra.data[tetrahedron_mesh_cell(node.index, hit.position)].material;
+    }
+    hit.object = node.id;
+    return hit;
+}
+
+fn bounds_hit(ray: Ray, inverse_direction: vec4<f32>, node: BvhNode, max_distance: f32) -> bool {
+    // rays going along the sides of the box would get nan distances to them, so they are only checked for being between them
+    let parallel = ray.direction == vec4<f32>(0.0);
+    if any(parallel & ((ray.origin < node.min) | (ray.origin > node.max))) {
+        return false;
+    }
+    let a = (node.min - ray.origin) * inverse_direction;
+    let b = (node.max - ray.origin) * inverse_direction;
+    let near = select(min(a, b), vec4<f32>(-3.402823e38), parallel);
+    let far = select(max(a, b), vec4<f32>(3.402823e38), parallel);
+    let near_distance = max(max(near.x, near.y), max(near.z, near.w));
+    let far_distance = min(min(far.x, far.y), min(far.z, far.w));
+    return near_distance <= far_distance && far_distance > 0.0 && near_distance < max_distance;
+}
+
+// the bvhs are built balanced, so this is plenty for any number of objects that fit in memory,
+// even when going from the scene bvh into the bvh of a tetrahedron mesh
+const BVH_STACK_SIZE: u32 = 64;
+
+fn ray_hit(ray: Ray) -> Hit {
+    var hit: Hit;
+    hit.hit = false;
+
     for (var i = 0u; i < hyper_planes.length; i += 1u) {
         let hyper_plane_hit = hyper_plane_hit(ray, hyper_planes.data[i]);
         if hyper_plane_hit.hit && (!hit.hit || hyper_plane_hit.distance < hit.distance) {
@@ -131,27 +1198,354 @@ fn ray_hit(ray: Ray) -> Hit {
         }
     }
 
+    var unbounded_root = csg_nodes.unbounded_length;
+    while unbounded_root > 0u {
+        unbounded_root -= 1u;
+        let object_hit = csg_hit(ray, unbounded_root);
+        if object_hit.hit && (!hit.hit || object_hit.distance < hit.distance) {
+            hit = object_hit;
+        }
+        // the node before this tree is the root of the previous one
+        unbounded_root -= csg_nodes.data[unbounded_root].subtree_size - 1u;
+    }
+
+    for (var i = 0u; i < hyper_cylinders.unbounded_length; i += 1u) {
+        let object_hit = hyper_cylinder_hit(ray, hyper_cylinders.data[i]);
+        if object_hit.hit && (!hit.hit || object_hit.distance < hit.distance) {
+            hit = object_hit;
+        }
+    }
+
+    var unbounded_sdf = 0u;
+    while unbounded_sdf < sdf_nodes.unbounded_length {
+        let object_hit = sdf_hit(ray, unbounded_sdf);
+        if object_hit.hit && (!hit.hit || object_hit.distance < hit.distance) {
+            hit = object_hit;
+        }
+        unbounded_sdf += sdf_nodes.data[unbounded_sdf].length + 1u;
+    }
+
+    if bvh_nodes.length == 0u {
+        return hit;
+    }
+
+    let inverse_direction = 1.0 / ray.direction;
+    var stack: array<u32, BVH_STACK_SIZE>;
+    stack[0] = 0u;
+    var stack_size = 1u;
+    while stack_size > 0u {
+        stack_size -= 1u;
+        let node_index = stack[stack_size];
+        let node = bvh_nodes.data[node_index];
+
+        var max_distance = 3.402823e38;
+        if hit.hit {
+            max_distance = hit.distance;
+        }
+        if !bounds_hit(ray, inverse_direction, node, max_distance) {
+            continue;
+        }
+
+        var object_hit: Hit;
+        object_hit.hit = false;
+        if node.kind == BVH_INTERIOR {
+            if stack_size + 2u <= BVH_STACK_SIZE {
+                stack[stack_size] = node.index;
+                stack[stack_size + 1u] = node_index + 1u;
+                stack_size += 2u;
+            }
+        } else if node.kind == BVH_HYPER_SPHERE {
+            object_hit = hyper_sphere_hit(ray, hyper_spheres.data[node.index]);
+        } else if node.kind == BVH_HYPER_CUBE {
+            object_hit = hyper_cube_hit(ray, hyper_cubes.data[node.index]);
+        } else if node.kind == BVH_CSG {
+            object_hit = csg_hit(ray, node.index);
+        } else if node.kind == BVH_SDF {
+            object_hit = sdf_hit(ray, node.index);
+        } else if node.kind == BVH_HYPER_CYLINDER {
+            object_hit = hyper_cylinder_hit(ray, hyper_cylinders.data[node.index]);
+        } else if node.kind == BVH_TETRAHEDRON_MESH {
+            if stack_size < BVH_STACK_SIZE {
+                stack[stack_size] = node.index;
+                stack_size += 1u;
+            }
+        } else if node.kind == BVH_TETRAHEDRON {
+            object_hit = tetrahedron_hit(ray, tetrahedra.data[node.index]);
+        }
+
+        if object_hit.hit && (!hit.hit || object_hit.distance < hit.distance) {
+            hit = object_hit;
+        }
+    }
+
     return hit;
 }
 
-fn ray_color(ray: Ray) -> vec3<f32> {
-    var color = mix(camera.down_sky_color, camera.up_sky_color, ray.direction.y * 0.5 + 0.5);
+// removes the part of a vector that points out of the slice, keeping its length, see `Camera::flatten_to_slice`
+fn flatten_to_slice(vector: vec4<f32>) -> vec4<f32> {
+    if camera.slice_percentage <= 0.0 {
+        return vector;
+    }
+
+    let flattened = vector - camera.ana * (dot(vector, camera.ana) * camera.slice_percentage);
+    let length_squared = dot(flattened, flattened);
+    if length_squared < 0.000001 {
+        return vector;
+    }
+    return flattened * (length(vector) / sqrt(length_squared));
+}
+
+fn sky_color(direction: vec4<f32>) -> vec3<f32> {
+    return mix(camera.down_sky_color, camera.up_sky_color, direction.y * 0.5 + 0.5);
+}
+
+fn sun_light(hit: Hit) -> vec3<f32> {
+    var sun_ray: Ray;
+    sun_ray.origin = hit.position + hit.normal * 0.001;
+    sun_ray.direction = camera.sun_direction;
+    let sun_hit = ray_hit(sun_ray);
+    if sun_hit.hit {
+        return vec3<f32>(0.0);
+    }
+    return camera.sun_light_color * max(dot(sun_ray.direction, hit.normal), 0.0);
+}
+
+// moves the origin off the surface to the side the direction is going towards, so the new ray doesnt hit the same surface again
+fn ray_from_hit(hit: Hit, direction: vec4<f32>) -> Ray {
+    var ray: Ray;
+    ray.origin = hit.position + hit.normal * (0.001 * sign(dot(direction, hit.normal)));
+    ray.direction = direction;
+    return ray;
+}
+
+fn reflect_direction(direction: vec4<f32>, normal: vec4<f32>) -> vec4<f32> {
+    return direction - normal * (2.0 * dot(direction, normal));
+}
+
+struct Refraction {
+    // false when there is total internal reflection
+    refracted: bool,
+    direction: vec4<f32>,
+    // the fraction of light that gets reflected instead of refracted
+    reflectance: f32,
+}
+
+fn refract_direction(direction: vec4<f32>, hit: Hit, index_of_refraction: f32) -> Refraction {
+    var refraction: Refraction;
+
+    var eta = index_of_refraction;
+    if hit.front_face {
+        eta = 1.0 / index_of_refraction;
+    }
+
+    let cos_theta = min(-dot(direction, hit.normal), 1.0);
+    let sin_theta_squared = eta * eta * (1.0 - cos_theta * cos_theta);
+    if sin_theta_squared > 1.0 {
+        refraction.refracted = false;
+        refraction.reflectance = 1.0;
+        return refraction;
+    }
+
+    refraction.refracted = true;
+    refraction.direction = normalize(direction * eta + hit.normal * (eta * cos_theta - sqrt(1.0 - sin_theta_squared)));
+
+    // schlick's approximation
+    let r0 = (1.0 - eta) / (1.0 + eta);
+    let r0_squared = r0 * r0;
+    refraction.reflectance = r0_squared + (1.0 - r0_squared) * pow(1.0 - cos_theta, 5.0);
+    return refraction;
+}
+
+struct TracedRay {
+    ray: Ray,
+    throughput: vec3<f32>,
+    bounces: u32,
+}
+
+// each bounce adds at most one ray waiting on the stack, so this is enough for up to 15 bounces
+const MAX_TRACED_RAYS: u32 = 16;
+
+fn ray_color(primary_ray: Ray) -> vec3<f32> {
+    var color = vec3<f32>(0.0);
+
+    // wgsl doesnt allow recursion, so the rays still to be traced are kept on a stack
+    var stack: array<TracedRay, MAX_TRACED_RAYS>;
+    stack[0] = TracedRay(primary_ray, vec3<f32>(1.0), 0u);
+    var stack_size = 1u;
+
+    while stack_size > 0u {
+        stack_size -= 1u;
+        let traced = stack[stack_size];
+        let ray = traced.ray;
+
+        var hit = ray_hit(ray);
+        // in slice mode surfaces are shaded like the 3d shapes they are cut into
+        hit.normal = flatten_to_slice(hit.normal);
+        if !hit.hit {
+            if dot(camera.sun_direction, ray.direction) > 0.99 {
+                color += traced.throughput * camera.sun_color;
+            } else {
+                color += traced.throughput * sky_color(ray.direction);
+            }
+            continue;
+        }
 
-    let hit = ray_hit(ray);
-    if hit.hit {
         let material = materials.data[hit.material];
-        color = material.color * camera.ambient_light_color;
+        color += traced.throughput * material.color * material.emission_strength;
 
-        var sun_ray: Ray;
-        sun_ray.origin = hit.position + hit.normal * 0.001;
-        sun_ray.direction = camera.sun_direction;
-        let sun_hit = ray_hit(sun_ray);
-        if !sun_hit.hit {
-            color += camera.sun_light_color * material.color * max(dot(sun_ray.direction, hit.normal), 0.0);
+        if material.kind == MATERIAL_DIFFUSE || traced.bounces >= render_settings.max_bounces {
+            color += traced.throughput * material.color * (camera.ambient_light_color + sun_light(hit));
+            continue;
+        }
+
+        let bounces = traced.bounces + 1u;
+        let reflected = ray_from_hit(hit, reflect_direction(ray.direction, hit.normal));
+        if material.kind == MATERIAL_METAL {
+            // rays arent scattered like when path tracing, so rough metal is shaded partly like a diffuse
+            // surface instead, which keeps the image free of noise
+            color += traced.throughput * material.color * (camera.ambient_light_color + sun_light(hit)) * material.roughness;
+            let throughput = traced.throughput * material.color * (1.0 - material.roughness);
+            stack[stack_size] = TracedRay(reflected, throughput, bounces);
+            stack_size += 1u;
+        } else if material.kind == MATERIAL_DIELECTRIC {
+            let refraction = refract_direction(ray.direction, hit, material.index_of_refraction);
+            if refraction.refracted && stack_size < MAX_TRACED_RAYS {
+                let throughput = traced.throughput * material.color * (1.0 - refraction.reflectance);
+                stack[stack_size] = TracedRay(ray_from_hit(hit, refraction.direction), throughput, bounces);
+                stack_size += 1u;
+            }
+            if stack_size < MAX_TRACED_RAYS {
+                let throughput = traced.throughput * refraction.reflectance;
+                stack[stack_size] = TracedRay(reflected, throughput, bounces);
+                stack_size += 1u;
+            }
         }
     }
-    else if dot(camera.sun_direction, ray.direction) > 0.99 {
-        color = camera.sun_color;
+
+    return color;
+}
+
+var<private> rng_state: u32;
+
+fn pcg_hash(input: u32) -> u32 {
+    let state = input * 747796405u + 2891336453u;
+    let word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+fn random_float() -> f32 {
+    rng_state = pcg_hash(rng_state);
+    return f32(rng_state) / 4294967295.0;
+}
+
+fn random_in_unit_ball() -> vec4<f32> {
+    loop {
+        let point = vec4<f32>(random_float(), random_float(), random_float(), random_float()) * 2.0 - 1.0;
+        if dot(point, point) <= 1.0 {
+            return point;
+        }
+    }
+    return vec4<f32>(0.0);
+}
+
+// returns 3 directions that are perpendicular to the normal and to each other
+fn tangent_basis(normal: vec4<f32>) -> array<vec4<f32>, 3> {
+    let axes = array<vec4<f32>, 4>(
+        vec4<f32>(1.0, 0.0, 0.0, 0.0),
+        vec4<f32>(0.0, 1.0, 0.0, 0.0),
+        vec4<f32>(0.0, 0.0, 1.0, 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+    );
+
+    // the axis closest to the normal is the one that would be the least stable to orthogonalize
+    var skipped_axis = 0u;
+    for (var i = 1u; i < 4u; i += 1u) {
+        if abs(normal[i]) > abs(normal[skipped_axis]) {
+            skipped_axis = i;
+        }
+    }
+
+    var basis: array<vec4<f32>, 3>;
+    var count = 0u;
+    for (var i = 0u; i < 4u; i += 1u) {
+        if i == skipped_axis {
+            continue;
+        }
+        var direction = axes[i] - normal * dot(axes[i], normal);
+        for (var j = 0u; j < count; j += 1u) {
+            direction -= basis[j] * dot(direction, basis[j]);
+        }
+        basis[count] = normalize(direction);
+        count += 1u;
+    }
+    return basis;
+}
+
+// picking a uniform point in the 3d ball of tangent directions and lifting it onto the hemisphere
+// gives directions distributed proportionally to the cosine with the normal
+fn random_cosine_direction(normal: vec4<f32>) -> vec4<f32> {
+    let z = random_float() * 2.0 - 1.0;
+    let angle = random_float() * 6.28318530718;
+    let ring_radius = sqrt(max(1.0 - z * z, 0.0));
+    let radius = pow(random_float(), 1.0 / 3.0);
+    let point = vec3<f32>(cos(angle) * ring_radius, sin(angle) * ring_radius, z) * radius;
+
+    let basis = tangent_basis(normal);
+    return basis[0] * point.x + basis[1] * point.y + basis[2] * point.z
+        + normal * sqrt(max(1.0 - dot(point, point), 0.0));
+}
+
+fn path_trace_color(primary_ray: Ray) -> vec3<f32> {
+    var ray = primary_ray;
+    var color = vec3<f32>(0.0);
+    var throughput = vec3<f32>(1.0);
+    // diffuse bounces already sample the sun directly, so only rays that arent from a diffuse bounce can see it
+    var can_see_sun = true;
+
+    for (var bounce = 0u; bounce <= render_settings.max_bounces; bounce += 1u) {
+        var hit = ray_hit(ray);
+        hit.normal = flatten_to_slice(hit.normal);
+        if !hit.hit {
+            if can_see_sun && dot(camera.sun_direction, ray.direction) > 0.99 {
+                color += throughput * camera.sun_color;
+            } else {
+                color += throughput * sky_color(ray.direction);
+            }
+            break;
+        }
+
+        let material = materials.data[hit.material];
+        color += throughput * material.color * material.emission_strength;
+
+        var direction: vec4<f32>;
+        if material.kind == MATERIAL_METAL {
+            direction = reflect_direction(ray.direction, hit.normal) + random_in_unit_ball() * material.roughness;
+            if dot(direction, hit.normal) <= 0.0 {
+                // the roughness scattered the ray into the surface, so it gets absorbed
+                break;
+            }
+            direction = normalize(direction);
+            throughput *= material.color;
+            can_see_sun = true;
+        } else if material.kind == MATERIAL_DIELECTRIC {
+            let refraction = refract_direction(ray.direction, hit, material.index_of_refraction);
+            if random_float() < refraction.reflectance {
+                direction = reflect_direction(ray.direction, hit.normal);
+            } else {
+                direction = refraction.direction;
+                throughput *= material.color;
+            }
+            can_see_sun = true;
+        } else {
+            throughput *= material.color;
+            color += throughput * sun_light(hit);
+            direction = random_cosine_direction(hit.normal);
+            can_see_sun = false;
+        }
+
+        // keeps bounces inside the slice, the flattened diffuse directions arent exactly cosine distributed
+        // in 3d but they are close enough for looking at a slice
+        ray = ray_from_hit(hit, flatten_to_slice(direction));
     }
 
     return color;
@@ -166,13 +1560,38 @@ fn main(@builtin(global_invocation_id) global_id: vec3<u32>) {
         return;
     }
 
+    rng_state = pcg_hash(coords.x ^ pcg_hash(coords.y ^ pcg_hash(render_settings.accumulated_frames)));
+
+    var pixel_offset = vec2<f32>(0.5);
+    if render_settings.path_tracing != 0u {
+        pixel_offset = vec2<f32>(random_float(), random_float());
+    }
+
     let aspect = f32(size.x) / f32(size.y);
-    let uv = ((vec2<f32>(coords) + 0.5) / vec2<f32>(size)) * 2.0 - 1.0;
+    let uv = ((vec2<f32>(coords) + pixel_offset) / vec2<f32>(size)) * 2.0 - 1.0;
 
     var ray: Ray;
     ray.origin = camera.position;
     ray.direction = normalize(camera.right * (uv.x * aspect) + camera.up * uv.y + camera.forward);
 
-    let color = ray_color(ray);
+    var color: vec3<f32>;
+    if render_settings.path_tracing != 0u {
+        var accumulated_color = path_trace_color(ray);
+        if render_settings.accumulated_frames > 0u {
+            accumulated_color += textureLoad(previous_accumulation_texture, coords, 0).rgb;
+        }
+        textureStore(accumulation_texture, coords, vec4<f32>(accumulated_color, 1.0));
+        color = accumulated_color / f32(render_settings.accumulated_frames + 1u);
+    } else {
+        color = ray_color(ray);
+    }
+
+    // the highlight is added after accumulating, so selecting something doesnt restart path tracing
+    if render_settings.selected_object != NOTHING_SELECTED {
+        let hit = ray_hit(ray);
+        if hit.hit && hit.object == render_settings.selected_object {
+            color = mix(color, SELECTION_COLOR, 0.35);
+        }
+    }
     textureStore(output_texture, coords, vec4<f32>(clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)), 1.0));
 }
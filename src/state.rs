@@ -1,22 +1,67 @@
 use crate::{
+    bvh::Bvh,
     camera::Camera,
     gpu_buffers::{BufferCreationInfo, BufferGroup, DynamicBuffer, FixedSizeBuffer},
     gpu_types::{
-        GpuCamera, GpuHyperPlane, GpuHyperSphere, GpuLengthArray, GpuLine, GpuMaterial, GpuUiInfo,
+        GpuBvhNode, GpuCamera, GpuCsgNodes, GpuHyperCube, GpuHyperCylinders, GpuHyperPlane,
+        GpuHyperSphere, GpuLengthArray, GpuLine, GpuMaterial, GpuObjects, GpuObjectsCache,
+        GpuRenderSettings, GpuSdfNodes, GpuTetrahedron, GpuUiInfo,
     },
+    history::{Edit, History},
     material::Material,
     math::{Rotor, Transform},
-    objects::{HyperPlane, HyperSphere, Object},
-    ray::{Ray, RayIntersect},
+    mesh::Mesh,
+    objects::{
+        HyperCube, HyperCylinder, HyperCylinderShape, HyperPlane, HyperSphere, HyperTorus,
+        HyperTorusShape, Object, TetrahedronMesh,
+    },
+    ray::{Ray, closest_hit},
+    scene::{Lighting, Scene},
 };
-use cgmath::InnerSpace;
-use encase::ArrayLength;
+use cgmath::{InnerSpace, Zero};
+use editor::{MeshImport, PolytopeImport, SceneSave};
+use std::sync::Arc;
 use winit::{
     event::{ElementState, MouseButton},
     keyboard::KeyCode,
 };
 
+mod editor;
+
 const RENDER_SAMPLES: u32 = 4;
+pub const DEFAULT_MAX_BOUNCES: u32 = 4;
+/// The fast mode in `ray_tracing.wgsl` can only keep track of this many bounces before it starts dropping rays
+pub const MAX_TRACED_BOUNCES: u32 = 15;
+/// How far in front of the camera new objects are placed
+const SPAWN_DISTANCE: f32 = 5.0;
+
+/// The limits that the device given to `State` needs, the ray tracing shader uses one more storage buffer than the default allows
+pub fn required_limits() -> wgpu::Limits {
+    wgpu::Limits {
+        max_storage_buffers_per_shader_stage: 9,
+        ..Default::default()
+    }
+}
+
+/// Checks that the adapter supports `required_limits`, so there is a clear error instead of failing to request a device
+pub fn check_adapter_limits(adapter: &wgpu::Adapter) -> Result<(), String> {
+    let mut unsupported = vec![];
+    required_limits().check_limits_with_fail_fn(
+        &adapter.limits(),
+        false,
+        |name, required, supported| {
+            unsupported.push(format!("{name} is {supported}, but {required} is needed"));
+        },
+    );
+    if unsupported.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "the gpu doesnt support the limits the ray tracer needs: {}",
+            unsupported.join(", "),
+        ))
+    }
+}
 
 pub struct State {
     camera: Camera,
@@ -24,19 +69,35 @@ pub struct State {
 
     materials: Vec<Material>,
     objects: Vec<Object>,
+    bvh: Bvh,
     #[expect(clippy::type_complexity)]
     objects_buffer: BufferGroup<(
         DynamicBuffer<Vec<GpuMaterial>>,
         DynamicBuffer<GpuLengthArray<GpuHyperSphere>>,
         DynamicBuffer<GpuLengthArray<GpuHyperPlane>>,
+        DynamicBuffer<GpuLengthArray<GpuHyperCube>>,
+        DynamicBuffer<GpuLengthArray<GpuBvhNode>>,
+        DynamicBuffer<GpuCsgNodes>,
+        DynamicBuffer<GpuSdfNodes>,
+        DynamicBuffer<GpuHyperCylinders>,
+        DynamicBuffer<GpuLengthArray<GpuTetrahedron>>,
     )>,
+    gpu_objects: GpuObjectsCache,
 
     ui_buffer: BufferGroup<(FixedSizeBuffer<GpuUiInfo>, DynamicBuffer<Vec<GpuLine>>)>,
 
+    path_tracing: bool,
+    max_bounces: u32,
+    accumulated_frames: u32,
+    /// Set when the camera, objects or materials change, so the accumulated samples are thrown away before the next frame
+    scene_changed: bool,
+    render_settings_buffer: BufferGroup<(FixedSizeBuffer<GpuRenderSettings>,)>,
+
     ray_tracing_texture_output_bind_group_layout: wgpu::BindGroupLayout,
     ray_tracing_texture_render_bind_group_layout: wgpu::BindGroupLayout,
     ray_tracing_texture: wgpu::Texture,
-    ray_tracing_texture_output_bind_group: wgpu::BindGroup,
+    /// Indexed by the accumulation texture that gets written to, the other one is read from
+    ray_tracing_texture_output_bind_groups: [wgpu::BindGroup; 2],
     ray_tracing_texture_render_bind_group: wgpu::BindGroup,
     ray_tracing_pipeline: wgpu::ComputePipeline,
 
@@ -44,22 +105,88 @@ pub struct State {
     ui_render_pipeline: wgpu::RenderPipeline,
 
     final_texture: wgpu::Texture,
-
-    selected_hyper_sphere: Option<usize>,
-    axis_line_interaction: Option<AxisLineInteraction>,
+    final_resolve_texture: wgpu::Texture,
+
+    egui_context: egui::Context,
+    egui_renderer: egui_wgpu::Renderer,
+    ui_input: Option<egui::RawInput>,
+    ui_platform_output: egui::PlatformOutput,
+    /// An edit from the editor ui that is still being made
+    pending_ui_edit: Option<Edit>,
+    mesh_import: MeshImport,
+    polytope_import: PolytopeImport,
+    scene_save: SceneSave,
+
+    selection: Option<Selection>,
+    gizmo_interaction: Option<GizmoInteraction>,
     use_camera_axes: bool,
     mouse_locked: bool,
+
+    history: History,
+    control_held: bool,
+    shift_held: bool,
 }
 
-struct AxisLineInteraction {
-    axis_index: usize,
+/// The planes that the rotation gizmo has a handle for, as pairs of axis indices
+const ROTATION_PLANES: [(usize, usize); 6] = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)];
+const ROTATION_ARC_RADIUS: f32 = 0.6;
+/// The arcs dont go all the way to the axes so they dont overlap with the translation handles
+const ROTATION_ARC_ANGLES: std::ops::RangeInclusive<f32> =
+    0.15 * core::f32::consts::PI..=0.35 * core::f32::consts::PI;
+const ROTATION_ARC_SEGMENTS: usize = 8;
+
+const AXIS_COLORS: [cgmath::Vector4<f32>; 4] = [
+    cgmath::Vector4::new(1.0, 0.2, 0.2, 1.0),
+    cgmath::Vector4::new(0.2, 1.0, 0.2, 1.0),
+    cgmath::Vector4::new(0.2, 0.2, 1.0, 1.0),
+    cgmath::Vector4::new(1.0, 0.2, 1.0, 1.0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum GizmoHandle {
+    Axis(usize),
+    Plane(usize),
+}
+
+struct GizmoInteraction {
+    handle: GizmoHandle,
+    /// How far along the axis line the cursor was, only used by axis handles
     start_pos: f32,
-    dragging: bool,
+    start_uv: cgmath::Vector2<f32>,
+    /// The screen space direction the cursor moves in per radian of rotation, only used by plane handles
+    angle_direction: cgmath::Vector2<f32>,
+    applied_angle: f32,
+    /// The object as it was before the drag started, this is `Some` while dragging
+    drag_start: Option<Object>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Selection {
+    object: usize,
+    /// Where the gizmo is, relative to the object's position. This is only non-zero for hyperplanes that were clicked on,
+    /// because their position can be anywhere on the plane, so the gizmo is put where they were clicked instead
+    gizmo_offset: cgmath::Vector4<f32>,
+}
+
+impl Selection {
+    fn new(object: usize) -> Self {
+        Self {
+            object,
+            gizmo_offset: cgmath::Vector4::zero(),
+        }
+    }
 }
 
 impl State {
-    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> State {
-        let camera = Camera::default();
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, scene: Scene) -> State {
+        let Scene {
+            lighting,
+            materials,
+            objects,
+        } = scene;
+
+        let mut camera = Camera::default();
+        lighting.apply_to_camera(&mut camera);
         let camera_buffer = BufferGroup::new(
             device,
             "Camera",
@@ -76,44 +203,21 @@ impl State {
             },),
         );
 
-        let materials = vec![
-            Material {
-                color: cgmath::vec3(0.1, 0.6, 0.2),
-            },
-            Material {
-                color: cgmath::vec3(0.8, 0.2, 0.1),
-            },
-            Material {
-                color: cgmath::vec3(0.2, 0.8, 0.3),
-            },
-            Material {
-                color: cgmath::vec3(0.1, 0.2, 0.8),
-            },
-        ];
-        let objects = vec![
-            Object::HyperPlane(HyperPlane {
-                position: cgmath::vec4(0.0, -1.0, 0.0, 0.0),
-                normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
-                material: 0,
-            }),
-            Object::HyperSphere(HyperSphere {
-                position: cgmath::vec4(3.0, 0.0, 0.0, 0.0),
-                radius: 1.0,
-                material: 1,
-            }),
-            Object::HyperSphere(HyperSphere {
-                position: cgmath::vec4(3.0, 0.0, 2.0, 0.0),
-                radius: 1.0,
-                material: 2,
-            }),
-            Object::HyperSphere(HyperSphere {
-                position: cgmath::vec4(3.0, 0.0, -2.0, 2.0),
-                radius: 1.0,
-                material: 3,
-            }),
-        ];
+        let bvh = Bvh::build(&objects);
+        let mut gpu_objects = GpuObjectsCache::default();
         let objects_buffer = {
-            let (hyper_spheres, hyper_planes) = Self::objects_to_gpu_objects(&objects);
+            let GpuObjects {
+                hyper_spheres,
+                hyper_planes,
+                hyper_cubes,
+                hyper_cylinders,
+                csg_nodes,
+                sdf_nodes,
+                bvh_nodes,
+                tetrahedra,
+            } = gpu_objects
+                .update(&objects, &bvh)
+                .expect("nothing should have been built yet");
             BufferGroup::new(
                 device,
                 "Objects",
@@ -138,10 +242,7 @@ impl State {
                             queue,
                             "Hyper Spheres",
                             wgpu::BufferUsages::STORAGE,
-                            &GpuLengthArray {
-                                length: ArrayLength,
-                                data: hyper_spheres,
-                            },
+                            hyper_spheres,
                         ),
                         binding_type: wgpu::BufferBindingType::Storage { read_only: true },
                         visibility: wgpu::ShaderStages::COMPUTE,
@@ -152,10 +253,73 @@ impl State {
                             queue,
                             "Hyper Planes",
                             wgpu::BufferUsages::STORAGE,
-                            &GpuLengthArray {
-                                length: ArrayLength,
-                                data: hyper_planes,
-                            },
+                            hyper_planes,
+                        ),
+                        binding_type: wgpu::BufferBindingType::Storage { read_only: true },
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                    },
+                    BufferCreationInfo {
+                        buffer: DynamicBuffer::new(
+                            device,
+                            queue,
+                            "Hyper Cubes",
+                            wgpu::BufferUsages::STORAGE,
+                            hyper_cubes,
+                        ),
+                        binding_type: wgpu::BufferBindingType::Storage { read_only: true },
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                    },
+                    BufferCreationInfo {
+                        buffer: DynamicBuffer::new(
+                            device,
+                            queue,
+                            "BVH Nodes",
+                            wgpu::BufferUsages::STORAGE,
+                            bvh_nodes,
+                        ),
+                        binding_type: wgpu::BufferBindingType::Storage { read_only: true },
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                    },
+                    BufferCreationInfo {
+                        buffer: DynamicBuffer::new(
+                            device,
+                            queue,
+                            "CSG Nodes",
+                            wgpu::BufferUsages::STORAGE,
+                            csg_nodes,
+                        ),
+                        binding_type: wgpu::BufferBindingType::Storage { read_only: true },
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                    },
+                    BufferCreationInfo {
+                        buffer: DynamicBuffer::new(
+                            device,
+                            queue,
+                            "SDF Nodes",
+                            wgpu::BufferUsages::STORAGE,
+                            sdf_nodes,
+                        ),
+                        binding_type: wgpu::BufferBindingType::Storage { read_only: true },
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                    },
+                    BufferCreationInfo {
+                        buffer: DynamicBuffer::new(
+                            device,
+                            queue,
+                            "Hyper Cylinders",
+                            wgpu::BufferUsages::STORAGE,
+                            hyper_cylinders,
+                        ),
+                        binding_type: wgpu::BufferBindingType::Storage { read_only: true },
+                        visibility: wgpu::ShaderStages::COMPUTE,
+                    },
+                    BufferCreationInfo {
+                        buffer: DynamicBuffer::new(
+                            device,
+                            queue,
+                            "Tetrahedra",
+                            wgpu::BufferUsages::STORAGE,
+                            tetrahedra,
                         ),
                         binding_type: wgpu::BufferBindingType::Storage { read_only: true },
                         visibility: wgpu::ShaderStages::COMPUTE,
@@ -193,13 +357,34 @@ impl State {
             ),
         );
 
+        let render_settings_buffer = BufferGroup::new(
+            device,
+            "Render Settings",
+            (BufferCreationInfo {
+                buffer: FixedSizeBuffer::new(
+                    device,
+                    queue,
+                    "Render Settings",
+                    wgpu::BufferUsages::UNIFORM,
+                    &GpuRenderSettings {
+                        path_tracing: 0,
+                        accumulated_frames: 0,
+                        max_bounces: DEFAULT_MAX_BOUNCES,
+                        selected_object: GpuRenderSettings::NOTHING_SELECTED,
+                    },
+                ),
+                binding_type: wgpu::BufferBindingType::Uniform,
+                visibility: wgpu::ShaderStages::COMPUTE,
+            },),
+        );
+
         let (
             ray_tracing_texture_output_bind_group_layout,
             ray_tracing_texture_render_bind_group_layout,
         ) = ray_tracing_texture_bind_group_layouts(device);
         let (
             ray_tracing_texture,
-            ray_tracing_texture_output_bind_group,
+            ray_tracing_texture_output_bind_groups,
             ray_tracing_texture_render_bind_group,
         ) = ray_tracing_texture_and_bind_groups(
             device,
@@ -218,6 +403,7 @@ impl State {
                     &ray_tracing_texture_output_bind_group_layout,
                     camera_buffer.bind_group_layout(),
                     objects_buffer.bind_group_layout(),
+                    render_settings_buffer.bind_group_layout(),
                 ],
                 push_constant_ranges: &[],
             });
@@ -333,6 +519,11 @@ impl State {
             ray_tracing_texture.width(),
             ray_tracing_texture.height(),
         );
+        let final_resolve_texture = final_resolve_texture(
+            device,
+            ray_tracing_texture.width(),
+            ray_tracing_texture.height(),
+        );
 
         State {
             camera,
@@ -340,14 +531,22 @@ impl State {
 
             materials,
             objects,
+            bvh,
             objects_buffer,
+            gpu_objects,
 
             ui_buffer,
 
+            path_tracing: false,
+            max_bounces: DEFAULT_MAX_BOUNCES,
+            accumulated_frames: 0,
+            scene_changed: true,
+            render_settings_buffer,
+
             ray_tracing_texture_output_bind_group_layout,
             ray_tracing_texture_render_bind_group_layout,
             ray_tracing_texture,
-            ray_tracing_texture_output_bind_group,
+            ray_tracing_texture_output_bind_groups,
             ray_tracing_texture_render_bind_group,
             ray_tracing_pipeline,
 
@@ -355,18 +554,90 @@ impl State {
             ui_render_pipeline,
 
             final_texture,
+            final_resolve_texture,
 
-            selected_hyper_sphere: None,
-            axis_line_interaction: None,
+            egui_context: egui::Context::default(),
+            egui_renderer: egui_wgpu::Renderer::new(
+                device,
+                wgpu::TextureFormat::Bgra8Unorm,
+                None,
+                RENDER_SAMPLES,
+                false,
+            ),
+            ui_input: None,
+            ui_platform_output: egui::PlatformOutput::default(),
+            pending_ui_edit: None,
+            mesh_import: MeshImport::default(),
+            polytope_import: PolytopeImport::default(),
+            scene_save: SceneSave::default(),
+
+            selection: None,
+            gizmo_interaction: None,
             use_camera_axes: false,
             mouse_locked: false,
+
+            history: History::default(),
+            control_held: false,
+            shift_held: false,
+        }
+    }
+
+    pub fn scene(&self) -> Scene {
+        Scene {
+            lighting: Lighting::from_camera(&self.camera),
+            materials: self.materials.clone(),
+            objects: self.objects.clone(),
         }
     }
 
+    pub fn path_tracing(&self) -> bool {
+        self.path_tracing
+    }
+
+    pub fn set_path_tracing(&mut self, path_tracing: bool) {
+        self.path_tracing = path_tracing;
+        self.accumulated_frames = 0;
+    }
+
+    pub fn max_bounces(&self) -> u32 {
+        self.max_bounces
+    }
+
+    /// How many times rays can be reflected or refracted, in both the fast mode and path tracing.
+    /// Clamped to `MAX_TRACED_BOUNCES`
+    pub fn set_max_bounces(&mut self, max_bounces: u32) {
+        self.max_bounces = max_bounces.min(MAX_TRACED_BOUNCES);
+        self.accumulated_frames = 0;
+    }
+
+    /// The context the editor ui runs in, needed to translate window events into ui input
+    pub fn egui_context(&self) -> &egui::Context {
+        &self.egui_context
+    }
+
+    /// Sets the input for the editor ui in the next call to `render`, the ui is only drawn for frames that have input
+    pub fn set_ui_input(&mut self, input: egui::RawInput) {
+        self.ui_input = Some(input);
+    }
+
+    /// Returns everything the editor ui wants the window to do (like changing the cursor) since this was last called
+    pub fn take_ui_platform_output(&mut self) -> egui::PlatformOutput {
+        std::mem::take(&mut self.ui_platform_output)
+    }
+
     pub fn update(&mut self, dt: std::time::Duration) {
         let ts = dt.as_secs_f32();
 
-        self.camera.update(ts);
+        self.update_camera(|camera| camera.update(ts));
+    }
+
+    /// Runs something that can change the camera, resetting the accumulated samples if it did
+    fn update_camera(&mut self, update: impl FnOnce(&mut Camera)) {
+        let before = GpuCamera::from_camera(&self.camera);
+        update(&mut self.camera);
+        if GpuCamera::from_camera(&self.camera) != before {
+            self.scene_changed = true;
+        }
     }
 
     pub fn key(&mut self, key: KeyCode, state: ElementState, window: &winit::window::Window) {
@@ -383,30 +654,67 @@ impl State {
                 }
             }
 
+            (KeyCode::KeyP, ElementState::Pressed) => {
+                self.set_path_tracing(!self.path_tracing);
+            }
+
             (KeyCode::KeyG, ElementState::Pressed) => {
                 self.use_camera_axes = !self.use_camera_axes;
-                if let Some(interaction) = &mut self.axis_line_interaction {
-                    interaction.dragging = false;
+                self.end_gizmo_drag();
+            }
+
+            (KeyCode::ControlLeft | KeyCode::ControlRight, _) => {
+                self.control_held = state.is_pressed();
+            }
+            (KeyCode::ShiftLeft | KeyCode::ShiftRight, _) => {
+                self.shift_held = state.is_pressed();
+            }
+
+            (KeyCode::KeyZ, ElementState::Pressed) if self.control_held => {
+                if self.shift_held {
+                    self.redo();
+                } else {
+                    self.undo();
                 }
             }
+            (KeyCode::KeyD, ElementState::Pressed) if self.control_held => {
+                self.duplicate_selected();
+            }
+
+            (KeyCode::Digit1, ElementState::Pressed) => self.spawn_hyper_sphere(),
+            (KeyCode::Digit2, ElementState::Pressed) => self.spawn_hyper_plane(),
+            (KeyCode::Digit3, ElementState::Pressed) => self.spawn_hyper_cube(),
+            (KeyCode::Digit4, ElementState::Pressed) => self.spawn_hyper_cylinder(),
+            (KeyCode::Digit5, ElementState::Pressed) => self.spawn_hyper_torus(),
+            (KeyCode::Delete | KeyCode::Backspace, ElementState::Pressed) => {
+                self.delete_selected();
+            }
 
             _ => (),
         }
 
-        self.camera.key(key, state);
+        // keys pressed with control held are commands, so they shouldnt also move the camera
+        if !(self.control_held && state.is_pressed()) {
+            self.camera.key(key, state);
+        }
     }
 
     pub fn mouse(&mut self, button: MouseButton, state: ElementState, uv: cgmath::Vector2<f32>) {
         if !self.mouse_locked {
             match (button, state) {
-                (MouseButton::Left, ElementState::Released) => self.axis_line_interaction = None,
+                (MouseButton::Left, ElementState::Released) => {
+                    self.end_gizmo_drag();
+                    self.gizmo_interaction = None;
+                }
 
                 (MouseButton::Left, ElementState::Pressed) => {
-                    if let Some(interaction) = &mut self.axis_line_interaction {
-                        interaction.dragging = true;
+                    if let Some(interaction) = &mut self.gizmo_interaction
+                        && let Some(selection) = self.selection
+                    {
+                        interaction.drag_start = Some(self.objects[selection.object].clone());
                     }
 
-                    if self.axis_line_interaction.is_none() {
+                    if self.gizmo_interaction.is_none() {
                         let rotation = self.camera.get_rotation();
                         let forward = rotation.rotate(Camera::FORWARD);
                         let up = rotation.rotate(Camera::UP);
@@ -416,32 +724,20 @@ impl State {
                             origin: self.camera.position,
                             direction: (right * uv.x + up * uv.y + forward).normalize(),
                         };
-                        let hit = self.objects.iter().enumerate().fold(
-                            None,
-                            |current_hit, (index, hyper_sphere)| {
-                                let hit = hyper_sphere.intersect(ray);
-                                match (current_hit, hit) {
-                                    (None, None) => None,
-                                    (None, Some(hit)) => Some((index, hit)),
-                                    (Some(_), None) => current_hit,
-                                    (Some((current_index, current_hit)), Some(hit)) => {
-                                        if current_hit.distance < hit.distance {
-                                            Some((current_index, current_hit))
-                                        } else {
-                                            Some((index, hit))
-                                        }
-                                    }
-                                }
-                            },
-                        );
+                        let hit = closest_hit(&self.objects, ray);
 
                         println!("{hit:?}");
 
-                        if let Some((index, _)) = hit {
-                            self.selected_hyper_sphere = Some(index);
-                        } else {
-                            self.selected_hyper_sphere = None;
-                        }
+                        self.selection = hit.map(|(index, hit)| {
+                            let object = &self.objects[index];
+                            Selection {
+                                object: index,
+                                gizmo_offset: match object {
+                                    Object::HyperPlane(_) => hit.position - object.position(),
+                                    _ => cgmath::Vector4::zero(),
+                                },
+                            }
+                        });
                     }
                 }
 
@@ -450,12 +746,204 @@ impl State {
         }
     }
 
+    /// Applies an edit to the scene and records it so it can be undone
+    pub fn edit(&mut self, edit: Edit) {
+        self.commit_ui_edit();
+        self.end_gizmo_drag();
+        self.apply_edit(&edit);
+        self.history.record(edit);
+    }
+
+    pub fn undo(&mut self) {
+        self.commit_ui_edit();
+        self.end_gizmo_drag();
+        if let Some(edit) = self.history.undo() {
+            self.apply_edit(&edit);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        self.commit_ui_edit();
+        self.end_gizmo_drag();
+        if let Some(edit) = self.history.redo() {
+            self.apply_edit(&edit);
+        }
+    }
+
+    /// Where new objects are placed, a few units in front of the camera
+    fn spawn_position(&self) -> cgmath::Vector4<f32> {
+        let forward = self.camera.get_rotation().rotate(Camera::FORWARD);
+        self.camera.position + forward * SPAWN_DISTANCE
+    }
+
+    pub fn spawn_hyper_sphere(&mut self) {
+        self.edit(Edit::AddObject {
+            index: self.objects.len(),
+            object: Object::HyperSphere(HyperSphere {
+                position: self.spawn_position(),
+                radius: 1.0,
+                material: 0,
+            }),
+        });
+    }
+
+    /// Adds a hyperplane facing the camera
+    pub fn spawn_hyper_plane(&mut self) {
+        let forward = self.camera.get_rotation().rotate(Camera::FORWARD);
+        self.edit(Edit::AddObject {
+            index: self.objects.len(),
+            object: Object::HyperPlane(HyperPlane {
+                position: self.spawn_position(),
+                normal: -forward,
+                material: 0,
+            }),
+        });
+    }
+
+    pub fn spawn_hyper_cube(&mut self) {
+        self.edit(Edit::AddObject {
+            index: self.objects.len(),
+            object: Object::HyperCube(HyperCube {
+                position: self.spawn_position(),
+                half_extents: cgmath::vec4(0.5, 0.5, 0.5, 0.5),
+                rotation: Rotor::IDENTITY,
+                material: 0,
+            }),
+        });
+    }
+
+    /// Adds a spherinder, the other hyper cylinder shapes can be picked in the inspector
+    pub fn spawn_hyper_cylinder(&mut self) {
+        self.edit(Edit::AddObject {
+            index: self.objects.len(),
+            object: Object::HyperCylinder(HyperCylinder {
+                position: self.spawn_position(),
+                rotation: Rotor::IDENTITY,
+                shape: HyperCylinderShape::Spherinder {
+                    radius: 0.5,
+                    half_height: 0.5,
+                },
+                material: 0,
+            }),
+        });
+    }
+
+    /// Adds a tiger, the other tori can be picked in the inspector
+    pub fn spawn_hyper_torus(&mut self) {
+        self.edit(Edit::AddObject {
+            index: self.objects.len(),
+            object: Object::HyperTorus(HyperTorus {
+                position: self.spawn_position(),
+                rotation: Rotor::IDENTITY,
+                shape: HyperTorusShape::Tiger {
+                    major_radii: cgmath::vec2(0.6, 0.6),
+                    minor_radius: 0.25,
+                },
+                material: 0,
+            }),
+        });
+    }
+
+    /// Adds a mesh that is moved into place by `transform`, like the ones made from 3d meshes by `TriangleMesh::extrude` and `TriangleMesh::spin`
+    pub fn add_tetrahedron_mesh(&mut self, mesh: Mesh, transform: Transform) {
+        self.edit(Edit::AddObject {
+            index: self.objects.len(),
+            object: Object::TetrahedronMesh(TetrahedronMesh {
+                position: transform.transform(cgmath::Vector4::zero()),
+                rotation: transform.rotor_part().normalized(),
+                mesh: Arc::new(mesh),
+                material: 0,
+                cell_materials: vec![],
+            }),
+        });
+    }
+
+    /// Adds a copy of the selected object in the same place, the copy is selected so it can be moved away with the gizmo
+    pub fn duplicate_selected(&mut self) {
+        if let Some(selection) = self.selection {
+            let index = self.objects.len();
+            self.edit(Edit::AddObject {
+                index,
+                object: self.objects[selection.object].clone(),
+            });
+            self.selection = Some(Selection {
+                object: index,
+                ..selection
+            });
+        }
+    }
+
+    pub fn delete_selected(&mut self) {
+        if let Some(selection) = self.selection {
+            self.edit(Edit::DeleteObject {
+                index: selection.object,
+                object: self.objects[selection.object].clone(),
+            });
+        }
+    }
+
+    fn apply_edit(&mut self, edit: &Edit) {
+        edit.apply(&mut self.objects, &mut self.materials);
+        self.bvh = Bvh::build(&self.objects);
+        self.gizmo_interaction = None;
+        self.gpu_objects.invalidate();
+        self.scene_changed = true;
+
+        // keep the selection pointing at the same object, or at the object that was just changed
+        match *edit {
+            Edit::ModifyObject { index, .. } => {
+                self.selection = match self.selection {
+                    Some(selection) if selection.object == index => Some(selection),
+                    _ => Some(Selection::new(index)),
+                };
+            }
+            Edit::AddObject { index, .. } => {
+                self.selection = Some(Selection::new(index));
+            }
+            Edit::DeleteObject { index, .. } => {
+                self.selection = match self.selection {
+                    Some(selection) if selection.object == index => None,
+                    Some(selection) if selection.object > index => Some(Selection {
+                        object: selection.object - 1,
+                        ..selection
+                    }),
+                    selection => selection,
+                };
+            }
+            Edit::ModifyMaterial { .. } => {}
+        }
+    }
+
+    /// Records the current gizmo drag as a single edit, so the whole drag can be undone at once
+    fn end_gizmo_drag(&mut self) {
+        let Some(interaction) = &mut self.gizmo_interaction else {
+            return;
+        };
+        let Some(before) = interaction.drag_start.take() else {
+            return;
+        };
+        let index = self
+            .selection
+            .expect("there should be a selected object while dragging the gizmo")
+            .object;
+
+        self.history
+            .record_object_change(index, before, self.objects[index].clone());
+
+        // the bvh was only refit while dragging
+        self.bvh = Bvh::build(&self.objects);
+        self.gpu_objects.invalidate();
+    }
+
     pub fn focused(&mut self, focused: bool, window: &winit::window::Window) {
         if !focused {
             _ = window.set_cursor_grab(winit::window::CursorGrabMode::None);
             window.set_cursor_visible(true);
             self.mouse_locked = false;
-            self.axis_line_interaction = None;
+            self.end_gizmo_drag();
+            self.gizmo_interaction = None;
+            self.control_held = false;
+            self.shift_held = false;
 
             self.camera.reset_keys();
         }
@@ -463,97 +951,122 @@ impl State {
 
     pub fn mouse_scrolled(&mut self, delta: cgmath::Vector2<f32>) {
         if self.mouse_locked {
-            self.camera.mouse_scrolled(delta);
+            self.update_camera(|camera| camera.mouse_scrolled(delta));
         }
     }
 
     pub fn mouse_moved(&mut self, delta: cgmath::Vector2<f32>) {
         if self.mouse_locked {
-            self.camera.mouse_moved(delta);
+            self.update_camera(|camera| camera.mouse_moved(delta));
         }
     }
 
     pub fn cursor_moved(&mut self, uv: cgmath::Vector2<f32>) {
-        if !self.mouse_locked {
-            if let Some(selected_hyper_sphere) = self.selected_hyper_sphere {
-                'axis_lines: {
-                    let axis_lines = Self::get_axis_lines(
-                        &self.camera,
-                        &self.objects[selected_hyper_sphere],
-                        self.use_camera_axes,
-                    );
-
-                    let axis_lines = axis_lines.map(|axis_line| {
-                        axis_line.and_then(|(axis_line, _)| {
-                            let a_to_b = axis_line.b - axis_line.a;
-                            let line_length = a_to_b.magnitude();
-                            if line_length < 0.01 {
-                                return None;
-                            }
-                            let a_to_b = a_to_b / line_length;
-                            let normal = cgmath::vec2(a_to_b.y, -a_to_b.x);
-
-                            let pos = (uv - axis_line.a).dot(a_to_b) / line_length;
-                            let dist = (uv - axis_line.a).dot(normal).abs();
-
-                            if dist > axis_line.width * 4.0
-                                && self
-                                    .axis_line_interaction
-                                    .as_ref()
-                                    .is_none_or(|interaction| !interaction.dragging)
-                            {
-                                return None;
-                            }
-
-                            Some((pos, dist))
-                        })
-                    });
-
-                    if let Some(interaction) = &mut self.axis_line_interaction {
-                        if interaction.dragging {
-                            if let Some((pos, _)) = axis_lines[interaction.axis_index] {
-                                let object = &mut self.objects[self.selected_hyper_sphere.expect(
-                                    "if an axis is being dragged a hyper sphere must be selected",
-                                )];
-                                let axis = Self::axis_from_index(
-                                    interaction.axis_index,
-                                    self.use_camera_axes.then(|| self.camera.get_rotation()),
-                                );
-                                let pos_delta = pos - interaction.start_pos;
-                                object.move_position(axis * pos_delta);
-                                break 'axis_lines;
-                            }
-                        }
-                    }
+        if self.mouse_locked {
+            return;
+        }
+        let Some(selection) = self.selection else {
+            return;
+        };
+        let gizmo_position = self.objects[selection.object].position() + selection.gizmo_offset;
 
-                    let closest = axis_lines
-                        .into_iter()
-                        .enumerate()
-                        .filter_map(|(index, distances)| {
-                            distances.and_then(|(pos, dist)| {
-                                (0.0..=1.0).contains(&pos).then_some((index, pos, dist))
-                            })
-                        })
-                        .min_by(|(_, _, a_dist), (_, _, b_dist)| a_dist.total_cmp(b_dist));
-
-                    if let Some((index, pos, _)) = closest {
-                        self.axis_line_interaction = Some(AxisLineInteraction {
-                            axis_index: index,
-                            start_pos: pos,
-                            dragging: false,
-                        });
-                    } else {
-                        self.axis_line_interaction = None;
+        if let Some(interaction) = &mut self.gizmo_interaction
+            && interaction.drag_start.is_some()
+        {
+            let rotation = self.use_camera_axes.then(|| self.camera.get_rotation());
+            let object = &mut self.objects[selection.object];
+            match interaction.handle {
+                GizmoHandle::Axis(axis_index) => {
+                    let axis_lines =
+                        Self::get_axis_lines(&self.camera, gizmo_position, self.use_camera_axes);
+                    if let Some((axis_line, _)) = &axis_lines[axis_index]
+                        && let Some((pos, _)) = Self::line_distance(axis_line, uv)
+                    {
+                        let axis = Self::axis_from_index(axis_index, rotation);
+                        let pos_delta = pos - interaction.start_pos;
+                        object.move_position(axis * pos_delta);
                     }
                 }
+                GizmoHandle::Plane(plane_index) => {
+                    let angle = (uv - interaction.start_uv).dot(interaction.angle_direction)
+                        / interaction.angle_direction.magnitude2();
+                    let plane_rotation =
+                        Self::plane_rotation(plane_index, angle - interaction.applied_angle);
+                    let plane_rotation = match rotation {
+                        Some(rotation) => rotation * plane_rotation * !rotation,
+                        None => plane_rotation,
+                    };
+                    object.rotate_around(plane_rotation, gizmo_position);
+                    // the gizmo stays where it is, so it has to be rotated along with the position it is relative to
+                    self.selection = Some(Selection {
+                        gizmo_offset: plane_rotation.rotate(selection.gizmo_offset),
+                        ..selection
+                    });
+                    interaction.applied_angle = angle;
+                }
             }
+            self.bvh.refit(&self.objects);
+            self.gpu_objects.invalidate();
+            self.scene_changed = true;
+            return;
         }
+
+        let closest_axis = Self::get_axis_lines(&self.camera, gizmo_position, self.use_camera_axes)
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, axis_line)| {
+                let (axis_line, _) = axis_line?;
+                let (pos, dist) = Self::line_distance(&axis_line, uv)?;
+                ((0.0..=1.0).contains(&pos) && dist <= axis_line.width * 4.0).then_some((
+                    GizmoInteraction {
+                        handle: GizmoHandle::Axis(index),
+                        start_pos: pos,
+                        start_uv: uv,
+                        angle_direction: cgmath::vec2(0.0, 0.0),
+                        applied_angle: 0.0,
+                        drag_start: None,
+                    },
+                    dist,
+                ))
+            });
+
+        let segment_angle = (ROTATION_ARC_ANGLES.end() - ROTATION_ARC_ANGLES.start())
+            / ROTATION_ARC_SEGMENTS as f32;
+        let closest_plane =
+            Self::get_rotation_arcs(&self.camera, gizmo_position, self.use_camera_axes)
+                .into_iter()
+                .enumerate()
+                .flat_map(|(index, segments)| segments.into_iter().map(move |line| (index, line)))
+                .filter_map(|(index, (segment, _))| {
+                    let (pos, dist) = Self::line_distance(&segment, uv)?;
+                    ((0.0..=1.0).contains(&pos) && dist <= segment.width * 4.0).then_some((
+                        GizmoInteraction {
+                            handle: GizmoHandle::Plane(index),
+                            start_pos: 0.0,
+                            start_uv: uv,
+                            angle_direction: (segment.b - segment.a) / segment_angle,
+                            applied_angle: 0.0,
+                            drag_start: None,
+                        },
+                        dist,
+                    ))
+                });
+
+        self.gizmo_interaction = closest_axis
+            .chain(closest_plane)
+            .min_by(|(_, a_dist), (_, b_dist)| a_dist.total_cmp(b_dist))
+            .map(|(interaction, _)| interaction);
     }
 
     pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.ray_tracing_texture.width() == width && self.ray_tracing_texture.height() == height
+        {
+            return;
+        }
+
         (
             self.ray_tracing_texture,
-            self.ray_tracing_texture_output_bind_group,
+            self.ray_tracing_texture_output_bind_groups,
             self.ray_tracing_texture_render_bind_group,
         ) = ray_tracing_texture_and_bind_groups(
             device,
@@ -564,6 +1077,8 @@ impl State {
         );
 
         self.final_texture = final_texture(device, width, height);
+        self.final_resolve_texture = final_resolve_texture(device, width, height);
+        self.accumulated_frames = 0;
     }
 
     fn axis_from_index(index: usize, rotation: Option<Rotor>) -> cgmath::Vector4<f32> {
@@ -581,41 +1096,49 @@ impl State {
         }
     }
 
+    /// Returns how far along the line the point is (0 at `a`, 1 at `b`) and how far away from the line it is
+    fn line_distance(line: &GpuLine, point: cgmath::Vector2<f32>) -> Option<(f32, f32)> {
+        let a_to_b = line.b - line.a;
+        let line_length = a_to_b.magnitude();
+        if line_length < 0.01 {
+            return None;
+        }
+        let a_to_b = a_to_b / line_length;
+        let normal = cgmath::vec2(a_to_b.y, -a_to_b.x);
+
+        let pos = (point - line.a).dot(a_to_b) / line_length;
+        let dist = (point - line.a).dot(normal).abs();
+        Some((pos, dist))
+    }
+
+    fn plane_rotation(index: usize, angle: f32) -> Rotor {
+        match ROTATION_PLANES[index] {
+            (0, 1) => Rotor::rotation_xy(angle),
+            (0, 2) => Rotor::rotation_xz(angle),
+            (0, 3) => Rotor::rotation_xw(angle),
+            (1, 2) => Rotor::rotation_yz(angle),
+            (1, 3) => Rotor::rotation_yw(angle),
+            (2, 3) => Rotor::rotation_zw(angle),
+            _ => unreachable!("all the rotation planes should be covered"),
+        }
+    }
+
     fn get_axis_lines(
         camera: &Camera,
-        object: &Object,
+        object_position: cgmath::Vector4<f32>,
         use_camera_axes: bool,
     ) -> [Option<(GpuLine, f32)>; 4] {
         let camera_transform =
             Transform::translation(camera.position) * Transform::from_rotor(camera.get_rotation());
 
         // applying the inverse camera transform to the position
-        let object_position = object.position();
         let position = (!camera_transform).transform(object_position);
         if position.x >= 0.0 {
             let position = cgmath::vec2(position.z / position.x, position.y / position.x);
 
-            let axis_lines = {
-                let rotation = use_camera_axes.then_some(camera_transform.rotor_part());
-                [
-                    (
-                        Self::axis_from_index(0, rotation),
-                        cgmath::vec4(1.0, 0.2, 0.2, 1.0),
-                    ),
-                    (
-                        Self::axis_from_index(1, rotation),
-                        cgmath::vec4(0.2, 1.0, 0.2, 1.0),
-                    ),
-                    (
-                        Self::axis_from_index(2, rotation),
-                        cgmath::vec4(0.2, 0.2, 1.0, 1.0),
-                    ),
-                    (
-                        Self::axis_from_index(3, rotation),
-                        cgmath::vec4(1.0, 0.2, 1.0, 1.0),
-                    ),
-                ]
-            };
+            let rotation = use_camera_axes.then_some(camera_transform.rotor_part());
+            let axis_lines = [0, 1, 2, 3]
+                .map(|index| (Self::axis_from_index(index, rotation), AXIS_COLORS[index]));
 
             axis_lines.map(|(axis_offset, axis_color)| {
                 let end_point = (!camera_transform).transform(object_position + axis_offset);
@@ -638,61 +1161,193 @@ impl State {
         }
     }
 
-    fn objects_to_gpu_objects(objects: &[Object]) -> (Vec<GpuHyperSphere>, Vec<GpuHyperPlane>) {
-        let mut hyper_spheres = vec![];
-        let mut hyper_planes = vec![];
-        for object in objects {
-            match object {
-                Object::HyperSphere(hyper_sphere) => {
-                    hyper_spheres.push(GpuHyperSphere::from_hyper_sphere(hyper_sphere));
-                }
-                Object::HyperPlane(hyper_plane) => {
-                    hyper_planes.push(GpuHyperPlane::from_hyper_plane(hyper_plane));
-                }
-            }
+    /// Returns the line segments of the arc for each rotation plane, along with their distance from the camera
+    fn get_rotation_arcs(
+        camera: &Camera,
+        object_position: cgmath::Vector4<f32>,
+        use_camera_axes: bool,
+    ) -> [Vec<(GpuLine, f32)>; 6] {
+        let camera_transform =
+            Transform::translation(camera.position) * Transform::from_rotor(camera.get_rotation());
+        let rotation = use_camera_axes.then_some(camera_transform.rotor_part());
+
+        ROTATION_PLANES.map(|(a, b)| {
+            let a_axis = Self::axis_from_index(a, rotation);
+            let b_axis = Self::axis_from_index(b, rotation);
+            let color = (AXIS_COLORS[a] + AXIS_COLORS[b]) * 0.5;
+
+            let points = (0..=ROTATION_ARC_SEGMENTS)
+                .map(|i| {
+                    let t = i as f32 / ROTATION_ARC_SEGMENTS as f32;
+                    let angle = ROTATION_ARC_ANGLES.start()
+                        + (ROTATION_ARC_ANGLES.end() - ROTATION_ARC_ANGLES.start()) * t;
+                    let (sin, cos) = angle.sin_cos();
+                    let offset = (a_axis * cos + b_axis * sin) * ROTATION_ARC_RADIUS;
+
+                    // applying the inverse camera transform to the point
+                    let point = (!camera_transform).transform(object_position + offset);
+                    (point.x >= 0.0)
+                        .then(|| (cgmath::vec2(point.z / point.x, point.y / point.x), point.x))
+                })
+                .collect::<Vec<_>>();
+
+            points
+                .windows(2)
+                .filter_map(|points| {
+                    let (a, a_distance) = points[0]?;
+                    let (b, b_distance) = points[1]?;
+                    Some((
+                        GpuLine {
+                            a,
+                            b,
+                            width: 0.01,
+                            color,
+                        },
+                        (a_distance + b_distance) * 0.5,
+                    ))
+                })
+                .collect()
+        })
+    }
+
+    /// The crosshair, and the gizmo for the selected object with the furthest lines first
+    fn overlay_lines(&self) -> Vec<GpuLine> {
+        let mut lines = vec![
+            GpuLine {
+                a: cgmath::vec2(0.02, 0.0),
+                b: cgmath::vec2(-0.02, 0.0),
+                width: 0.005,
+                color: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+            },
+            GpuLine {
+                a: cgmath::vec2(0.0, 0.02),
+                b: cgmath::vec2(0.0, -0.02),
+                width: 0.005,
+                color: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+            },
+        ];
+
+        if let Some(selection) = self.selection {
+            let gizmo_position = self.objects[selection.object].position() + selection.gizmo_offset;
+            let axis_lines =
+                Self::get_axis_lines(&self.camera, gizmo_position, self.use_camera_axes)
+                    .into_iter()
+                    .enumerate()
+                    .filter_map(|(index, line)| Some((GizmoHandle::Axis(index), line?)));
+            let rotation_arcs =
+                Self::get_rotation_arcs(&self.camera, gizmo_position, self.use_camera_axes)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(index, segments)| {
+                        segments
+                            .into_iter()
+                            .map(move |segment| (GizmoHandle::Plane(index), segment))
+                    });
+
+            let mut gizmo_lines = axis_lines
+                .chain(rotation_arcs)
+                .map(|(handle, (mut line, distance))| {
+                    if self
+                        .gizmo_interaction
+                        .as_ref()
+                        .is_some_and(|interaction| interaction.handle == handle)
+                    {
+                        line.color *= 2.0;
+                    }
+                    (line, distance)
+                })
+                .collect::<Vec<_>>();
+            gizmo_lines.sort_by(|(_, distance_a), (_, distance_b)| {
+                distance_a.total_cmp(distance_b).reverse()
+            });
+            lines.extend(gizmo_lines.into_iter().map(|(line, _)| line));
         }
-        (hyper_spheres, hyper_planes)
+        lines
     }
 
-    pub fn render(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, texture: &wgpu::Texture) {
+    /// Draws a frame into `texture`, with the crosshair, gizmos, selection highlight and editor on top when `overlay` is set.
+    /// Offscreen renders leave the overlay out so they are clean pictures of the scene
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        overlay: bool,
+    ) {
         let wgpu::Extent3d { width, height, .. } = texture.size();
         assert_eq!(texture.size(), self.ray_tracing_texture.size());
         assert_eq!(texture.size(), self.final_texture.size());
+        assert_eq!(texture.size(), self.final_resolve_texture.size());
+
+        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Main Rendering Encoder"),
+        });
+
+        // the editor can change the scene, so it has to run before anything is uploaded
+        let editor_frame = if overlay {
+            self.prepare_editor(device, queue, &mut command_encoder, width, height)
+        } else {
+            None
+        };
+
+        if self.scene_changed {
+            self.accumulated_frames = 0;
+            self.scene_changed = false;
+        }
 
         self.camera_buffer.write(
             device,
             queue,
             (Some(&GpuCamera::from_camera(&self.camera)),),
         );
-
+        if let Some(GpuObjects {
+            hyper_spheres,
+            hyper_planes,
+            hyper_cubes,
+            hyper_cylinders,
+            csg_nodes,
+            sdf_nodes,
+            bvh_nodes,
+            tetrahedra,
+        }) = self.gpu_objects.update(&self.objects, &self.bvh)
         {
-            let (hyper_spheres, hyper_planes) = Self::objects_to_gpu_objects(&self.objects);
+            let materials = self
+                .materials
+                .iter()
+                .map(GpuMaterial::from_material)
+                .collect::<Vec<_>>();
             self.objects_buffer.write(
                 device,
                 queue,
                 (
-                    Some(
-                        &self
-                            .materials
-                            .iter()
-                            .map(GpuMaterial::from_material)
-                            .collect::<Vec<_>>(),
-                    ),
-                    Some(&GpuLengthArray {
-                        length: ArrayLength,
-                        data: hyper_spheres,
-                    }),
-                    Some(&GpuLengthArray {
-                        length: ArrayLength,
-                        data: hyper_planes,
-                    }),
+                    Some(&materials),
+                    Some(hyper_spheres),
+                    Some(hyper_planes),
+                    Some(hyper_cubes),
+                    Some(bvh_nodes),
+                    Some(csg_nodes),
+                    Some(sdf_nodes),
+                    Some(hyper_cylinders),
+                    Some(tetrahedra),
                 ),
             );
         }
+        self.render_settings_buffer.write(
+            device,
+            queue,
+            (Some(&GpuRenderSettings {
+                path_tracing: self.path_tracing.into(),
+                accumulated_frames: self.accumulated_frames,
+                max_bounces: self.max_bounces,
+                selected_object: self
+                    .selection
+                    .filter(|_| overlay)
+                    .map_or(GpuRenderSettings::NOTHING_SELECTED, |selection| {
+                        selection.object as u32
+                    }),
+            }),),
+        );
 
-        let mut command_encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
-            label: Some("Main Rendering Encoder"),
-        });
         {
             let mut compute_pass =
                 command_encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
@@ -701,33 +1356,48 @@ impl State {
                 });
 
             compute_pass.set_pipeline(&self.ray_tracing_pipeline);
-            compute_pass.set_bind_group(0, &self.ray_tracing_texture_output_bind_group, &[]);
+            compute_pass.set_bind_group(
+                0,
+                &self.ray_tracing_texture_output_bind_groups[self.accumulated_frames as usize % 2],
+                &[],
+            );
             compute_pass.set_bind_group(1, self.camera_buffer.bind_group(), &[]);
             compute_pass.set_bind_group(2, self.objects_buffer.bind_group(), &[]);
+            compute_pass.set_bind_group(3, self.render_settings_buffer.bind_group(), &[]);
             compute_pass.dispatch_workgroups(width.div_ceil(16), height.div_ceil(16), 1);
         }
+        if self.path_tracing {
+            self.accumulated_frames += 1;
+        }
 
         {
-            let mut render_pass = command_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-                label: Some("Main Render Pass"),
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &self
-                        .final_texture
-                        .create_view(&wgpu::TextureViewDescriptor::default()),
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 1.0,
-                            g: 0.0,
-                            b: 1.0,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
-                    },
-                })],
-                depth_stencil_attachment: None,
-                ..Default::default()
-            });
+            let mut render_pass = command_encoder
+                .begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("Main Render Pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &self
+                            .final_texture
+                            .create_view(&wgpu::TextureViewDescriptor::default()),
+                        resolve_target: Some(
+                            &self
+                                .final_resolve_texture
+                                .create_view(&wgpu::TextureViewDescriptor::default()),
+                        ),
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color {
+                                r: 1.0,
+                                g: 0.0,
+                                b: 1.0,
+                                a: 1.0,
+                            }),
+                            store: wgpu::StoreOp::Store,
+                        },
+                    })],
+                    depth_stencil_attachment: None,
+                    ..Default::default()
+                })
+                // egui needs a render pass that doesnt borrow the encoder
+                .forget_lifetime();
 
             render_pass.set_pipeline(&self.ray_tracing_render_pipeline);
             render_pass.set_bind_group(0, &self.ray_tracing_texture_render_bind_group, &[]);
@@ -736,45 +1406,11 @@ impl State {
             let info = GpuUiInfo {
                 aspect: width as f32 / height as f32,
             };
-            let mut lines = vec![
-                GpuLine {
-                    a: cgmath::vec2(0.02, 0.0),
-                    b: cgmath::vec2(-0.02, 0.0),
-                    width: 0.005,
-                    color: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
-                },
-                GpuLine {
-                    a: cgmath::vec2(0.0, 0.02),
-                    b: cgmath::vec2(0.0, -0.02),
-                    width: 0.005,
-                    color: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
-                },
-            ];
-
-            if let Some(index) = self.selected_hyper_sphere {
-                let mut axis_lines =
-                    Self::get_axis_lines(&self.camera, &self.objects[index], self.use_camera_axes);
-                for (index, line) in axis_lines.iter_mut().enumerate() {
-                    if let Some((line, _)) = line {
-                        if self
-                            .axis_line_interaction
-                            .as_ref()
-                            .is_some_and(|interaction| interaction.axis_index == index)
-                        {
-                            line.color *= 2.0;
-                        }
-                    }
-                }
-                axis_lines.sort_by(|a, b| match (a, b) {
-                    (None, None) => std::cmp::Ordering::Equal,
-                    (None, Some(_)) => std::cmp::Ordering::Greater,
-                    (Some(_), None) => std::cmp::Ordering::Less,
-                    (Some((_, distance_a)), Some((_, distance_b))) => {
-                        distance_a.total_cmp(distance_b).reverse()
-                    }
-                });
-                lines.extend(axis_lines.into_iter().flatten().map(|(line, _)| line));
-            }
+            let lines = if overlay {
+                self.overlay_lines()
+            } else {
+                vec![]
+            };
 
             self.ui_buffer
                 .write(device, queue, (Some(&info), Some(&lines)));
@@ -788,14 +1424,28 @@ impl State {
                     .try_into()
                     .expect("there should be less than u32::MAX lines"),
             );
+
+            if let Some(editor_frame) = &editor_frame {
+                self.egui_renderer.render(
+                    &mut render_pass,
+                    &editor_frame.paint_jobs,
+                    &editor_frame.screen_descriptor,
+                );
+            }
         }
         command_encoder.copy_texture_to_texture(
-            self.final_texture.as_image_copy(),
+            self.final_resolve_texture.as_image_copy(),
             texture.as_image_copy(),
             texture.size(),
         );
 
         queue.submit(std::iter::once(command_encoder.finish()));
+
+        if let Some(editor_frame) = editor_frame {
+            for id in &editor_frame.textures_to_free {
+                self.egui_renderer.free_texture(id);
+            }
+        }
     }
 }
 
@@ -805,16 +1455,38 @@ fn ray_tracing_texture_bind_group_layouts(
     let output_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("Ray Tracing Texture Output Bind Group Layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStages::COMPUTE,
-                ty: wgpu::BindingType::StorageTexture {
-                    access: wgpu::StorageTextureAccess::WriteOnly,
-                    format: wgpu::TextureFormat::Rgba32Float,
-                    view_dimension: wgpu::TextureViewDimension::D2,
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::Texture {
+                        multisampled: false,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    },
+                    count: None,
                 },
-                count: None,
-            }],
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStages::COMPUTE,
+                    ty: wgpu::BindingType::StorageTexture {
+                        access: wgpu::StorageTextureAccess::WriteOnly,
+                        format: wgpu::TextureFormat::Rgba32Float,
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+            ],
         });
     let render_bind_group_layout =
         device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -847,22 +1519,30 @@ fn ray_tracing_texture_and_bind_groups(
     height: u32,
     output_layout: &wgpu::BindGroupLayout,
     render_layout: &wgpu::BindGroupLayout,
-) -> (wgpu::Texture, wgpu::BindGroup, wgpu::BindGroup) {
-    let texture = device.create_texture(&wgpu::TextureDescriptor {
-        label: Some("Ray Tracing Texture"),
-        size: wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        },
-        mip_level_count: 1,
-        sample_count: 1,
-        dimension: wgpu::TextureDimension::D2,
-        format: wgpu::TextureFormat::Rgba32Float,
-        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
-        view_formats: &[],
-    });
+) -> (wgpu::Texture, [wgpu::BindGroup; 2], wgpu::BindGroup) {
+    let create_texture = |label| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        })
+    };
+    let texture = create_texture("Ray Tracing Texture");
     let texture_view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let accumulation_texture_views = [
+        "Ray Tracing Accumulation Texture 0",
+        "Ray Tracing Accumulation Texture 1",
+    ]
+    .map(|label| create_texture(label).create_view(&wgpu::TextureViewDescriptor::default()));
     let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
         label: Some("Ray Tracing Texture Sampler"),
         address_mode_u: wgpu::AddressMode::ClampToEdge,
@@ -873,13 +1553,29 @@ fn ray_tracing_texture_and_bind_groups(
         mipmap_filter: wgpu::FilterMode::Nearest,
         ..Default::default()
     });
-    let output_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-        label: Some("Ray Tracing Texture Output Bind Group"),
-        layout: output_layout,
-        entries: &[wgpu::BindGroupEntry {
-            binding: 0,
-            resource: wgpu::BindingResource::TextureView(&texture_view),
-        }],
+    let output_bind_groups = [0, 1].map(|write_index| {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Ray Tracing Texture Output Bind Group"),
+            layout: output_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(
+                        &accumulation_texture_views[1 - write_index],
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(
+                        &accumulation_texture_views[write_index],
+                    ),
+                },
+            ],
+        })
     });
     let render_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
         label: Some("Ray Tracing Texture Render Bind Group"),
@@ -895,7 +1591,7 @@ fn ray_tracing_texture_and_bind_groups(
             },
         ],
     });
-    (texture, output_bind_group, render_bind_group)
+    (texture, output_bind_groups, render_bind_group)
 }
 
 fn final_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
@@ -910,6 +1606,23 @@ fn final_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Textur
         sample_count: RENDER_SAMPLES,
         dimension: wgpu::TextureDimension::D2,
         format: wgpu::TextureFormat::Bgra8Unorm,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
+
+fn final_resolve_texture(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Final Resolve Texture"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Bgra8Unorm,
         usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
         view_formats: &[],
     })
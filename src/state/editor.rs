@@ -0,0 +1,988 @@
+//! The egui panel for editing the scene, it is drawn on top of everything else in `State::render`
+
+use super::{MAX_TRACED_BOUNCES, Selection, State};
+use crate::{
+    bvh::Bvh,
+    history::Edit,
+    material::{Material, MaterialKind},
+    math::{Bivector, RotationKind, Rotor, Transform},
+    objects::{CsgOperation, HyperCylinderShape, HyperTorusShape, Object},
+    polytope::{Polytope, RegularPolychoron},
+    triangle_mesh::TriangleMesh,
+};
+use cgmath::InnerSpace;
+
+pub(super) struct EditorFrame {
+    pub paint_jobs: Vec<egui::ClippedPrimitive>,
+    pub screen_descriptor: egui_wgpu::ScreenDescriptor,
+    pub textures_to_free: Vec<egui::TextureId>,
+}
+
+/// The settings for turning an obj file into a 4d mesh, which are kept between imports
+pub(super) struct MeshImport {
+    path: String,
+    /// Spins the mesh instead of extruding it
+    spin: bool,
+    half_depth: f32,
+    segments: u32,
+    error: Option<String>,
+}
+
+impl Default for MeshImport {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            spin: false,
+            half_depth: 0.5,
+            segments: 24,
+            error: None,
+        }
+    }
+}
+
+/// Where the Save Scene panel saves the scene to, which is kept between saves
+pub(super) struct SceneSave {
+    path: String,
+    /// What happened the last time the scene was saved
+    result: Option<Result<(), String>>,
+}
+
+impl Default for SceneSave {
+    fn default() -> Self {
+        Self {
+            path: "scene.ron".to_string(),
+            result: None,
+        }
+    }
+}
+
+/// The polytope that the Add Polytope panel adds, which is kept between adds
+pub(super) struct PolytopeImport {
+    /// Loads the 4OFF file at `path` instead of making `polychoron`
+    from_file: bool,
+    polychoron: RegularPolychoron,
+    path: String,
+    error: Option<String>,
+}
+
+impl Default for PolytopeImport {
+    fn default() -> Self {
+        Self {
+            from_file: false,
+            polychoron: RegularPolychoron::Tesseract,
+            path: String::new(),
+            error: None,
+        }
+    }
+}
+
+impl State {
+    /// Runs the editor ui for this frame and uploads everything it needs to draw, returns `None` if there was no ui input
+    pub(super) fn prepare_editor(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        command_encoder: &mut wgpu::CommandEncoder,
+        width: u32,
+        height: u32,
+    ) -> Option<EditorFrame> {
+        let input = self.ui_input.take()?;
+
+        let context = self.egui_context.clone();
+        let output = context.run(input, |context| self.editor_ui(context));
+        self.ui_platform_output.append(output.platform_output);
+
+        let paint_jobs = context.tessellate(output.shapes, output.pixels_per_point);
+        let screen_descriptor = egui_wgpu::ScreenDescriptor {
+            size_in_pixels: [width, height],
+            pixels_per_point: output.pixels_per_point,
+        };
+        for (id, image_delta) in &output.textures_delta.set {
+            self.egui_renderer
+                .update_texture(device, queue, *id, image_delta);
+        }
+        let command_buffers = self.egui_renderer.update_buffers(
+            device,
+            queue,
+            command_encoder,
+            &paint_jobs,
+            &screen_descriptor,
+        );
+        queue.submit(command_buffers);
+
+        Some(EditorFrame {
+            paint_jobs,
+            screen_descriptor,
+            textures_to_free: output.textures_delta.free,
+        })
+    }
+
+    fn editor_ui(&mut self, context: &egui::Context) {
+        egui::SidePanel::left("Editor")
+            .default_width(260.0)
+            .show(context, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui
+                            .add_enabled(self.history.can_undo(), egui::Button::new("Undo"))
+                            .clicked()
+                        {
+                            self.undo();
+                        }
+                        if ui
+                            .add_enabled(self.history.can_redo(), egui::Button::new("Redo"))
+                            .clicked()
+                        {
+                            self.redo();
+                        }
+                    });
+
+                    egui::CollapsingHeader::new("Save Scene").show(ui, |ui| self.scene_save_ui(ui));
+                    egui::CollapsingHeader::new("Objects")
+                        .default_open(true)
+                        .show(ui, |ui| self.outliner_ui(ui));
+                    egui::CollapsingHeader::new("Import Mesh")
+                        .show(ui, |ui| self.mesh_import_ui(ui));
+                    egui::CollapsingHeader::new("Add Polytope")
+                        .show(ui, |ui| self.polytope_import_ui(ui));
+                    egui::CollapsingHeader::new("Inspector")
+                        .default_open(true)
+                        .show(ui, |ui| self.inspector_ui(ui));
+                    egui::CollapsingHeader::new("Lighting").show(ui, |ui| self.lighting_ui(ui));
+                    egui::CollapsingHeader::new("Rendering").show(ui, |ui| self.rendering_ui(ui));
+                    egui::CollapsingHeader::new("Camera").show(ui, |ui| self.camera_ui(ui));
+                });
+            });
+
+        // widgets change things every frame while they are dragged or typed into, but that should only be one edit
+        if !context.is_using_pointer() && context.memory(|memory| memory.focused().is_none()) {
+            self.commit_ui_edit();
+        }
+    }
+
+    /// Saves the scene as it is now to a scene file, which can be loaded again by passing it to the app
+    fn scene_save_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Scene File");
+            ui.text_edit_singleline(&mut self.scene_save.path);
+        });
+        if ui.button("Save").clicked() {
+            let path = &self.scene_save.path;
+            let result = self
+                .scene()
+                .save(path)
+                .map_err(|error| format!("{path}: {error}"));
+            self.scene_save.result = Some(result);
+        }
+        match &self.scene_save.result {
+            Some(Ok(())) => _ = ui.label(format!("Saved to {}", self.scene_save.path)),
+            Some(Err(error)) => _ = ui.colored_label(ui.visuals().error_fg_color, error),
+            None => {}
+        }
+    }
+
+    fn outliner_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui.button("Add Sphere").clicked() {
+                self.spawn_hyper_sphere();
+            }
+            if ui.button("Add Plane").clicked() {
+                self.spawn_hyper_plane();
+            }
+            if ui.button("Add Cube").clicked() {
+                self.spawn_hyper_cube();
+            }
+            if ui.button("Add Cylinder").clicked() {
+                self.spawn_hyper_cylinder();
+            }
+            if ui.button("Add Torus").clicked() {
+                self.spawn_hyper_torus();
+            }
+        });
+        let has_selection = self.selection.is_some();
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Duplicate"))
+                .clicked()
+            {
+                self.duplicate_selected();
+            }
+            if ui
+                .add_enabled(has_selection, egui::Button::new("Delete"))
+                .clicked()
+            {
+                self.delete_selected();
+            }
+        });
+
+        egui::ScrollArea::vertical()
+            .id_salt("Outliner")
+            .max_height(200.0)
+            .show(ui, |ui| {
+                for (index, object) in self.objects.iter().enumerate() {
+                    let name = match object {
+                        Object::HyperSphere(_) => "Hyper Sphere",
+                        Object::HyperPlane(_) => "Hyper Plane",
+                        Object::HyperCube(_) => "Hyper Cube",
+                        Object::HyperCylinder(_) => "Hyper Cylinder",
+                        Object::HyperTorus(_) => "Hyper Torus",
+                        Object::TetrahedronMesh(_) => "Tetrahedron Mesh",
+                        Object::Csg(_) => "CSG",
+                        Object::Sdf(_) => "SDF",
+                    };
+                    let selected = self
+                        .selection
+                        .is_some_and(|selection| selection.object == index);
+                    if ui
+                        .selectable_label(selected, format!("{index}: {name}"))
+                        .clicked()
+                    {
+                        self.selection = (!selected).then(|| Selection::new(index));
+                        self.gizmo_interaction = None;
+                    }
+                }
+            });
+    }
+
+    fn inspector_ui(&mut self, ui: &mut egui::Ui) {
+        let Some(index) = self.selection.map(|selection| selection.object) else {
+            ui.label("Nothing selected");
+            return;
+        };
+
+        let before = self.objects[index].clone();
+        let mut object = before.clone();
+        let material_count = self.materials.len() as u32;
+        let rotation = object.rotation().map(rotation_readout);
+        let material = egui::Grid::new("Inspector")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let material = match &mut object {
+                    Object::Csg(csg) => {
+                        let mut position = csg.left.position();
+                        ui.label("Position");
+                        if vector4_ui(ui, &mut position).changed() {
+                            let offset = position - csg.left.position();
+                            csg.left.move_position(offset);
+                            csg.right.move_position(offset);
+                        }
+                        ui.end_row();
+
+                        ui.label("Operation");
+                        egui::ComboBox::from_id_salt("CSG Operation")
+                            .selected_text(csg_operation_name(csg.operation))
+                            .show_ui(ui, |ui| {
+                                for operation in [
+                                    CsgOperation::Union,
+                                    CsgOperation::Intersection,
+                                    CsgOperation::Difference,
+                                ] {
+                                    ui.selectable_value(
+                                        &mut csg.operation,
+                                        operation,
+                                        csg_operation_name(operation),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        // the objects inside can each have their own material, so there isnt one to show
+                        return None;
+                    }
+                    Object::HyperSphere(hyper_sphere) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut hyper_sphere.position);
+                        ui.end_row();
+
+                        ui.label("Radius");
+                        ui.add(
+                            egui::DragValue::new(&mut hyper_sphere.radius)
+                                .speed(0.01)
+                                .range(0.01..=f32::INFINITY),
+                        );
+                        ui.end_row();
+
+                        &mut hyper_sphere.material
+                    }
+                    Object::HyperPlane(hyper_plane) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut hyper_plane.position);
+                        ui.end_row();
+
+                        ui.label("Normal");
+                        if vector4_ui(ui, &mut hyper_plane.normal).changed()
+                            && hyper_plane.normal.magnitude2() > 0.0
+                        {
+                            hyper_plane.normal = hyper_plane.normal.normalize();
+                        }
+                        ui.end_row();
+
+                        &mut hyper_plane.material
+                    }
+                    Object::HyperCube(hyper_cube) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut hyper_cube.position);
+                        ui.end_row();
+
+                        ui.label("Half Extents");
+                        vector4_ui(ui, &mut hyper_cube.half_extents);
+                        ui.end_row();
+
+                        &mut hyper_cube.material
+                    }
+                    Object::HyperCylinder(hyper_cylinder) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut hyper_cylinder.position);
+                        ui.end_row();
+
+                        hyper_cylinder_shape_ui(ui, &mut hyper_cylinder.shape);
+
+                        &mut hyper_cylinder.material
+                    }
+                    Object::HyperTorus(hyper_torus) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut hyper_torus.position);
+                        ui.end_row();
+
+                        hyper_torus_shape_ui(ui, &mut hyper_torus.shape);
+
+                        &mut hyper_torus.material
+                    }
+                    Object::TetrahedronMesh(tetrahedron_mesh) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut tetrahedron_mesh.position);
+                        ui.end_row();
+
+                        ui.label("Cells");
+                        ui.label(tetrahedron_mesh.mesh.cells().len().to_string());
+                        ui.end_row();
+
+                        &mut tetrahedron_mesh.material
+                    }
+                    Object::Sdf(sdf) => {
+                        ui.label("Position");
+                        vector4_ui(ui, &mut sdf.position);
+                        ui.end_row();
+
+                        &mut sdf.material
+                    }
+                };
+
+                if let Some(rotation) = &rotation {
+                    ui.label("Rotation");
+                    ui.label(rotation);
+                    ui.end_row();
+                }
+
+                ui.label("Material");
+                ui.add(egui::DragValue::new(material).range(0..=material_count.saturating_sub(1)));
+                ui.end_row();
+
+                Some(*material)
+            })
+            .inner;
+
+        if object != before {
+            self.ui_edit(Edit::ModifyObject {
+                index,
+                before,
+                after: object,
+            });
+        }
+
+        let Some(index) = material.map(|material| material as usize) else {
+            return;
+        };
+        let Some(before) = self.materials.get(index).cloned() else {
+            return;
+        };
+        ui.separator();
+        let mut material = before.clone();
+        material_ui(ui, &mut material);
+        if material != before {
+            self.ui_edit(Edit::ModifyMaterial {
+                index,
+                before,
+                after: material,
+            });
+        }
+    }
+
+    /// Makes a 4d mesh from an obj file, with the 3d mesh scaled to be 1 unit across
+    fn mesh_import_ui(&mut self, ui: &mut egui::Ui) {
+        let import = &mut self.mesh_import;
+        egui::Grid::new("Import Mesh")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("OBJ File");
+                ui.text_edit_singleline(&mut import.path);
+                ui.end_row();
+
+                ui.label("Shape");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut import.spin, false, "Extrude");
+                    ui.selectable_value(&mut import.spin, true, "Spin");
+                });
+                ui.end_row();
+
+                if import.spin {
+                    ui.label("Segments");
+                    ui.add(egui::DragValue::new(&mut import.segments).range(3..=256));
+                } else {
+                    ui.label("Half Depth");
+                    ui.add(
+                        egui::DragValue::new(&mut import.half_depth)
+                            .speed(0.01)
+                            .range(0.01..=f32::INFINITY),
+                    );
+                }
+                ui.end_row();
+            });
+
+        if ui.button("Import").clicked() {
+            let mesh = TriangleMesh::load_obj(&import.path).and_then(|mesh| {
+                let mesh = mesh.fitted(1.0);
+                if import.spin {
+                    // moved off of the plane it is spun around, so it makes a ring
+                    mesh.translated(cgmath::vec3(0.75, 0.0, 0.0))
+                        .spin(import.segments)
+                } else {
+                    mesh.extrude(import.half_depth)
+                }
+            });
+            match mesh {
+                Ok(mesh) => {
+                    import.error = None;
+                    let transform = Transform::translation(self.spawn_position());
+                    self.add_tetrahedron_mesh(mesh, transform);
+                }
+                Err(error) => import.error = Some(format!("{}: {error}", import.path)),
+            }
+        }
+        if let Some(error) = &self.mesh_import.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    /// Adds a regular polychoron or a convex polytope from a 4OFF file, scaled to be 1 unit across
+    fn polytope_import_ui(&mut self, ui: &mut egui::Ui) {
+        let import = &mut self.polytope_import;
+        egui::Grid::new("Add Polytope")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Source");
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut import.from_file, false, "Regular");
+                    ui.selectable_value(&mut import.from_file, true, "4OFF File");
+                });
+                ui.end_row();
+
+                if import.from_file {
+                    ui.label("4OFF File");
+                    ui.text_edit_singleline(&mut import.path);
+                } else {
+                    ui.label("Polychoron");
+                    egui::ComboBox::from_id_salt("Regular Polychoron")
+                        .selected_text(polychoron_name(import.polychoron))
+                        .show_ui(ui, |ui| {
+                            for polychoron in RegularPolychoron::ALL {
+                                ui.selectable_value(
+                                    &mut import.polychoron,
+                                    polychoron,
+                                    polychoron_name(polychoron),
+                                );
+                            }
+                        });
+                }
+                ui.end_row();
+            });
+
+        if ui.button("Add").clicked() {
+            let material_count = self.materials.len() as u32;
+            let polytope = if import.from_file {
+                Polytope::load_off(&import.path)
+                    .map_err(|error| format!("{}: {error}", import.path))
+            } else {
+                Ok(import.polychoron.polytope())
+            };
+            let tetrahedron_mesh = polytope.and_then(|polytope| {
+                let radius = polytope
+                    .vertices()
+                    .iter()
+                    .map(|vertex| vertex.magnitude())
+                    .fold(0.0, f32::max);
+                if radius <= 0.0 {
+                    return Err(
+                        "the polytope has no size, all of its vertices are at its center".into(),
+                    );
+                }
+                if material_count == 0 {
+                    return Err("the scene has no materials for the polytope".into());
+                }
+                // the cells are spread over the materials like in scenes/polychora.ron, so they can be told apart
+                polytope
+                    .scaled(0.5 / radius)
+                    .tetrahedron_mesh(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |cell| {
+                        cell as u32 % material_count
+                    })
+                    .map_err(|error| error.to_string())
+            });
+            match tetrahedron_mesh {
+                Ok(mut tetrahedron_mesh) => {
+                    import.error = None;
+                    tetrahedron_mesh.position = self.spawn_position();
+                    self.edit(Edit::AddObject {
+                        index: self.objects.len(),
+                        object: Object::TetrahedronMesh(tetrahedron_mesh),
+                    });
+                }
+                Err(error) => import.error = Some(error),
+            }
+        }
+        if let Some(error) = &self.polytope_import.error {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn lighting_ui(&mut self, ui: &mut egui::Ui) {
+        self.update_camera(|camera| {
+            egui::Grid::new("Lighting").num_columns(2).show(ui, |ui| {
+                ui.label("Sun Direction");
+                vector4_ui(ui, &mut camera.sun_direction);
+                ui.end_row();
+
+                for (name, color) in [
+                    ("Sun Color", &mut camera.sun_color),
+                    ("Sun Light Color", &mut camera.sun_light_color),
+                    ("Ambient Light Color", &mut camera.ambient_light_color),
+                    ("Up Sky Color", &mut camera.up_sky_color),
+                    ("Down Sky Color", &mut camera.down_sky_color),
+                ] {
+                    ui.label(name);
+                    color_ui(ui, color);
+                    ui.end_row();
+                }
+            });
+        });
+    }
+
+    fn rendering_ui(&mut self, ui: &mut egui::Ui) {
+        let mut path_tracing = self.path_tracing;
+        if ui.checkbox(&mut path_tracing, "Path Tracing").changed() {
+            self.set_path_tracing(path_tracing);
+        }
+        if self.path_tracing {
+            ui.label(format!("Samples: {}", self.accumulated_frames));
+        }
+
+        let mut max_bounces = self.max_bounces;
+        ui.horizontal(|ui| {
+            ui.label("Max Bounces");
+            ui.add(egui::DragValue::new(&mut max_bounces).range(0..=MAX_TRACED_BOUNCES));
+        });
+        if max_bounces != self.max_bounces {
+            self.set_max_bounces(max_bounces);
+        }
+    }
+
+    fn camera_ui(&self, ui: &mut egui::Ui) {
+        let camera = &self.camera;
+        egui::Grid::new("Camera").num_columns(2).show(ui, |ui| {
+            let position = camera.position;
+            ui.label("Position");
+            ui.label(format!(
+                "{:.2} {:.2} {:.2} {:.2}",
+                position.x, position.y, position.z, position.w
+            ));
+            ui.end_row();
+
+            ui.label("Rotation");
+            ui.label(rotation_readout(camera.get_rotation()));
+            ui.end_row();
+        });
+    }
+
+    /// Applies an edit from a widget straight away, but only records it once the widget is let go of
+    /// and `commit_ui_edit` is called
+    pub fn ui_edit(&mut self, edit: Edit) {
+        let merged = match (&mut self.pending_ui_edit, &edit) {
+            (
+                Some(Edit::ModifyObject { index, after, .. }),
+                Edit::ModifyObject {
+                    index: new_index,
+                    after: new_after,
+                    ..
+                },
+            ) if index == new_index => {
+                *after = new_after.clone();
+                true
+            }
+            (
+                Some(Edit::ModifyMaterial { index, after, .. }),
+                Edit::ModifyMaterial {
+                    index: new_index,
+                    after: new_after,
+                    ..
+                },
+            ) if index == new_index => {
+                *after = new_after.clone();
+                true
+            }
+            _ => false,
+        };
+        if !merged {
+            self.commit_ui_edit();
+            self.pending_ui_edit = Some(edit.clone());
+        }
+
+        edit.apply(&mut self.objects, &mut self.materials);
+        self.gpu_objects.invalidate();
+        self.scene_changed = true;
+        if let Edit::ModifyObject { .. } = edit {
+            self.bvh.refit(&self.objects);
+        }
+    }
+
+    /// Records the edit that `ui_edit` is making as a single edit in the history
+    pub fn commit_ui_edit(&mut self) {
+        if let Some(edit) = self.pending_ui_edit.take() {
+            self.history.record(edit);
+            // the bvh was only refit while editing
+            self.bvh = Bvh::build(&self.objects);
+            self.gpu_objects.invalidate();
+        }
+    }
+}
+
+fn csg_operation_name(operation: CsgOperation) -> &'static str {
+    match operation {
+        CsgOperation::Union => "Union",
+        CsgOperation::Intersection => "Intersection",
+        CsgOperation::Difference => "Difference",
+    }
+}
+
+fn polychoron_name(polychoron: RegularPolychoron) -> &'static str {
+    match polychoron {
+        RegularPolychoron::FiveCell => "5-Cell",
+        RegularPolychoron::Tesseract => "Tesseract",
+        RegularPolychoron::SixteenCell => "16-Cell",
+        RegularPolychoron::TwentyFourCell => "24-Cell",
+        RegularPolychoron::OneHundredTwentyCell => "120-Cell",
+        RegularPolychoron::SixHundredCell => "600-Cell",
+    }
+}
+
+fn hyper_cylinder_shape_ui(ui: &mut egui::Ui, shape: &mut HyperCylinderShape) {
+    ui.label("Shape");
+    egui::ComboBox::from_id_salt("Hyper Cylinder Shape")
+        .selected_text(match shape {
+            HyperCylinderShape::Spherinder { .. } => "Spherinder",
+            HyperCylinderShape::Cubinder { .. } => "Cubinder",
+            HyperCylinderShape::Duocylinder { .. } => "Duocylinder",
+            HyperCylinderShape::SphereThroughW { .. } => "Sphere Through W",
+            HyperCylinderShape::CylinderThroughW { .. } => "Cylinder Through W",
+        })
+        .show_ui(ui, |ui| {
+            let shapes = [
+                (
+                    "Spherinder",
+                    HyperCylinderShape::Spherinder {
+                        radius: 0.5,
+                        half_height: 0.5,
+                    },
+                ),
+                (
+                    "Cubinder",
+                    HyperCylinderShape::Cubinder {
+                        radius: 0.5,
+                        half_extents: cgmath::vec2(0.5, 0.5),
+                    },
+                ),
+                (
+                    "Duocylinder",
+                    HyperCylinderShape::Duocylinder {
+                        radii: cgmath::vec2(0.5, 0.5),
+                    },
+                ),
+                (
+                    "Sphere Through W",
+                    HyperCylinderShape::SphereThroughW { radius: 0.5 },
+                ),
+                (
+                    "Cylinder Through W",
+                    HyperCylinderShape::CylinderThroughW {
+                        radius: 0.5,
+                        half_height: 0.5,
+                    },
+                ),
+            ];
+            for (name, new_shape) in shapes {
+                let selected = std::mem::discriminant(shape) == std::mem::discriminant(&new_shape);
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    *shape = new_shape;
+                }
+            }
+        });
+    ui.end_row();
+
+    let mut size_ui = |name: &str, value: &mut f32| {
+        ui.label(name);
+        ui.add(
+            egui::DragValue::new(value)
+                .speed(0.01)
+                .range(0.01..=f32::INFINITY),
+        );
+        ui.end_row();
+    };
+    match shape {
+        HyperCylinderShape::Spherinder {
+            radius,
+            half_height,
+        }
+        | HyperCylinderShape::CylinderThroughW {
+            radius,
+            half_height,
+        } => {
+            size_ui("Radius", radius);
+            size_ui("Half Height", half_height);
+        }
+        HyperCylinderShape::Cubinder {
+            radius,
+            half_extents,
+        } => {
+            size_ui("Radius", radius);
+            size_ui("Half Extent Z", &mut half_extents.x);
+            size_ui("Half Extent W", &mut half_extents.y);
+        }
+        HyperCylinderShape::Duocylinder { radii } => {
+            size_ui("XY Radius", &mut radii.x);
+            size_ui("ZW Radius", &mut radii.y);
+        }
+        HyperCylinderShape::SphereThroughW { radius } => size_ui("Radius", radius),
+    }
+}
+
+fn hyper_torus_shape_ui(ui: &mut egui::Ui, shape: &mut HyperTorusShape) {
+    ui.label("Shape");
+    egui::ComboBox::from_id_salt("Hyper Torus Shape")
+        .selected_text(match shape {
+            HyperTorusShape::Tiger { .. } => "Tiger",
+            HyperTorusShape::Ditorus { .. } => "Ditorus",
+            HyperTorusShape::Spheritorus { .. } => "Spheritorus",
+            HyperTorusShape::Torisphere { .. } => "Torisphere",
+        })
+        .show_ui(ui, |ui| {
+            let shapes = [
+                (
+                    "Tiger",
+                    HyperTorusShape::Tiger {
+                        major_radii: cgmath::vec2(0.6, 0.6),
+                        minor_radius: 0.25,
+                    },
+                ),
+                (
+                    "Ditorus",
+                    HyperTorusShape::Ditorus {
+                        major_radius: 0.6,
+                        middle_radius: 0.25,
+                        minor_radius: 0.1,
+                    },
+                ),
+                (
+                    "Spheritorus",
+                    HyperTorusShape::Spheritorus {
+                        major_radius: 0.6,
+                        minor_radius: 0.25,
+                    },
+                ),
+                (
+                    "Torisphere",
+                    HyperTorusShape::Torisphere {
+                        major_radius: 0.6,
+                        minor_radius: 0.25,
+                    },
+                ),
+            ];
+            for (name, new_shape) in shapes {
+                let selected = std::mem::discriminant(shape) == std::mem::discriminant(&new_shape);
+                if ui.selectable_label(selected, name).clicked() && !selected {
+                    *shape = new_shape;
+                }
+            }
+        });
+    ui.end_row();
+
+    let mut radius_ui = |name: &str, value: &mut f32| {
+        ui.label(name);
+        ui.add(
+            egui::DragValue::new(value)
+                .speed(0.01)
+                .range(0.01..=f32::INFINITY),
+        );
+        ui.end_row();
+    };
+    match shape {
+        HyperTorusShape::Tiger {
+            major_radii,
+            minor_radius,
+        } => {
+            radius_ui("XY Major Radius", &mut major_radii.x);
+            radius_ui("ZW Major Radius", &mut major_radii.y);
+            radius_ui("Minor Radius", minor_radius);
+        }
+        HyperTorusShape::Ditorus {
+            major_radius,
+            middle_radius,
+            minor_radius,
+        } => {
+            radius_ui("Major Radius", major_radius);
+            radius_ui("Middle Radius", middle_radius);
+            radius_ui("Minor Radius", minor_radius);
+        }
+        HyperTorusShape::Spheritorus {
+            major_radius,
+            minor_radius,
+        }
+        | HyperTorusShape::Torisphere {
+            major_radius,
+            minor_radius,
+        } => {
+            radius_ui("Major Radius", major_radius);
+            radius_ui("Minor Radius", minor_radius);
+        }
+    }
+}
+
+/// The kind of the rotation, and the angle in each of the planes it is split into
+fn rotation_readout(rotation: Rotor) -> String {
+    let kind = rotation.kind();
+    let [first, second] = rotation.decompose();
+    let parts = if kind == RotationKind::Simple {
+        &[first][..]
+    } else {
+        &[first, second][..]
+    };
+    parts.iter().fold(format!("{kind:?}"), |readout, part| {
+        format!(
+            "{readout}\n{:.1}° in {}",
+            part.angle.to_degrees(),
+            plane_name(part.plane)
+        )
+    })
+}
+
+/// The axis plane that a plane is, or what it is made from when it isnt one of them
+fn plane_name(plane: Bivector) -> String {
+    let components = [
+        ("xy", plane.xy),
+        ("xz", plane.xz),
+        ("xw", plane.xw),
+        ("yz", plane.yz),
+        ("yw", plane.yw),
+        ("zw", plane.zw),
+    ]
+    .into_iter()
+    .filter(|(_, size)| size.abs() >= 0.005)
+    .collect::<Vec<_>>();
+    match components[..] {
+        [] => "no plane".to_string(),
+        [(name, size)] if size > 0.0 => name.to_string(),
+        [(name, _)] => name.chars().rev().collect(),
+        _ => components
+            .iter()
+            .map(|(name, size)| format!("{size:.2} {name}"))
+            .collect::<Vec<_>>()
+            .join(" + ")
+            .replace("+ -", "- "),
+    }
+}
+
+fn vector4_ui(ui: &mut egui::Ui, vector: &mut cgmath::Vector4<f32>) -> egui::Response {
+    ui.horizontal(|ui| {
+        ["x", "y", "z", "w"]
+            .into_iter()
+            .enumerate()
+            .map(|(axis, name)| {
+                ui.add(
+                    egui::DragValue::new(&mut vector[axis])
+                        .speed(0.01)
+                        .prefix(format!("{name}: ")),
+                )
+            })
+            .reduce(|a, b| a | b)
+            .expect("there are always 4 axes")
+    })
+    .inner
+}
+
+fn color_ui(ui: &mut egui::Ui, color: &mut cgmath::Vector3<f32>) -> egui::Response {
+    let mut rgb = [color.x, color.y, color.z];
+    let response = egui::color_picker::color_edit_button_rgb(ui, &mut rgb);
+    *color = rgb.into();
+    response
+}
+
+fn material_ui(ui: &mut egui::Ui, material: &mut Material) {
+    egui::Grid::new("Material").num_columns(2).show(ui, |ui| {
+        ui.label("Color");
+        color_ui(ui, &mut material.color);
+        ui.end_row();
+
+        ui.label("Kind");
+        egui::ComboBox::from_id_salt("Material Kind")
+            .selected_text(match material.kind {
+                MaterialKind::Diffuse => "Diffuse",
+                MaterialKind::Metal { .. } => "Metal",
+                MaterialKind::Dielectric { .. } => "Dielectric",
+            })
+            .show_ui(ui, |ui| {
+                let kinds = [
+                    ("Diffuse", MaterialKind::Diffuse),
+                    ("Metal", MaterialKind::Metal { roughness: 0.0 }),
+                    (
+                        "Dielectric",
+                        MaterialKind::Dielectric {
+                            index_of_refraction: 1.5,
+                        },
+                    ),
+                ];
+                for (name, kind) in kinds {
+                    let selected =
+                        std::mem::discriminant(&material.kind) == std::mem::discriminant(&kind);
+                    if ui.selectable_label(selected, name).clicked() && !selected {
+                        material.kind = kind;
+                    }
+                }
+            });
+        ui.end_row();
+
+        match &mut material.kind {
+            MaterialKind::Diffuse => {}
+            MaterialKind::Metal { roughness } => {
+                ui.label("Roughness");
+                ui.add(egui::Slider::new(roughness, 0.0..=1.0));
+                ui.end_row();
+            }
+            MaterialKind::Dielectric {
+                index_of_refraction,
+            } => {
+                ui.label("Index of Refraction");
+                ui.add(
+                    egui::DragValue::new(index_of_refraction)
+                        .speed(0.01)
+                        .range(1.0..=3.0),
+                );
+                ui.end_row();
+            }
+        }
+
+        ui.label("Emission Strength");
+        ui.add(
+            egui::DragValue::new(&mut material.emission_strength)
+                .speed(0.05)
+                .range(0.0..=f32::INFINITY),
+        );
+        ui.end_row();
+    });
+}
@@ -0,0 +1,422 @@
+//! 3d triangle meshes, like the ones made by modelling tools, and the ways of making 4d tetrahedron meshes out of them
+
+use crate::mesh::{Mesh, MeshError, Tetrahedron};
+use cgmath::InnerSpace;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum TriangleMeshError {
+    Io(std::io::Error),
+    /// A problem with the contents of an obj file, `line` starts from 1
+    Obj {
+        line: usize,
+        message: String,
+    },
+    NoTriangles,
+    VertexOutOfRange {
+        triangle: usize,
+        vertex: u32,
+        vertex_count: usize,
+    },
+    /// There is no point inside the mesh that can see all of it, so its ends cant be filled in when extruding it
+    NotStarShaped,
+    /// Meshes can only be spun when they are all on the positive x side of the plane they are spun around
+    BehindSpinPlane {
+        vertex: usize,
+    },
+    Mesh(MeshError),
+}
+
+impl std::fmt::Display for TriangleMeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TriangleMeshError::Io(error) => write!(f, "{error}"),
+            TriangleMeshError::Obj { line, message } => write!(f, "{line}: {message}"),
+            TriangleMeshError::NoTriangles => write!(f, "a mesh needs at least one triangle"),
+            TriangleMeshError::VertexOutOfRange {
+                triangle,
+                vertex,
+                vertex_count,
+            } => write!(
+                f,
+                "triangle {triangle} uses vertex {vertex}, but there are only {vertex_count} vertices"
+            ),
+            TriangleMeshError::NotStarShaped => write!(
+                f,
+                "the ends of the mesh cant be filled in, since there is no point inside it that can see all of it"
+            ),
+            TriangleMeshError::BehindSpinPlane { vertex } => write!(
+                f,
+                "vertex {vertex} has a negative x, so it would go through the plane the mesh is spun around"
+            ),
+            TriangleMeshError::Mesh(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for TriangleMeshError {}
+
+impl From<std::io::Error> for TriangleMeshError {
+    fn from(error: std::io::Error) -> Self {
+        TriangleMeshError::Io(error)
+    }
+}
+
+impl From<MeshError> for TriangleMeshError {
+    fn from(error: MeshError) -> Self {
+        TriangleMeshError::Mesh(error)
+    }
+}
+
+/// The surface of a 3d solid made from triangles that share vertices
+#[derive(Debug, Clone, PartialEq)]
+pub struct TriangleMesh {
+    vertices: Vec<cgmath::Vector3<f32>>,
+    triangles: Vec<[u32; 3]>,
+}
+
+impl TriangleMesh {
+    /// The triangles can go either way around, as long as they all go the same way
+    pub fn new(
+        vertices: Vec<cgmath::Vector3<f32>>,
+        mut triangles: Vec<[u32; 3]>,
+    ) -> Result<Self, TriangleMeshError> {
+        if triangles.is_empty() {
+            return Err(TriangleMeshError::NoTriangles);
+        }
+        for (triangle, indices) in triangles.iter().enumerate() {
+            if let Some(&vertex) = indices
+                .iter()
+                .find(|&&vertex| vertex as usize >= vertices.len())
+            {
+                return Err(TriangleMeshError::VertexOutOfRange {
+                    triangle,
+                    vertex,
+                    vertex_count: vertices.len(),
+                });
+            }
+        }
+
+        // the triangles should go anticlockwise when looking at the outside of the mesh, which gives it a positive volume
+        let volume = triangles
+            .iter()
+            .map(|triangle| {
+                let [a, b, c] = triangle.map(|vertex| vertices[vertex as usize]);
+                a.dot(b.cross(c))
+            })
+            .sum::<f32>();
+        if volume < 0.0 {
+            for triangle in &mut triangles {
+                triangle.swap(1, 2);
+            }
+        }
+        Ok(Self {
+            vertices,
+            triangles,
+        })
+    }
+
+    /// Reads the vertices and faces of an obj file, faces with more than 3 vertices are split into triangles
+    /// and everything else (normals, texture coordinates, groups and materials) is ignored
+    pub fn from_obj(source: &str) -> Result<Self, TriangleMeshError> {
+        let mut vertices = vec![];
+        let mut triangles = vec![];
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let error = |message: String| TriangleMeshError::Obj {
+                line: line_number,
+                message,
+            };
+            let mut words = line
+                .split('#')
+                .next()
+                .unwrap_or_default()
+                .split_whitespace();
+            match words.next() {
+                Some("v") => {
+                    let coordinates = words
+                        .take(3)
+                        .map(|word| {
+                            word.parse::<f32>()
+                                .map_err(|_| error(format!("{word} isnt a valid number")))
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let &[x, y, z] = coordinates.as_slice() else {
+                        return Err(error("a vertex needs 3 coordinates".into()));
+                    };
+                    vertices.push(cgmath::vec3(x, y, z));
+                }
+                Some("f") => {
+                    // the vertex index is the part before the first slash, negative ones count back from the last vertex
+                    let face = words
+                        .map(|word| {
+                            let index = word.split('/').next().unwrap_or_default();
+                            match index.parse::<i64>() {
+                                Ok(index) if index > 0 && index as usize <= vertices.len() => {
+                                    Ok(index as u32 - 1)
+                                }
+                                Ok(index)
+                                    if index < 0
+                                        && index.unsigned_abs() as usize <= vertices.len() =>
+                                {
+                                    Ok((vertices.len() as i64 + index) as u32)
+                                }
+                                Ok(_) => Err(error(format!("there is no vertex {index}"))),
+                                Err(_) => Err(error(format!("{word} isnt a valid vertex index"))),
+                            }
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if face.len() < 3 {
+                        return Err(error("a face needs at least 3 vertices".into()));
+                    }
+                    for i in 1..face.len() - 1 {
+                        triangles.push([face[0], face[i], face[i + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Self::new(vertices, triangles)
+    }
+
+    pub fn load_obj(path: impl AsRef<Path>) -> Result<Self, TriangleMeshError> {
+        Self::from_obj(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn vertices(&self) -> &[cgmath::Vector3<f32>] {
+        &self.vertices
+    }
+
+    /// The indices of the vertices of each triangle, going anticlockwise when looking at the outside of the mesh
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+
+    /// Moves and scales the mesh so its bounding box is centered on the origin, and its longest side is `size` long
+    pub fn fitted(mut self, size: f32) -> Self {
+        let (min, max) = self.vertices.iter().fold(
+            (self.vertices[0], self.vertices[0]),
+            |(min, max), vertex| {
+                (
+                    cgmath::vec3(
+                        min.x.min(vertex.x),
+                        min.y.min(vertex.y),
+                        min.z.min(vertex.z),
+                    ),
+                    cgmath::vec3(
+                        max.x.max(vertex.x),
+                        max.y.max(vertex.y),
+                        max.z.max(vertex.z),
+                    ),
+                )
+            },
+        );
+        let center = (min + max) * 0.5;
+        let extent = max - min;
+        let scale = size / extent.x.max(extent.y).max(extent.z).max(f32::EPSILON);
+        for vertex in &mut self.vertices {
+            *vertex = (*vertex - center) * scale;
+        }
+        self
+    }
+
+    /// The same mesh moved by `offset`
+    pub fn translated(mut self, offset: cgmath::Vector3<f32>) -> Self {
+        for vertex in &mut self.vertices {
+            *vertex += offset;
+        }
+        self
+    }
+
+    /// The 4d prism made by stretching the solid from `-half_depth` to `half_depth` along w.
+    ///
+    /// Its ends are filled in with tetrahedra going out from a point that can see the whole mesh,
+    /// so this only works for meshes where there is such a point (star shaped meshes, which includes all convex ones)
+    pub fn extrude(&self, half_depth: f32) -> Result<Mesh, TriangleMeshError> {
+        let center = self
+            .visible_point()
+            .ok_or(TriangleMeshError::NotStarShaped)?;
+        let vertex_count = self.vertices.len() as u32;
+        let layer = |w: f32| {
+            self.vertices
+                .iter()
+                .map(move |vertex| vertex.extend(w))
+                .chain([center.extend(w)])
+        };
+        let vertices = layer(-half_depth)
+            .chain(layer(half_depth))
+            .collect::<Vec<_>>();
+        // the vertices of the second layer, and the centers of the ends, come after the vertices of the first layer
+        let top = |vertex: u32| vertex + vertex_count + 1;
+        let bottom_center = vertex_count;
+
+        let mut cells = vec![];
+        for &triangle in &self.triangles {
+            let normal = self.triangle_normal(triangle).extend(0.0);
+            cells.extend(prism_cells(triangle, |vertex| vertex, top).map(|cell| (cell, normal)));
+            cells.push((
+                [bottom_center, triangle[0], triangle[1], triangle[2]],
+                cgmath::vec4(0.0, 0.0, 0.0, -1.0),
+            ));
+            cells.push((
+                [
+                    top(bottom_center),
+                    top(triangle[0]),
+                    top(triangle[1]),
+                    top(triangle[2]),
+                ],
+                cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+            ));
+        }
+        Ok(Mesh::new(
+            vertices.clone(),
+            oriented_cells(&vertices, cells),
+        )?)
+    }
+
+    /// The 4d solid of revolution made by spinning the solid all the way around the yz plane, through the xw plane.
+    /// The solid needs to be on the positive x side of the plane, and the circles that its vertices go around are split
+    /// into `segments` straight lines (at least 3)
+    pub fn spin(&self, segments: u32) -> Result<Mesh, TriangleMeshError> {
+        let segments = segments.max(3);
+        if let Some(vertex) = self.vertices.iter().position(|vertex| vertex.x < 0.0) {
+            return Err(TriangleMeshError::BehindSpinPlane { vertex });
+        }
+
+        // vertices on the plane stay where they are, so they only need to be added once
+        let mut vertices = vec![];
+        let mut first_copies = vec![];
+        for vertex in &self.vertices {
+            first_copies.push(vertices.len() as u32);
+            let copies = if vertex.x == 0.0 { 1 } else { segments };
+            vertices.extend((0..copies).map(|segment| {
+                let (sin, cos) =
+                    (segment as f32 / segments as f32 * std::f32::consts::TAU).sin_cos();
+                cgmath::vec4(vertex.x * cos, vertex.y, vertex.z, vertex.x * sin)
+            }));
+        }
+        let copy = |vertex: u32, segment: u32| {
+            if self.vertices[vertex as usize].x == 0.0 {
+                first_copies[vertex as usize]
+            } else {
+                first_copies[vertex as usize] + segment % segments
+            }
+        };
+
+        let mut cells = vec![];
+        for segment in 0..segments {
+            // the normal of the triangle, spun around to halfway through the segment
+            let (sin, cos) =
+                ((segment as f32 + 0.5) / segments as f32 * std::f32::consts::TAU).sin_cos();
+            for &triangle in &self.triangles {
+                let normal = self.triangle_normal(triangle);
+                let normal = cgmath::vec4(normal.x * cos, normal.y, normal.z, normal.x * sin);
+                cells.extend(
+                    prism_cells(
+                        triangle,
+                        |vertex| copy(vertex, segment),
+                        |vertex| copy(vertex, segment + 1),
+                    )
+                    .map(|cell| (cell, normal)),
+                );
+            }
+        }
+        Ok(Mesh::new(
+            vertices.clone(),
+            oriented_cells(&vertices, cells),
+        )?)
+    }
+
+    fn triangle_normal(&self, triangle: [u32; 3]) -> cgmath::Vector3<f32> {
+        let [a, b, c] = triangle.map(|vertex| self.vertices[vertex as usize]);
+        (b - a).cross(c - a)
+    }
+
+    /// A point that is behind the planes of all of the triangles, found by starting in the middle of the vertices
+    /// and moving onto the inside of whichever plane it is furthest in front of until it is behind all of them
+    fn visible_point(&self) -> Option<cgmath::Vector3<f32>> {
+        let planes = self
+            .triangles
+            .iter()
+            .filter_map(|&triangle| {
+                let normal = self.triangle_normal(triangle);
+                let length = normal.magnitude();
+                (length > 0.0).then(|| {
+                    let normal = normal / length;
+                    (normal, normal.dot(self.vertices[triangle[0] as usize]))
+                })
+            })
+            .collect::<Vec<_>>();
+        let size = self
+            .vertices
+            .iter()
+            .map(|vertex| (vertex - self.vertices[0]).magnitude())
+            .fold(0.0, f32::max);
+        let margin = size * 0.0001;
+
+        let mut point =
+            self.vertices.iter().sum::<cgmath::Vector3<f32>>() / self.vertices.len() as f32;
+        for _ in 0..planes.len() * 16 {
+            let (normal, distance) = planes
+                .iter()
+                .map(|&(normal, offset)| (normal, normal.dot(point) - offset + margin))
+                .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+            if distance <= 0.0 {
+                return Some(point);
+            }
+            point -= normal * distance;
+        }
+        None
+    }
+}
+
+/// Splits the prism between a triangle on the `bottom` and the same triangle on the `top` into 3 tetrahedra.
+/// The vertices are sorted first, so prisms on either side of a square face split it along the same diagonal,
+/// and tetrahedra that use the same vertex twice (where the top and bottom meet) are left out
+fn prism_cells(
+    mut triangle: [u32; 3],
+    bottom: impl Fn(u32) -> u32,
+    top: impl Fn(u32) -> u32,
+) -> impl Iterator<Item = [u32; 4]> {
+    triangle.sort();
+    let [a, b, c] = triangle;
+    [
+        [bottom(a), bottom(b), bottom(c), top(c)],
+        [bottom(a), bottom(b), top(b), top(c)],
+        [bottom(a), top(a), top(b), top(c)],
+    ]
+    .into_iter()
+    .filter(|cell| (1..4).all(|i| !cell[..i].contains(&cell[i])))
+}
+
+/// How flat a tetrahedron can be before it is left out of a mesh, as a fraction of how big it would be with the same edges at right angles
+const FLAT_TOLERANCE: f32 = 0.00001;
+
+/// Turns each cell to face the same way as the normal it was made with, leaving out the ones that are flat
+fn oriented_cells(
+    vertices: &[cgmath::Vector4<f32>],
+    cells: Vec<([u32; 4], cgmath::Vector4<f32>)>,
+) -> Vec<[u32; 4]> {
+    cells
+        .into_iter()
+        .filter_map(|(mut cell, outwards)| {
+            let tetrahedron = Tetrahedron {
+                vertices: cell.map(|vertex| vertices[vertex as usize]),
+            };
+            // the normal is as long as the edges multiplied together when they are all perpendicular, and zero when they are flat
+            let normal = tetrahedron.unnormalized_normal();
+            let edge_lengths = tetrahedron
+                .edges()
+                .iter()
+                .map(|edge| edge.magnitude())
+                .product::<f32>();
+            if normal.magnitude() <= edge_lengths * FLAT_TOLERANCE {
+                return None;
+            }
+            if normal.dot(outwards) < 0.0 {
+                cell.swap(1, 2);
+            }
+            Some(cell)
+        })
+        .collect()
+}
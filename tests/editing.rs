@@ -0,0 +1,175 @@
+use ray_tracer::{
+    bvh::{Bvh, BvhNodeKind},
+    history::Edit,
+    material::Material,
+    math::Rotor,
+    objects::{
+        Csg, CsgOperation, HyperCylinder, HyperCylinderShape, HyperPlane, HyperSphere, Object,
+    },
+    scene::Scene,
+    state::{State, check_adapter_limits, required_limits},
+};
+
+/// A `State` on a real gpu, software adapters like llvmpipe take far too long to build the ray tracing pipeline
+fn gpu_state(scene: Scene) -> Option<State> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    let Some(adapter) =
+        pollster::block_on(instance.request_adapter(&Default::default())).filter(|adapter| {
+            adapter.get_info().device_type != wgpu::DeviceType::Cpu
+                && check_adapter_limits(adapter).is_ok()
+        })
+    else {
+        eprintln!("no gpu adapter with the limits the ray tracer needs is available, skipping");
+        return None;
+    };
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_limits: required_limits(),
+            ..Default::default()
+        },
+        None,
+    ))
+    .expect("device should have been requested successfully");
+    Some(State::new(&device, &queue, scene))
+}
+
+/// The objects that have a leaf in the bvh, in order
+fn leaves(bvh: &Bvh) -> Vec<usize> {
+    let mut leaves = bvh
+        .nodes
+        .iter()
+        .filter_map(|node| match node.kind {
+            BvhNodeKind::Leaf { object } => Some(object),
+            BvhNodeKind::Interior { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    leaves.sort();
+    leaves
+}
+
+fn hyper_cylinder(shape: HyperCylinderShape) -> Object {
+    Object::HyperCylinder(HyperCylinder {
+        position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        rotation: Rotor::IDENTITY,
+        shape,
+        material: 0,
+    })
+}
+
+const SPHERINDER: HyperCylinderShape = HyperCylinderShape::Spherinder {
+    radius: 0.5,
+    half_height: 0.5,
+};
+
+/// Goes on forever along w, so it doesnt have bounds
+const SPHERE_THROUGH_W: HyperCylinderShape = HyperCylinderShape::SphereThroughW { radius: 0.5 };
+
+fn cylinder_scene() -> Scene {
+    Scene {
+        lighting: Default::default(),
+        materials: vec![Material::diffuse(cgmath::vec3(0.8, 0.8, 0.8))],
+        objects: vec![
+            hyper_cylinder(SPHERINDER),
+            Object::HyperSphere(HyperSphere {
+                position: cgmath::vec4(3.0, 0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: 0,
+            }),
+        ],
+    }
+}
+
+#[test]
+fn refit_when_bounds_change() {
+    let mut objects = cylinder_scene().objects;
+    let mut bvh = Bvh::build(&objects);
+    assert_eq!(leaves(&bvh), [0, 1]);
+
+    objects[0] = hyper_cylinder(SPHERE_THROUGH_W);
+    bvh.refit(&objects);
+    assert_eq!(leaves(&bvh), [1]);
+
+    objects[0] = hyper_cylinder(SPHERINDER);
+    bvh.refit(&objects);
+    assert_eq!(leaves(&bvh), [0, 1]);
+}
+
+#[test]
+fn switch_hyper_cylinder_shape() {
+    let Some(mut state) = gpu_state(cylinder_scene()) else {
+        return;
+    };
+    for (before, after) in [
+        (SPHERINDER, SPHERE_THROUGH_W),
+        (SPHERE_THROUGH_W, SPHERINDER),
+    ] {
+        state.ui_edit(Edit::ModifyObject {
+            index: 0,
+            before: hyper_cylinder(before),
+            after: hyper_cylinder(after),
+        });
+        assert_eq!(state.scene().objects[0], hyper_cylinder(after));
+    }
+    state.commit_ui_edit();
+}
+
+/// A sphere cut by a hyperplane, which only has bounds when the sphere is what is left
+fn cut_sphere(operation: CsgOperation) -> Object {
+    Object::Csg(Csg {
+        operation,
+        left: Box::new(Object::HyperSphere(HyperSphere {
+            position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            radius: 1.0,
+            material: 0,
+        })),
+        right: Box::new(Object::HyperPlane(HyperPlane {
+            position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+            normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            material: 0,
+        })),
+    })
+}
+
+fn csg_scene() -> Scene {
+    Scene {
+        objects: vec![cut_sphere(CsgOperation::Difference)],
+        ..cylinder_scene()
+    }
+}
+
+#[test]
+fn refit_when_csg_operation_changes() {
+    let mut objects = csg_scene().objects;
+    let mut bvh = Bvh::build(&objects);
+    assert_eq!(leaves(&bvh), [0]);
+
+    for (operation, expected) in [
+        (CsgOperation::Union, vec![]),
+        (CsgOperation::Intersection, vec![0]),
+        (CsgOperation::Union, vec![]),
+        (CsgOperation::Difference, vec![0]),
+    ] {
+        objects[0] = cut_sphere(operation);
+        bvh.refit(&objects);
+        assert_eq!(leaves(&bvh), expected);
+    }
+}
+
+#[test]
+fn change_csg_operation() {
+    let Some(mut state) = gpu_state(csg_scene()) else {
+        return;
+    };
+    for (before, after) in [
+        (CsgOperation::Difference, CsgOperation::Union),
+        (CsgOperation::Union, CsgOperation::Intersection),
+    ] {
+        state.ui_edit(Edit::ModifyObject {
+            index: 0,
+            before: cut_sphere(before),
+            after: cut_sphere(after),
+        });
+        assert_eq!(state.scene().objects[0], cut_sphere(after));
+    }
+    state.commit_ui_edit();
+}
@@ -0,0 +1,261 @@
+use cgmath::InnerSpace;
+use ray_tracer::{
+    camera::Camera,
+    cpu_renderer::CpuRenderer,
+    material::Material,
+    math::Rotor,
+    objects::{HyperPlane, HyperTorus, HyperTorusShape, Object},
+    offscreen::OffscreenRenderer,
+    ray::{Ray, RayIntersect},
+    scene::Scene,
+    state::{DEFAULT_MAX_BOUNCES, State, check_adapter_limits, required_limits},
+};
+
+/// How far a hit can be from where it should be, rays only step through the surface by a tiny amount
+const DISTANCE_TOLERANCE: f32 = 0.001;
+
+fn hyper_torus(shape: HyperTorusShape) -> HyperTorus {
+    HyperTorus {
+        position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        rotation: Rotor::IDENTITY,
+        shape,
+        material: 0,
+    }
+}
+
+/// Checks that a ray going along -x from `x = 5` hits the outside of the torus at `expected_x`, with a normal along x
+fn assert_hit_from_x(hyper_torus: &HyperTorus, origin: cgmath::Vector4<f32>, expected_x: f32) {
+    let ray = Ray {
+        origin,
+        direction: cgmath::vec4(-1.0, 0.0, 0.0, 0.0),
+    };
+    let hit = hyper_torus
+        .intersect(ray)
+        .expect("the ray should hit the torus");
+    assert!(
+        (hit.position.x - expected_x).abs() < DISTANCE_TOLERANCE,
+        "hit at {:?}, expected x to be {expected_x}",
+        hit.position,
+    );
+    assert!(hit.front_face);
+    assert!(
+        hit.normal.dot(cgmath::vec4(1.0, 0.0, 0.0, 0.0)) > 0.99,
+        "normal was {:?}",
+        hit.normal,
+    );
+}
+
+#[test]
+fn tiger_hit() {
+    let tiger = hyper_torus(HyperTorusShape::Tiger {
+        major_radii: cgmath::vec2(1.0, 0.5),
+        minor_radius: 0.25,
+    });
+    // the closest point of the zw circle is at z = 0.5, so the ray only has to get within the minor radius of the xy circle
+    assert_hit_from_x(&tiger, cgmath::vec4(5.0, 0.0, 0.5, 0.0), 1.25);
+}
+
+#[test]
+fn ditorus_hit() {
+    let ditorus = hyper_torus(HyperTorusShape::Ditorus {
+        major_radius: 1.0,
+        middle_radius: 0.4,
+        minor_radius: 0.1,
+    });
+    assert_hit_from_x(&ditorus, cgmath::vec4(5.0, 0.0, 0.0, 0.0), 1.5);
+}
+
+#[test]
+fn spheritorus_hit() {
+    let spheritorus = hyper_torus(HyperTorusShape::Spheritorus {
+        major_radius: 1.0,
+        minor_radius: 0.25,
+    });
+    assert_hit_from_x(&spheritorus, cgmath::vec4(5.0, 0.0, 0.0, 0.0), 1.25);
+}
+
+#[test]
+fn torisphere_hit() {
+    let torisphere = hyper_torus(HyperTorusShape::Torisphere {
+        major_radius: 1.0,
+        minor_radius: 0.25,
+    });
+    // every direction in xyz is the same for a torisphere
+    let direction = cgmath::vec4(-1.0, -1.0, -1.0, 0.0).normalize();
+    let hit = torisphere
+        .intersect(Ray {
+            origin: -direction * 5.0,
+            direction,
+        })
+        .expect("the ray should hit the torisphere");
+    assert!((hit.distance - 3.75).abs() < DISTANCE_TOLERANCE);
+}
+
+#[test]
+fn hit_from_inside() {
+    let spheritorus = hyper_torus(HyperTorusShape::Spheritorus {
+        major_radius: 1.0,
+        minor_radius: 0.25,
+    });
+    let hit = spheritorus
+        .intersect(Ray {
+            origin: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+            direction: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        })
+        .expect("a ray starting inside should hit the inside of the surface");
+    assert!(!hit.front_face);
+    assert!((hit.distance - 0.25).abs() < DISTANCE_TOLERANCE);
+    assert!(hit.normal.dot(cgmath::vec4(0.0, 0.0, 0.0, -1.0)) > 0.99);
+}
+
+#[test]
+fn miss_through_hole() {
+    let spheritorus = hyper_torus(HyperTorusShape::Spheritorus {
+        major_radius: 1.0,
+        minor_radius: 0.25,
+    });
+    // the ray goes straight through the middle of the circle
+    let hit = spheritorus.intersect(Ray {
+        origin: cgmath::vec4(0.0, 0.0, 5.0, 0.0),
+        direction: cgmath::vec4(0.0, 0.0, -1.0, 0.0),
+    });
+    assert!(hit.is_none(), "the ray shouldnt hit anything, hit {hit:?}");
+}
+
+#[test]
+fn rotated_hit() {
+    let mut tiger = hyper_torus(HyperTorusShape::Tiger {
+        major_radii: cgmath::vec2(1.0, 0.5),
+        minor_radius: 0.25,
+    });
+    tiger.position = cgmath::vec4(2.0, 1.0, 0.0, 0.0);
+    tiger.rotation = Rotor::rotation_xz(0.7) * Rotor::rotation_yw(0.3);
+
+    // aim at a point on the surface, the ray might hit another part of it first but it cant go past that point
+    let target = tiger.position + tiger.rotation.rotate(cgmath::vec4(1.25, 0.0, 0.5, 0.0));
+    let origin = cgmath::vec4(-3.0, 0.5, 0.2, 0.1);
+    let ray = Ray {
+        origin,
+        direction: (target - origin).normalize(),
+    };
+    let hit = tiger.intersect(ray).expect("the ray should hit the tiger");
+    assert!(hit.distance <= (target - origin).magnitude() + DISTANCE_TOLERANCE);
+    assert!(tiger.sdf().distance(hit.position).abs() < DISTANCE_TOLERANCE);
+}
+
+fn tori_scene() -> Scene {
+    let hyper_torus = |position, shape, material| {
+        Object::HyperTorus(HyperTorus {
+            position,
+            rotation: Rotor::rotation_xz(0.4) * Rotor::rotation_yz(0.3),
+            shape,
+            material,
+        })
+    };
+    Scene {
+        lighting: Default::default(),
+        materials: vec![
+            Material::diffuse(cgmath::vec3(0.8, 0.8, 0.8)),
+            Material::diffuse(cgmath::vec3(0.9, 0.3, 0.2)),
+            Material::diffuse(cgmath::vec3(0.3, 0.4, 0.9)),
+        ],
+        objects: vec![
+            Object::HyperPlane(HyperPlane {
+                position: cgmath::vec4(0.0, -1.0, 0.0, 0.0),
+                normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                material: 0,
+            }),
+            hyper_torus(
+                cgmath::vec4(4.0, 0.0, -1.5, 0.0),
+                HyperTorusShape::Tiger {
+                    major_radii: cgmath::vec2(0.6, 0.6),
+                    minor_radius: 0.3,
+                },
+                1,
+            ),
+            hyper_torus(
+                cgmath::vec4(4.0, 0.0, 0.0, 0.0),
+                HyperTorusShape::Ditorus {
+                    major_radius: 0.6,
+                    middle_radius: 0.3,
+                    minor_radius: 0.15,
+                },
+                2,
+            ),
+            hyper_torus(
+                cgmath::vec4(4.0, 0.0, 1.5, 0.0),
+                HyperTorusShape::Spheritorus {
+                    major_radius: 0.5,
+                    minor_radius: 0.2,
+                },
+                1,
+            ),
+            hyper_torus(
+                cgmath::vec4(6.0, 0.5, 0.0, 0.2),
+                HyperTorusShape::Torisphere {
+                    major_radius: 0.6,
+                    minor_radius: 0.3,
+                },
+                2,
+            ),
+        ],
+    }
+}
+
+#[test]
+fn cpu_matches_gpu() {
+    const WIDTH: u32 = 160;
+    const HEIGHT: u32 = 90;
+
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+    // software adapters like llvmpipe take far too long to build the ray tracing pipeline
+    let Some(adapter) =
+        pollster::block_on(instance.request_adapter(&Default::default())).filter(|adapter| {
+            adapter.get_info().device_type != wgpu::DeviceType::Cpu
+                && check_adapter_limits(adapter).is_ok()
+        })
+    else {
+        eprintln!("no gpu adapter with the limits the ray tracer needs is available, skipping");
+        return;
+    };
+    let (device, queue) = pollster::block_on(adapter.request_device(
+        &wgpu::DeviceDescriptor {
+            required_limits: required_limits(),
+            ..Default::default()
+        },
+        None,
+    ))
+    .expect("device should have been requested successfully");
+
+    let scene = tori_scene();
+    let mut camera = Camera::default();
+    scene.lighting.apply_to_camera(&mut camera);
+    let cpu_image = CpuRenderer {
+        camera: &camera,
+        materials: &scene.materials,
+        objects: &scene.objects,
+        max_bounces: DEFAULT_MAX_BOUNCES,
+    }
+    .render(WIDTH, HEIGHT);
+
+    let mut state = State::new(&device, &queue, scene);
+    let gpu_image =
+        OffscreenRenderer::new(&device, WIDTH, HEIGHT).render(&mut state, &device, &queue);
+
+    // pixels right on the edge of a torus can go either way, but almost all of them should be the same
+    let different_pixels = cpu_image
+        .pixels
+        .iter()
+        .zip(&gpu_image.pixels)
+        .filter(|(cpu, gpu)| {
+            cpu.iter()
+                .zip(gpu.iter())
+                .any(|(&cpu, &gpu)| cpu.abs_diff(gpu) > 2)
+        })
+        .count();
+    assert!(
+        different_pixels <= cpu_image.pixels.len() / 100,
+        "{different_pixels} of {} pixels were different",
+        cpu_image.pixels.len(),
+    );
+}
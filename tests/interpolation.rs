@@ -0,0 +1,183 @@
+use cgmath::InnerSpace;
+use ray_tracer::math::{Bivector, Rotor, Transform};
+
+const TOLERANCE: f32 = 0.0001;
+
+/// Points that together tell apart any two transforms
+const POINTS: [cgmath::Vector4<f32>; 5] = [
+    cgmath::Vector4::new(0.0, 0.0, 0.0, 0.0),
+    cgmath::Vector4::new(1.0, 0.0, 0.0, 0.0),
+    cgmath::Vector4::new(0.0, 1.0, 0.0, 0.0),
+    cgmath::Vector4::new(0.0, 0.0, 1.0, 0.0),
+    cgmath::Vector4::new(0.0, 0.0, 0.0, 1.0),
+];
+
+/// Checks that the rotors are the same rotation, a rotor and its negative being the same
+fn assert_same_rotation(a: Rotor, b: Rotor) {
+    for point in POINTS {
+        let (a, b) = (a.rotate(point), b.rotate(point));
+        assert!((a - b).magnitude() < TOLERANCE, "{a:?} isnt {b:?}");
+    }
+}
+
+fn assert_same_transform(a: Transform, b: Transform) {
+    for point in POINTS {
+        let (a, b) = (a.transform(point), b.transform(point));
+        assert!((a - b).magnitude() < TOLERANCE, "{a:?} isnt {b:?}");
+    }
+}
+
+/// Some transforms that turn in one or two planes and move
+fn transforms() -> [Transform; 4] {
+    [
+        Transform::translation(cgmath::vec4(1.0, -2.0, 0.5, 3.0)),
+        Transform::translation(cgmath::vec4(0.0, 1.0, 0.0, 0.0)) * Transform::rotation_xz(1.1),
+        Transform::translation(cgmath::vec4(-0.5, 0.2, 2.0, -1.0))
+            * Transform::rotation_xy(0.8)
+            * Transform::rotation_yw(-2.3)
+            * Transform::rotation_zw(0.4),
+        Transform::translation(cgmath::vec4(0.3, 0.3, -0.7, 0.1))
+            * Transform::rotation_xw(2.0)
+            * Transform::rotation_yz(2.0),
+    ]
+}
+
+#[test]
+fn slerp_in_one_plane() {
+    let from = Rotor::rotation_xy(0.2);
+    let to = Rotor::rotation_xy(1.4);
+    assert_same_rotation(from.slerp(to, 0.0), from);
+    assert_same_rotation(from.slerp(to, 0.25), Rotor::rotation_xy(0.5));
+    assert_same_rotation(from.slerp(to, 1.0), to);
+    // going past the ends keeps turning
+    assert_same_rotation(from.slerp(to, 2.0), Rotor::rotation_xy(2.6));
+
+    // the negative of a rotor is the same rotation, so it shouldnt go the long way round
+    assert_same_rotation(
+        Rotor::IDENTITY.slerp(-Rotor::rotation_yw(0.6), 0.5),
+        Rotor::rotation_yw(0.3),
+    );
+    assert_same_rotation(
+        Rotor::rotation_xz(3.0).slerp(Rotor::rotation_xz(-3.0), 0.5),
+        Rotor::rotation_xz(std::f32::consts::PI),
+    );
+}
+
+#[test]
+fn slerp_in_two_planes() {
+    // both planes of the rotation turn at their own speed
+    let to = Rotor::rotation_xy(1.0) * Rotor::rotation_zw(0.4);
+    for t in [0.0, 0.3, 0.5, 1.0] {
+        assert_same_rotation(
+            Rotor::IDENTITY.slerp(to, t),
+            Rotor::rotation_xy(t) * Rotor::rotation_zw(0.4 * t),
+        );
+    }
+
+    // and when both angles of the rotation between them are the same
+    let from = Rotor::rotation_yz(0.5);
+    let to = Rotor::rotation_xw(1.2) * Rotor::rotation_yz(1.7);
+    assert_same_rotation(
+        from.slerp(to, 0.5),
+        Rotor::rotation_xw(0.6) * Rotor::rotation_yz(1.1),
+    );
+
+    // the angle between steps stays the same
+    let from = Rotor::rotation_xz(0.3) * Rotor::rotation_yw(-0.2);
+    let to = Rotor::rotation_xy(1.1) * Rotor::rotation_zw(0.5) * Rotor::rotation_xw(0.9);
+    let step_angle = |t: f32| {
+        let direction = cgmath::vec4(0.5, 0.5, 0.5, 0.5);
+        let a = from.slerp(to, t).rotate(direction);
+        let b = from.slerp(to, t + 0.1).rotate(direction);
+        a.angle(b).0
+    };
+    for t in [0.2, 0.4, 0.6, 0.8] {
+        assert!((step_angle(t) - step_angle(0.0)).abs() < TOLERANCE);
+    }
+}
+
+#[test]
+fn exp_and_log() {
+    assert_same_transform(
+        Transform::exp(Bivector::ZERO, cgmath::vec4(1.0, 2.0, 3.0, 4.0)),
+        Transform::translation(cgmath::vec4(1.0, 2.0, 3.0, 4.0)),
+    );
+    for transform in transforms() {
+        let (rotation, velocity) = transform.log();
+        assert_same_transform(Transform::exp(rotation, velocity), transform);
+    }
+}
+
+#[test]
+fn interpolate_along_a_screw() {
+    // turning around the point (1, 0, 0, 0) in the xy plane while moving along w
+    let center = cgmath::vec4(1.0, 0.0, 0.0, 0.0);
+    let screw = |t: f32| {
+        Transform::translation(center + cgmath::vec4(0.0, 0.0, 0.0, 3.0 * t))
+            * Transform::rotation_xy(1.2 * t)
+            * Transform::translation(-center)
+    };
+    for t in [0.0, 0.25, 0.5, 1.0, 1.5] {
+        assert_same_transform(Transform::IDENTITY.interpolate(screw(1.0), t), screw(t));
+    }
+    // the center of the screw stays on its axis
+    let center_at_half = Transform::IDENTITY
+        .interpolate(screw(1.0), 0.5)
+        .transform(center);
+    assert!((center_at_half - cgmath::vec4(1.0, 0.0, 0.0, 1.5)).magnitude() < TOLERANCE);
+
+    for from in transforms() {
+        for to in transforms() {
+            assert_same_transform(from.interpolate(to, 0.0), from);
+            assert_same_transform(from.interpolate(to, 1.0), to);
+            // half way twice gets to the end
+            let half = from.interpolate(to, 0.5);
+            assert_same_transform(half.interpolate(to, 1.0), to);
+            assert_same_transform(from.interpolate(half, 2.0), to);
+        }
+    }
+}
+
+#[test]
+fn blend() {
+    let [a, b, c, d] = transforms();
+    assert_same_transform(Transform::blend(&[]), Transform::IDENTITY);
+    assert_same_transform(Transform::blend(&[(c, 2.0)]), c);
+    assert_same_transform(
+        Transform::blend(&[(b, 1.0), (d, 3.0)]),
+        b.interpolate(d, 0.75),
+    );
+    assert_same_transform(
+        Transform::blend(&[(c, 0.5), (a, 0.5)]),
+        c.interpolate(a, 0.5),
+    );
+
+    // translations average their offsets
+    let translations = [
+        (cgmath::vec4(1.0, 0.0, 0.0, 0.0), 1.0),
+        (cgmath::vec4(0.0, 2.0, 0.0, -1.0), 2.0),
+        (cgmath::vec4(0.0, 0.0, 4.0, 1.0), 1.0),
+    ];
+    assert_same_transform(
+        Transform::blend(
+            &translations.map(|(offset, weight)| (Transform::translation(offset), weight)),
+        ),
+        Transform::translation(cgmath::vec4(0.25, 1.0, 1.0, -0.25)),
+    );
+
+    // rotations in the same plane average their angles
+    assert_same_transform(
+        Transform::blend(&[
+            (Transform::rotation_zw(0.3), 1.0),
+            (Transform::rotation_zw(1.0), 1.0),
+            (Transform::rotation_zw(-0.4), 2.0),
+        ]),
+        Transform::rotation_zw(0.125),
+    );
+
+    // the order doesnt matter
+    let weighted = [(a, 0.2), (b, 0.5), (c, 0.1), (d, 0.2)];
+    let mut reversed = weighted;
+    reversed.reverse();
+    assert_same_transform(Transform::blend(&weighted), Transform::blend(&reversed));
+}
@@ -0,0 +1,74 @@
+use ray_tracer::{
+    camera::Camera,
+    cpu_renderer::CpuRenderer,
+    image::Image,
+    material::{Material, MaterialKind},
+    objects::{HyperPlane, HyperSphere, Object},
+    scene::Scene,
+    state::DEFAULT_MAX_BOUNCES,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 36;
+
+/// A metal hyper sphere in front of the camera, sitting on a diffuse floor
+fn metal_scene(roughness: f32) -> Scene {
+    Scene {
+        lighting: Default::default(),
+        materials: vec![
+            Material::diffuse(cgmath::vec3(0.8, 0.8, 0.8)),
+            Material {
+                color: cgmath::vec3(0.9, 0.9, 0.9),
+                kind: MaterialKind::Metal { roughness },
+                emission_strength: 0.0,
+            },
+        ],
+        objects: vec![
+            Object::HyperPlane(HyperPlane {
+                position: cgmath::vec4(0.0, -1.0, 0.0, 0.0),
+                normal: cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+                material: 0,
+            }),
+            Object::HyperSphere(HyperSphere {
+                position: cgmath::vec4(2.5, 0.0, 0.0, 0.0),
+                radius: 1.0,
+                material: 1,
+            }),
+        ],
+    }
+}
+
+fn render_cpu(scene: &Scene) -> Image {
+    let mut camera = Camera::default();
+    scene.lighting.apply_to_camera(&mut camera);
+    CpuRenderer {
+        camera: &camera,
+        materials: &scene.materials,
+        objects: &scene.objects,
+        max_bounces: DEFAULT_MAX_BOUNCES,
+    }
+    .render(WIDTH, HEIGHT)
+}
+
+#[test]
+fn rough_metal_differs_from_smooth_metal() {
+    let smooth = render_cpu(&metal_scene(0.0));
+    let rough = render_cpu(&metal_scene(0.5));
+
+    let different_pixels = smooth
+        .pixels
+        .iter()
+        .zip(&rough.pixels)
+        .filter(|(smooth, rough)| smooth != rough)
+        .count();
+    assert!(
+        different_pixels > 50,
+        "only {different_pixels} pixels changed with roughness",
+    );
+}
+
+#[test]
+fn rough_metal_has_no_noise() {
+    let scene = metal_scene(0.5);
+    assert_eq!(render_cpu(&scene).pixels, render_cpu(&scene).pixels);
+}
@@ -0,0 +1,140 @@
+use cgmath::{InnerSpace, Matrix, SquareMatrix};
+use ray_tracer::math::{Rotor, Transform};
+
+const TOLERANCE: f32 = 0.0001;
+
+/// How many random rotors each test checks
+const SAMPLES: usize = 1000;
+
+/// A xorshift random number generator, so that the tests are the same every time
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> f32 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as f32 / u32::MAX as f32
+    }
+
+    fn range(&mut self, min: f32, max: f32) -> f32 {
+        min + (max - min) * self.next()
+    }
+
+    fn vector(&mut self) -> cgmath::Vector4<f32> {
+        cgmath::vec4(
+            self.range(-5.0, 5.0),
+            self.range(-5.0, 5.0),
+            self.range(-5.0, 5.0),
+            self.range(-5.0, 5.0),
+        )
+    }
+
+    /// A rotor made from rotations in all six planes, by any angle
+    fn rotor(&mut self) -> Rotor {
+        let rotations = [
+            Rotor::rotation_xy,
+            Rotor::rotation_xz,
+            Rotor::rotation_xw,
+            Rotor::rotation_yz,
+            Rotor::rotation_yw,
+            Rotor::rotation_zw,
+        ];
+        rotations
+            .into_iter()
+            .fold(Rotor::IDENTITY, |rotor, rotation| {
+                rotation(self.range(-std::f32::consts::TAU, std::f32::consts::TAU)) * rotor
+            })
+    }
+}
+
+fn assert_vectors_equal(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) {
+    assert!((a - b).magnitude() < TOLERANCE * 10.0, "{a:?} isnt {b:?}");
+}
+
+fn assert_rotors_equal(a: Rotor, b: Rotor) {
+    let components = |rotor: Rotor| {
+        [
+            rotor.s,
+            rotor.e12,
+            rotor.e13,
+            rotor.e14,
+            rotor.e23,
+            rotor.e24,
+            rotor.e34,
+            rotor.e1234,
+        ]
+    };
+    for (a_component, b_component) in components(a).into_iter().zip(components(b)) {
+        assert!(
+            (a_component - b_component).abs() < TOLERANCE,
+            "{a:?} isnt {b:?}"
+        );
+    }
+}
+
+#[test]
+fn rotate_matches_the_matrix() {
+    let mut random = Random(0x2545f491);
+    for _ in 0..SAMPLES {
+        let rotor = random.rotor();
+        let matrix = rotor.to_matrix();
+        let vector = random.vector();
+        assert_vectors_equal(matrix * vector, rotor.rotate(vector));
+
+        // a rotation matrix is orthonormal and doesnt mirror anything
+        let identity = matrix.transpose() * matrix;
+        for column in 0..4 {
+            for row in 0..4 {
+                let expected = if column == row { 1.0 } else { 0.0 };
+                assert!((identity[column][row] - expected).abs() < TOLERANCE);
+            }
+        }
+        assert!((matrix.determinant() - 1.0).abs() < TOLERANCE);
+    }
+}
+
+#[test]
+fn matrix_back_to_rotor() {
+    let mut random = Random(0x9e3779b9);
+    for _ in 0..SAMPLES {
+        let rotor = random.rotor();
+        let rotor = if rotor.s < 0.0 { -rotor } else { rotor };
+        assert_rotors_equal(Rotor::from_matrix(rotor.to_matrix()), rotor);
+    }
+
+    // the axes going to other axes
+    let quarter_turn = cgmath::Matrix4::from_cols(
+        cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+        cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+        cgmath::vec4(-1.0, 0.0, 0.0, 0.0),
+    );
+    assert_rotors_equal(
+        Rotor::from_matrix(quarter_turn),
+        Rotor::rotation_xw(std::f32::consts::FRAC_PI_2),
+    );
+    let half_turns = cgmath::Matrix4::from_diagonal(cgmath::vec4(-1.0, -1.0, -1.0, -1.0));
+    let rotor = Rotor::from_matrix(half_turns);
+    for axis in 0..4 {
+        let mut direction = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+        direction[axis] = 1.0;
+        assert_vectors_equal(rotor.rotate(direction), -direction);
+    }
+}
+
+#[test]
+fn transform_matches_the_matrix() {
+    let mut random = Random(0x85ebca6b);
+    for _ in 0..SAMPLES {
+        let offset = random.vector();
+        let transform = Transform::translation(offset) * Transform::from_rotor(random.rotor());
+        let (matrix, matrix_offset) = transform.to_matrix();
+        assert_vectors_equal(matrix_offset, offset);
+        let point = random.vector();
+        assert_vectors_equal(matrix * point + matrix_offset, transform.transform(point));
+
+        let from_matrix = Transform::from_matrix(matrix, matrix_offset);
+        assert_vectors_equal(from_matrix.transform(point), transform.transform(point));
+    }
+}
@@ -0,0 +1,229 @@
+use cgmath::InnerSpace;
+use ray_tracer::math::{Hyperplane, Line, Point, Transform};
+
+const TOLERANCE: f32 = 0.0001;
+
+fn assert_vectors_equal(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) {
+    assert!((a - b).magnitude() < TOLERANCE, "{a:?} isnt {b:?}");
+}
+
+fn axis(index: usize) -> cgmath::Vector4<f32> {
+    let mut axis = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    axis[index] = 1.0;
+    axis
+}
+
+/// The hyperplane at right angles to an axis, `distance` along it
+fn axis_hyperplane(index: usize, distance: f32) -> Hyperplane {
+    Hyperplane::new(axis(index), distance)
+}
+
+/// Some transforms that turn in one or two planes and move
+fn transforms() -> [Transform; 3] {
+    [
+        Transform::translation(cgmath::vec4(1.0, -2.0, 0.5, 3.0)),
+        Transform::translation(cgmath::vec4(0.0, 1.0, 0.0, 0.0)) * Transform::rotation_xz(1.1),
+        Transform::translation(cgmath::vec4(-0.5, 0.2, 2.0, -1.0))
+            * Transform::rotation_xy(0.8)
+            * Transform::rotation_yw(-2.3)
+            * Transform::rotation_zw(0.4),
+    ]
+}
+
+/// Points that arent all in one hyperplane
+fn points() -> [cgmath::Vector4<f32>; 4] {
+    [
+        cgmath::vec4(1.0, 2.0, 3.0, 4.0),
+        cgmath::vec4(-1.0, 0.5, 0.0, 2.0),
+        cgmath::vec4(0.3, -0.4, 0.8, 0.1),
+        cgmath::vec4(2.0, 1.0, -1.0, 0.0),
+    ]
+}
+
+#[test]
+fn points_and_hyperplanes() {
+    let position = cgmath::vec4(1.0, -2.0, 3.0, 0.5);
+    assert_vectors_equal(Point::new(position).position(), position);
+    assert_vectors_equal((Point::new(position) * -2.0).position(), position);
+    assert_eq!((Point::new(position) * -2.0).normalized().weight(), 1.0);
+    assert_eq!(Point::ideal(position).weight(), 0.0);
+    assert_vectors_equal(Point::ORIGIN.position(), cgmath::vec4(0.0, 0.0, 0.0, 0.0));
+
+    let hyperplane = Hyperplane::new(cgmath::vec4(0.0, 2.0, 0.0, 0.0), 4.0);
+    assert_vectors_equal(hyperplane.normal(), cgmath::vec4(0.0, 2.0, 0.0, 0.0));
+    assert!((hyperplane.signed_distance(position) + 4.0).abs() < TOLERANCE);
+    let normalized = hyperplane.normalized();
+    assert!((normalized.normal().magnitude() - 1.0).abs() < TOLERANCE);
+    assert!((normalized.distance() - 2.0).abs() < TOLERANCE);
+}
+
+#[test]
+fn meet() {
+    // a hyperplane at right angles to each axis meets the others in a point
+    let [x, y, z, w] = [0, 1, 2, 3].map(|index| axis_hyperplane(index, index as f32 + 1.0));
+    let expected = cgmath::vec4(1.0, 2.0, 3.0, 4.0);
+    assert_vectors_equal((x ^ y ^ z ^ w).position(), expected);
+    assert_vectors_equal((w ^ (y ^ (z ^ x))).position(), expected);
+    assert_vectors_equal(((x ^ y) ^ (z ^ w)).position(), expected);
+
+    // a ray hitting a hyperplane
+    let ray = Line::new(
+        cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+        cgmath::vec4(1.0, 1.0, 0.5, 0.0),
+    );
+    let hyperplane = Hyperplane::new(cgmath::vec4(2.0, 0.0, 0.0, 0.0), 1.0);
+    assert_vectors_equal(
+        (ray ^ hyperplane).position(),
+        cgmath::vec4(0.5, 1.5, 0.25, 0.0),
+    );
+    // and a ray along the hyperplane only meets it infinitely far away
+    let along = Line::new(cgmath::vec4(0.0, 1.0, 0.0, 0.0), axis(1));
+    assert!((along ^ hyperplane).weight().abs() < TOLERANCE);
+}
+
+#[test]
+fn join() {
+    let [a, b, c, d] = points().map(Point::new);
+    let line = a & b;
+    let direction = b.position() - a.position();
+    assert_vectors_equal(line.direction(), direction);
+    assert_vectors_equal(line.normalized().direction(), direction.normalize());
+    assert_vectors_equal(Line::new(a.position(), direction).direction(), direction);
+
+    let plane = line & c;
+    let hyperplane = plane & d;
+    for point in [a, b, c, d] {
+        assert!(hyperplane.signed_distance(point.position()).abs() < TOLERANCE);
+    }
+    for point in [a, b, c] {
+        assert!(plane.reject(point).magnitude() < TOLERANCE);
+        assert!((a & (b & c)).reject(point).magnitude() < TOLERANCE);
+    }
+    // two lines that dont meet make a hyperplane too
+    let other = (a & b) & (c & d);
+    for point in [a, b, c, d] {
+        assert!(other.signed_distance(point.position()).abs() < TOLERANCE);
+    }
+
+    // the hyperplane x = 1
+    let [a, b, c, d] = [
+        cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+        cgmath::vec4(1.0, 1.0, 0.0, 0.0),
+        cgmath::vec4(1.0, 0.0, 1.0, 0.0),
+        cgmath::vec4(1.0, 0.0, 0.0, 1.0),
+    ]
+    .map(Point::new);
+    let hyperplane = (a & b & c & d).normalized();
+    assert_vectors_equal(hyperplane.normal() * hyperplane.distance(), axis(0));
+}
+
+#[test]
+fn project_and_reject() {
+    let point = Point::new(cgmath::vec4(3.0, 2.0, 1.0, 4.0));
+
+    let hyperplane = axis_hyperplane(0, 1.0) * 3.0;
+    assert_vectors_equal(
+        hyperplane.project(point).position(),
+        cgmath::vec4(1.0, 2.0, 1.0, 4.0),
+    );
+    assert_vectors_equal(hyperplane.reject(point), cgmath::vec4(2.0, 0.0, 0.0, 0.0));
+    assert_vectors_equal(
+        hyperplane
+            .perpendicular_through(point)
+            .normalized()
+            .direction(),
+        -axis(0),
+    );
+
+    let plane = axis_hyperplane(1, 0.0) ^ axis_hyperplane(3, 1.0);
+    assert_vectors_equal(
+        plane.project(point).position(),
+        cgmath::vec4(3.0, 0.0, 1.0, 1.0),
+    );
+    assert_vectors_equal(plane.reject(point), cgmath::vec4(0.0, 2.0, 0.0, 3.0));
+    // the plane at right angles meets it where the point is projected
+    assert_vectors_equal(
+        (plane.perpendicular_through(point) ^ plane).position(),
+        plane.project(point).position(),
+    );
+
+    let line = Line::new(cgmath::vec4(0.0, 1.0, 0.0, 0.0), axis(0) * 2.0);
+    assert_vectors_equal(
+        line.project(point).position(),
+        cgmath::vec4(3.0, 1.0, 0.0, 0.0),
+    );
+    assert_vectors_equal(line.reject(point), cgmath::vec4(0.0, 1.0, 1.0, 4.0));
+    let hyperplane = line.perpendicular_through(point).normalized();
+    assert_vectors_equal(hyperplane.normal() * hyperplane.distance(), axis(0) * 3.0);
+
+    // projecting onto flats that arent along the axes
+    let [a, b, c, d] = points().map(Point::new);
+    let line = a & b;
+    let plane = line & c;
+    let hyperplane = plane & d;
+    for projected in [
+        hyperplane.project(point),
+        plane.project(point),
+        line.project(point),
+    ] {
+        assert!(hyperplane.signed_distance(projected.position()).abs() < TOLERANCE);
+    }
+    // the offsets are at right angles to the flats
+    let closest = plane.project(point).position();
+    for on_plane in [a, b, c] {
+        assert!(
+            (on_plane.position() - closest)
+                .dot(plane.reject(point))
+                .abs()
+                < TOLERANCE
+        );
+    }
+    assert!(line.direction().dot(line.reject(point)).abs() < TOLERANCE);
+    assert_vectors_equal(
+        hyperplane.reject(point),
+        hyperplane.normal().normalize() * hyperplane.signed_distance(point.position()),
+    );
+}
+
+#[test]
+fn transformed() {
+    let [a, b, c, d] = points().map(Point::new);
+    let line = a & b;
+    let plane = line & c;
+    let hyperplane = plane & d;
+    for transform in transforms() {
+        let [ta, tb, tc, td] = [a, b, c, d].map(|point| point.transformed(transform));
+        for (point, transformed) in [a, b, c, d].into_iter().zip([ta, tb, tc, td]) {
+            assert_vectors_equal(
+                transformed.position(),
+                transform.transform(point.position()),
+            );
+        }
+
+        // the flats still go through the points
+        let hyperplane = hyperplane.transformed(transform);
+        let plane = plane.transformed(transform);
+        let line = line.transformed(transform);
+        for point in [ta, tb, tc, td] {
+            assert!(hyperplane.signed_distance(point.position()).abs() < TOLERANCE);
+        }
+        for point in [ta, tb, tc] {
+            assert!(plane.reject(point).magnitude() < TOLERANCE);
+        }
+        for point in [ta, tb] {
+            assert!(line.reject(point).magnitude() < TOLERANCE);
+        }
+        assert_vectors_equal(line.direction(), tb.position() - ta.position());
+
+        // meeting transformed hyperplanes is the same as transforming where they meet
+        let [x, y, z, w] = [0, 1, 2, 3].map(|index| axis_hyperplane(index, index as f32 - 1.5));
+        assert_vectors_equal(
+            (x ^ y ^ z ^ w).transformed(transform).position(),
+            (x.transformed(transform)
+                ^ y.transformed(transform)
+                ^ z.transformed(transform)
+                ^ w.transformed(transform))
+            .position(),
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use cgmath::InnerSpace;
+use ray_tracer::{
+    objects::Object,
+    polytope::{Polytope, PolytopeError, RegularPolychoron},
+    ray::{Ray, RayIntersect},
+    scene::Scene,
+};
+use std::collections::HashSet;
+
+const DISTANCE_TOLERANCE: f32 = 0.0001;
+
+/// The numbers of vertices, edges, faces and cells
+fn counts(polytope: &Polytope) -> [usize; 4] {
+    let edges = polytope
+        .faces()
+        .iter()
+        .flat_map(|face| {
+            (0..face.len()).map(|i| {
+                let (a, b) = (face[i], face[(i + 1) % face.len()]);
+                (a.min(b), a.max(b))
+            })
+        })
+        .collect::<HashSet<_>>();
+    [
+        polytope.vertices().len(),
+        edges.len(),
+        polytope.faces().len(),
+        polytope.cells().len(),
+    ]
+}
+
+#[test]
+fn regular_polychora_counts() {
+    let expected = [
+        (RegularPolychoron::FiveCell, [5, 10, 10, 5], 3),
+        (RegularPolychoron::Tesseract, [16, 32, 24, 8], 4),
+        (RegularPolychoron::SixteenCell, [8, 24, 32, 16], 3),
+        (RegularPolychoron::TwentyFourCell, [24, 96, 96, 24], 3),
+        (
+            RegularPolychoron::OneHundredTwentyCell,
+            [600, 1200, 720, 120],
+            5,
+        ),
+        (RegularPolychoron::SixHundredCell, [120, 720, 1200, 600], 3),
+    ];
+    for (polychoron, counts_of_each, face_vertices) in expected {
+        let polytope = polychoron.polytope();
+        assert_eq!(counts(&polytope), counts_of_each, "{polychoron:?}");
+        assert!(
+            polytope
+                .faces()
+                .iter()
+                .all(|face| face.len() == face_vertices),
+            "{polychoron:?} has faces with the wrong number of vertices",
+        );
+        for vertex in polytope.vertices() {
+            assert!((vertex.magnitude() - 1.0).abs() < DISTANCE_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn tetrahedra_face_outwards() {
+    for polychoron in RegularPolychoron::ALL {
+        let polytope = polychoron.polytope();
+        let mesh = polytope
+            .tetrahedron_mesh(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |_| 0)
+            .expect("the polytope should make a valid mesh")
+            .mesh;
+        let mut volume = 0.0;
+        for cell in 0..mesh.cells().len() {
+            let tetrahedron = mesh.cell(cell);
+            let center = tetrahedron.vertices.iter().sum::<cgmath::Vector4<f32>>() / 4.0;
+            assert!(
+                tetrahedron.normal().dot(center) > 0.0,
+                "cell {cell} of the {polychoron:?} faces inwards",
+            );
+            // the 4d volume of the cone from the center to the tetrahedron
+            volume += tetrahedron
+                .unnormalized_normal()
+                .dot(tetrahedron.vertices[0])
+                / 24.0;
+        }
+        // the tesseract has an edge length of 1
+        if polychoron == RegularPolychoron::Tesseract {
+            assert!((volume - 1.0).abs() < DISTANCE_TOLERANCE);
+        }
+    }
+}
+
+#[test]
+fn cell_materials() {
+    let tesseract = RegularPolychoron::Tesseract
+        .polytope()
+        .tetrahedron_mesh(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |cell| cell as u32 + 1)
+        .expect("the tesseract should make a valid mesh");
+
+    // each cell of the tesseract faces along a different axis
+    let mut materials = HashSet::new();
+    for axis in 0..4 {
+        for direction in [-1.0, 1.0] {
+            let mut origin = cgmath::vec4(0.1, 0.05, -0.05, 0.02);
+            origin[axis] = -3.0 * direction;
+            let mut ray_direction = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+            ray_direction[axis] = direction;
+            let hit = tesseract
+                .intersect(Ray {
+                    origin,
+                    direction: ray_direction,
+                })
+                .expect("the ray should hit the tesseract");
+            assert!((hit.distance - 2.5).abs() < DISTANCE_TOLERANCE);
+            assert_eq!(hit.normal[axis], -direction);
+
+            let spans = tesseract.spans(Ray {
+                origin,
+                direction: ray_direction,
+            });
+            assert_eq!(spans.len(), 1);
+            assert_eq!(spans[0].start.material, hit.material);
+            materials.insert(hit.material);
+        }
+    }
+    assert_eq!(materials, (1..=8).collect());
+}
+
+const FIVE_CELL: &str = "4OFF
+# Vertices, Faces, Edges, Cells
+5 10 10 5
+# Vertices
+0.559017 0.559017 0.559017 -0.25
+0.559017 -0.559017 -0.559017 -0.25
+-0.559017 0.559017 -0.559017 -0.25
+-0.559017 -0.559017 0.559017 -0.25
+0 0 0 1
+# Faces
+3 0 1 2
+3 0 1 3
+3 0 1 4
+3 0 2 3
+3 0 2 4
+3 0 3 4
+3 1 2 3
+3 1 2 4
+3 1 3 4
+3 2 3 4
+# Cells, with colors
+4 6 7 8 9 1 0 0
+4 3 4 5 9 0 1 0
+4 1 2 5 8 0 0 1
+4 0 2 4 7
+4 0 1 3 6
+";
+
+#[test]
+fn load_off() {
+    let polytope = Polytope::from_off(FIVE_CELL).expect("the 5-cell should load");
+    assert_eq!(counts(&polytope), [5, 10, 10, 5]);
+    assert_eq!(polytope.cells()[0], [6, 7, 8, 9]);
+
+    let mesh = polytope
+        .tetrahedron_mesh(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |_| 0)
+        .expect("the 5-cell should make a valid mesh")
+        .mesh;
+    assert_eq!(mesh.cells().len(), 5);
+    let hit = mesh
+        .closest_crossing(Ray {
+            origin: cgmath::vec4(0.0, 0.01, 0.02, -3.0),
+            direction: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        })
+        .expect("the ray should hit the 5-cell");
+    // the bottom cell is the one opposite the last vertex
+    assert!((hit.0 - 2.75).abs() < DISTANCE_TOLERANCE);
+    assert!(!mesh.cells()[hit.1].contains(&4));
+}
+
+#[test]
+fn load_off_errors() {
+    let error = Polytope::from_off("OFF\n1 1 1\n").unwrap_err();
+    assert_eq!(error.to_string(), "1: expected 4OFF, found OFF");
+
+    let error = Polytope::from_off(&FIVE_CELL.replace("0 0 0 1", "0 0 zero 1")).unwrap_err();
+    assert_eq!(error.to_string(), "9: zero isnt a valid number");
+
+    let error = Polytope::from_off(&FIVE_CELL.replace("3 2 3 4", "3 2 3 5")).unwrap_err();
+    assert!(matches!(
+        error,
+        PolytopeError::VertexOutOfRange {
+            face: 9,
+            vertex: 5,
+            vertex_count: 5,
+        },
+    ));
+
+    // a count so big that the indices after it cant be counted shouldnt overflow
+    let error = Polytope::from_off(&FIVE_CELL.replace("3 2 3 4", &format!("{} 2 3 4", usize::MAX)))
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("20: expected {} indices", usize::MAX)
+    );
+
+    let error = Polytope::from_off(&FIVE_CELL[..FIVE_CELL.len() - 10]).unwrap_err();
+    assert!(error.to_string().contains("the file ended before cell 4"));
+}
+
+#[test]
+fn ray_along_cell_boundaries() {
+    // the cells of the 16-cell meet at w = 0, so this ray is in the sides of their bounding boxes
+    let sixteen_cell = RegularPolychoron::SixteenCell
+        .polytope()
+        .tetrahedron_mesh(cgmath::vec4(0.0, 0.0, 0.0, 0.0), |_| 0)
+        .expect("the 16-cell should make a valid mesh");
+    let hit = sixteen_cell
+        .intersect(Ray {
+            origin: cgmath::vec4(-3.0, 0.05, 0.02, 0.0),
+            direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+        })
+        .expect("the ray should hit the 16-cell");
+    assert!((hit.distance - 2.07).abs() < DISTANCE_TOLERANCE);
+}
+
+#[test]
+fn polychora_scene() {
+    let scene = Scene::load("scenes/polychora.ron").expect("the scene should load");
+    let meshes = scene
+        .objects
+        .iter()
+        .filter_map(|object| match object {
+            Object::TetrahedronMesh(tetrahedron_mesh) => Some(tetrahedron_mesh),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(meshes.len(), RegularPolychoron::ALL.len());
+    for (mesh, polychoron) in meshes.into_iter().zip(RegularPolychoron::ALL) {
+        let (tetrahedra, _) = polychoron.polytope().tetrahedra();
+        assert_eq!(mesh.mesh.cells().len(), tetrahedra.len(), "{polychoron:?}");
+        assert_eq!(
+            mesh.cell_materials.len(),
+            tetrahedra.len(),
+            "{polychoron:?}"
+        );
+    }
+}
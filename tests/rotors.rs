@@ -0,0 +1,231 @@
+use cgmath::InnerSpace;
+use ray_tracer::math::{Bivector, RotationKind, Rotor, Transform};
+
+const TOLERANCE: f32 = 0.0001;
+
+fn components(rotor: Rotor) -> [f32; 8] {
+    let Rotor {
+        s,
+        e12,
+        e13,
+        e14,
+        e23,
+        e24,
+        e34,
+        e1234,
+    } = rotor;
+    [s, e12, e13, e14, e23, e24, e34, e1234]
+}
+
+fn assert_rotors_equal(a: Rotor, b: Rotor) {
+    let difference = components(a)
+        .into_iter()
+        .zip(components(b))
+        .map(|(a, b)| (a - b).abs())
+        .fold(0.0, f32::max);
+    assert!(difference < TOLERANCE, "{a:?} isnt {b:?}");
+}
+
+fn assert_bivectors_equal(a: Bivector, b: Bivector) {
+    assert!((a - b).magnitude() < TOLERANCE, "{a:?} isnt {b:?}");
+}
+
+fn assert_vectors_equal(a: cgmath::Vector4<f32>, b: cgmath::Vector4<f32>) {
+    assert!((a - b).magnitude() < TOLERANCE, "{a:?} isnt {b:?}");
+}
+
+type Rotation = fn(f32) -> Rotor;
+
+/// Each of the rotation constructors with the bivector for its plane
+fn planes() -> [(Rotation, Bivector); 6] {
+    let zero = Bivector::ZERO;
+    [
+        (Rotor::rotation_xy, Bivector { xy: 1.0, ..zero }),
+        (Rotor::rotation_xz, Bivector { xz: 1.0, ..zero }),
+        (Rotor::rotation_xw, Bivector { xw: 1.0, ..zero }),
+        (Rotor::rotation_yz, Bivector { yz: 1.0, ..zero }),
+        (Rotor::rotation_yw, Bivector { yw: 1.0, ..zero }),
+        (Rotor::rotation_zw, Bivector { zw: 1.0, ..zero }),
+    ]
+}
+
+fn axis(index: usize) -> cgmath::Vector4<f32> {
+    let mut axis = cgmath::vec4(0.0, 0.0, 0.0, 0.0);
+    axis[index] = 1.0;
+    axis
+}
+
+#[test]
+fn exp_and_log_of_single_planes() {
+    for (rotation, plane) in planes() {
+        for angle in [0.0, 0.3, -1.2, 2.5, 3.0] {
+            let rotor = rotation(angle);
+            assert_rotors_equal(Rotor::exp(plane * angle), rotor);
+
+            let (log_plane, log_angle) = rotor.plane_and_angle();
+            assert!((log_angle - angle.abs()).abs() < TOLERANCE);
+            if angle != 0.0 {
+                assert_bivectors_equal(log_plane, plane * angle.signum());
+            }
+            assert_bivectors_equal(rotor.log(), plane * angle);
+        }
+    }
+}
+
+#[test]
+fn exp_and_log_of_double_rotations() {
+    let [xy, xz, xw, yz, yw, zw] = planes();
+    // every pair of planes at right angles, with the same and different angles
+    for ((first, first_plane), (second, second_plane)) in [(xy, zw), (xz, yw), (xw, yz)] {
+        for (first_angle, second_angle) in [(0.4, 1.1), (0.7, 0.7), (-0.5, 0.5), (2.0, -0.3)] {
+            let rotor = first(first_angle) * second(second_angle);
+            let bivector = first_plane * first_angle + second_plane * second_angle;
+            assert_rotors_equal(Rotor::exp(bivector), rotor);
+            assert_bivectors_equal(rotor.log(), bivector);
+        }
+    }
+
+    // rotations that dont commute still go back and forth
+    for rotor in [
+        Rotor::rotation_xy(0.3) * Rotor::rotation_yz(0.8),
+        Rotor::rotation_xw(1.3) * Rotor::rotation_zw(-0.4) * Rotor::rotation_xz(2.1),
+        Rotor::rotation_yw(0.9) * Rotor::rotation_xy(0.9) * Rotor::rotation_zw(0.2),
+    ] {
+        assert_rotors_equal(Rotor::exp(rotor.log()), rotor);
+    }
+}
+
+#[test]
+fn rotation_between() {
+    let directions = [
+        cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+        cgmath::vec4(0.0, 0.0, 0.0, 2.0),
+        cgmath::vec4(0.3, -0.4, 0.8, 0.1),
+        cgmath::vec4(-1.0, 1.0, 1.0, -1.0),
+        cgmath::vec4(-0.3, 0.4, -0.8, -0.1),
+    ];
+    for from in directions {
+        for to in directions.into_iter().chain([from, -from]) {
+            let rotor = Rotor::rotation_between(from, to);
+            assert_vectors_equal(rotor.rotate(from.normalize()), to.normalize());
+            assert!((rotor.magnitude() - 1.0).abs() < TOLERANCE);
+
+            let transform = Transform::rotation_between(from, to);
+            assert_vectors_equal(transform.transform(from.normalize()), to.normalize());
+        }
+    }
+
+    // it should only rotate in the plane of the two directions
+    let rotor = Rotor::rotation_between(axis(1), axis(3));
+    assert_rotors_equal(rotor, Rotor::rotation_yw(std::f32::consts::FRAC_PI_2));
+    assert_vectors_equal(rotor.rotate(axis(0)), axis(0));
+    assert_vectors_equal(rotor.rotate(axis(2)), axis(2));
+}
+
+#[test]
+fn rotation_in_plane() {
+    for (index, (rotation, _)) in planes().into_iter().enumerate() {
+        let (a, b) = [(0, 1), (0, 2), (0, 3), (1, 2), (1, 3), (2, 3)][index];
+        // the second direction doesnt need to be at right angles to the first
+        let rotor = Rotor::rotation_in_plane(axis(a) * 2.0, axis(a) + axis(b) * 0.5, 0.9);
+        assert_rotors_equal(rotor, rotation(0.9));
+    }
+
+    let a = cgmath::vec4(0.3, -0.4, 0.8, 0.1);
+    let b = cgmath::vec4(0.5, 0.5, 0.0, -0.2);
+    let angle = a.angle(b).0;
+    assert_rotors_equal(
+        Rotor::rotation_in_plane(a, b, angle),
+        Rotor::rotation_between(a, b),
+    );
+    let point = cgmath::vec4(1.0, 2.0, 3.0, 4.0);
+    assert_vectors_equal(
+        Transform::rotation_in_plane(a, b, 0.6).transform(point),
+        Rotor::rotation_in_plane(a, b, 0.6).rotate(point),
+    );
+}
+
+/// Checks that the decomposition makes the rotor again from two rotations in planes at right angles
+fn assert_decomposes(rotor: Rotor) -> RotationKind {
+    let [first, second] = rotor.decompose();
+    assert!(first.angle >= second.angle);
+    assert!(first.angle <= std::f32::consts::PI + TOLERANCE);
+    assert_rotors_equal(first.rotor * second.rotor, second.rotor * first.rotor);
+    let product = first.rotor * second.rotor;
+    let product = if (product.s < 0.0) != (rotor.s < 0.0) {
+        -product
+    } else {
+        product
+    };
+    assert_rotors_equal(product, rotor);
+
+    for part in [first, second] {
+        if part.angle > 0.0 {
+            assert!((part.plane.magnitude() - 1.0).abs() < TOLERANCE);
+            assert!(part.plane.pfaffian().abs() < TOLERANCE);
+            assert_rotors_equal(Rotor::exp(part.plane * part.angle), part.rotor);
+        }
+    }
+    if second.angle > 0.0 {
+        // planes at right angles dont share any directions, so together they fill all of 4d
+        assert!((first.plane + second.plane).pfaffian().abs() > 1.0 - TOLERANCE);
+    }
+    rotor.kind()
+}
+
+#[test]
+fn decompose() {
+    assert_eq!(assert_decomposes(Rotor::IDENTITY), RotationKind::Simple);
+    assert_eq!(
+        Rotor::IDENTITY.decompose().map(|part| part.angle),
+        [0.0, 0.0]
+    );
+
+    let [first, second] = Rotor::rotation_xz(0.7).decompose();
+    assert!((first.angle - 0.7).abs() < TOLERANCE);
+    assert_bivectors_equal(first.plane, planes()[1].1);
+    assert_eq!(second.angle, 0.0);
+    assert_eq!(
+        assert_decomposes(Rotor::rotation_xz(0.7)),
+        RotationKind::Simple
+    );
+
+    let rotor = Rotor::rotation_xy(0.4) * Rotor::rotation_zw(1.0);
+    assert_eq!(assert_decomposes(rotor), RotationKind::General);
+    let [first, second] = rotor.decompose();
+    assert!((first.angle - 1.0).abs() < TOLERANCE);
+    assert_bivectors_equal(first.plane, planes()[5].1);
+    assert!((second.angle - 0.4).abs() < TOLERANCE);
+    assert_bivectors_equal(second.plane, planes()[0].1);
+
+    let rotor = Rotor::rotation_xw(0.8) * Rotor::rotation_yz(-0.8);
+    assert_eq!(assert_decomposes(rotor), RotationKind::Isoclinic);
+    assert!(
+        rotor
+            .decompose()
+            .iter()
+            .all(|part| (part.angle - 0.8).abs() < TOLERANCE)
+    );
+
+    // the angles dont change when the planes are turned
+    let turn = Rotor::rotation_xz(0.3) * Rotor::rotation_yw(1.2) * Rotor::rotation_xy(-0.5);
+    let rotor = turn * Rotor::rotation_xw(2.0) * Rotor::rotation_yz(0.6) * !turn;
+    assert_eq!(assert_decomposes(rotor), RotationKind::General);
+    let [first, second] = rotor.decompose();
+    assert!((first.angle - 2.0).abs() < TOLERANCE);
+    assert!((second.angle - 0.6).abs() < TOLERANCE);
+
+    // and more than a half turn goes the other way
+    let [first, _] = Rotor::rotation_yw(4.0).decompose();
+    assert!((first.angle - (std::f32::consts::TAU - 4.0)).abs() < TOLERANCE);
+    assert_bivectors_equal(first.plane, -planes()[4].1);
+
+    for rotor in [
+        Rotor::rotation_xy(0.3) * Rotor::rotation_yz(0.8),
+        Rotor::rotation_xw(1.3) * Rotor::rotation_zw(-0.4) * Rotor::rotation_xz(2.1),
+        Rotor::rotation_yw(0.9) * Rotor::rotation_xy(0.9) * Rotor::rotation_zw(0.2),
+        Rotor::rotation_xy(3.0) * Rotor::rotation_zw(3.0),
+    ] {
+        assert_decomposes(rotor);
+    }
+}
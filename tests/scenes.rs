@@ -0,0 +1,133 @@
+use ray_tracer::{
+    objects::Object,
+    scene::{Scene, SceneError},
+};
+
+/// Saves the scene to a file and loads it again
+fn round_trip(scene: &Scene, name: &str) -> Scene {
+    let path = std::env::temp_dir().join(format!("ray_tracer_{name}"));
+    scene.save(&path).expect("the scene should save");
+    let loaded = Scene::load(&path).expect("the saved scene should load");
+    _ = std::fs::remove_file(&path);
+    loaded
+}
+
+#[test]
+fn save_and_load_default_scene() {
+    let scene = Scene::default();
+    assert_eq!(round_trip(&scene, "default.ron"), scene);
+}
+
+#[test]
+fn save_and_load_scene_files() {
+    for entry in std::fs::read_dir("scenes").expect("the scenes folder should be readable") {
+        let path = entry.expect("the scenes folder should be readable").path();
+        let scene = Scene::load(&path).expect("the scene should load");
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(
+            round_trip(&scene, &name) == scene,
+            "{name} changed when it was saved"
+        );
+    }
+}
+
+/// Saves the scene to ron and returns the error from loading it again
+fn load_error(scene: &Scene) -> SceneError {
+    let source = scene.to_ron().expect("the scene should save");
+    Scene::from_ron(&source).expect_err("the scene shouldnt load")
+}
+
+#[test]
+fn out_of_range_material() {
+    let mut scene = Scene::default();
+    let Object::HyperSphere(hyper_sphere) = &mut scene.objects[2] else {
+        panic!("the third object of the default scene should be a hyper sphere");
+    };
+    hyper_sphere.material = 5;
+
+    let error = load_error(&scene);
+    assert!(
+        matches!(
+            error,
+            SceneError::MissingMaterial {
+                object: 2,
+                material: 5,
+                material_count: 5,
+            }
+        ),
+        "{error}"
+    );
+    assert!(error.to_string().contains("material 5"), "{error}");
+}
+
+#[test]
+fn out_of_range_csg_material() {
+    let mut scene = Scene::load("scenes/csg.ron").expect("the scene should load");
+    let Object::Csg(csg) = &mut scene.objects[0] else {
+        panic!("the first object of the csg scene should be a csg object");
+    };
+    let Object::HyperSphere(hyper_sphere) = csg.right.as_mut() else {
+        panic!("the ground should have a hyper sphere for a hill");
+    };
+    hyper_sphere.material = 7;
+
+    let error = load_error(&scene);
+    assert!(
+        matches!(
+            error,
+            SceneError::MissingMaterial {
+                object: 0,
+                material: 7,
+                ..
+            }
+        ),
+        "{error}"
+    );
+}
+
+#[test]
+fn out_of_range_cell_material() {
+    let mut scene = Scene::load("scenes/polychora.ron").expect("the scene should load");
+    let (index, tetrahedron_mesh) = scene
+        .objects
+        .iter_mut()
+        .enumerate()
+        .find_map(|(index, object)| match object {
+            Object::TetrahedronMesh(tetrahedron_mesh) => Some((index, tetrahedron_mesh)),
+            _ => None,
+        })
+        .expect("the polychora scene should have a tetrahedron mesh");
+    tetrahedron_mesh.cell_materials[1] = 100;
+
+    let error = load_error(&scene);
+    assert!(
+        matches!(
+            error,
+            SceneError::MissingMaterial { object, material: 100, .. } if object == index
+        ),
+        "{error}"
+    );
+}
+
+#[test]
+fn malformed_scene_reports_position() {
+    let source = "(
+    materials: [],
+    objects: [
+        HyperSphere(radius: ),
+    ],
+)";
+    let error = Scene::from_ron(source).expect_err("the scene shouldnt load");
+    assert!(
+        matches!(
+            error,
+            SceneError::Parse {
+                line: 4,
+                column: 29,
+                ..
+            }
+        ),
+        "{error:?}"
+    );
+    assert!(error.to_string().starts_with("4:29: "), "{error}");
+}
@@ -0,0 +1,304 @@
+use cgmath::InnerSpace;
+use ray_tracer::{
+    math::Rotor,
+    mesh::{Mesh, MeshError, Tetrahedron, cross},
+    objects::{Csg, CsgOperation, HyperSphere, TetrahedronMesh},
+    ray::{Ray, RayIntersect},
+    scene::Scene,
+};
+use std::sync::Arc;
+
+const DISTANCE_TOLERANCE: f32 = 0.0001;
+
+/// A regular 5-cell around `center`, with its cells turned to face outwards
+fn five_cell_cells(
+    vertices: &mut Vec<cgmath::Vector4<f32>>,
+    cells: &mut Vec<[u32; 4]>,
+    center: cgmath::Vector4<f32>,
+    scale: f32,
+) {
+    let w = 1.0 / 5.0f32.sqrt();
+    let first = vertices.len() as u32;
+    vertices.extend(
+        [
+            cgmath::vec4(1.0, 1.0, 1.0, -w),
+            cgmath::vec4(1.0, -1.0, -1.0, -w),
+            cgmath::vec4(-1.0, 1.0, -1.0, -w),
+            cgmath::vec4(-1.0, -1.0, 1.0, -w),
+            cgmath::vec4(0.0, 0.0, 0.0, 4.0 * w),
+        ]
+        .map(|vertex| center + vertex * scale),
+    );
+    for missing in 0..5 {
+        let mut cell: [u32; 4] = (0..5)
+            .filter(|&vertex| vertex != missing)
+            .map(|vertex| first + vertex)
+            .collect::<Vec<_>>()
+            .try_into()
+            .expect("each cell has all but one of the vertices");
+        let tetrahedron = Tetrahedron {
+            vertices: cell.map(|vertex| vertices[vertex as usize]),
+        };
+        let cell_center = tetrahedron.vertices.iter().sum::<cgmath::Vector4<f32>>() / 4.0;
+        if tetrahedron.unnormalized_normal().dot(cell_center - center) < 0.0 {
+            cell.swap(1, 2);
+        }
+        cells.push(cell);
+    }
+}
+
+fn five_cell() -> TetrahedronMesh {
+    let mut vertices = vec![];
+    let mut cells = vec![];
+    five_cell_cells(
+        &mut vertices,
+        &mut cells,
+        cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        1.0,
+    );
+    TetrahedronMesh {
+        position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        rotation: Rotor::IDENTITY,
+        mesh: Arc::new(Mesh::new(vertices, cells).expect("the 5-cell should be a valid mesh")),
+        material: 0,
+        cell_materials: vec![],
+    }
+}
+
+/// A simple random number generator, so the tests dont need another dependency
+struct Random(u32);
+
+impl Random {
+    fn next(&mut self) -> f32 {
+        self.0 = self.0.wrapping_mul(747796405).wrapping_add(2891336453);
+        let word = ((self.0 >> ((self.0 >> 28) + 4)) ^ self.0).wrapping_mul(277803737);
+        ((word >> 22) ^ word) as f32 / u32::MAX as f32
+    }
+
+    fn vector(&mut self, scale: f32) -> cgmath::Vector4<f32> {
+        cgmath::vec4(
+            self.next() - 0.5,
+            self.next() - 0.5,
+            self.next() - 0.5,
+            self.next() - 0.5,
+        ) * (2.0 * scale)
+    }
+}
+
+#[test]
+fn cross_is_perpendicular() {
+    assert_eq!(
+        cross(
+            cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+            cgmath::vec4(0.0, 1.0, 0.0, 0.0),
+            cgmath::vec4(0.0, 0.0, 1.0, 0.0),
+        ),
+        cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+    );
+
+    let mut random = Random(1);
+    for _ in 0..100 {
+        let [a, b, c] = [(); 3].map(|_| random.vector(1.0));
+        let normal = cross(a, b, c);
+        for vector in [a, b, c] {
+            assert!(normal.dot(vector).abs() < 0.0001);
+        }
+    }
+}
+
+#[test]
+fn tetrahedron_crossing() {
+    let tetrahedron = Tetrahedron {
+        vertices: [
+            cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+            cgmath::vec4(1.0, 0.0, 0.0, 1.0),
+            cgmath::vec4(0.0, 1.0, 0.0, 1.0),
+            cgmath::vec4(0.0, 0.0, 1.0, 1.0),
+        ],
+    };
+    let ray = |x, y, z| Ray {
+        origin: cgmath::vec4(x, y, z, -2.0),
+        direction: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+    };
+    let distance = tetrahedron
+        .crossing(ray(0.2, 0.2, 0.2))
+        .expect("the ray should go through the tetrahedron");
+    assert!((distance - 3.0).abs() < DISTANCE_TOLERANCE);
+    assert!(tetrahedron.crossing(ray(0.4, 0.4, 0.4)).is_none());
+    assert!(tetrahedron.crossing(ray(-0.1, 0.2, 0.2)).is_none());
+}
+
+#[test]
+fn hit_from_outside() {
+    let five_cell = five_cell();
+    let ray = Ray {
+        origin: cgmath::vec4(-5.0, 0.1, 0.0, 0.0),
+        direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+    };
+    let hit = five_cell
+        .intersect(ray)
+        .expect("the ray should hit the 5-cell");
+    assert!(hit.front_face);
+    assert!(hit.normal.dot(ray.direction) < 0.0);
+    assert!((hit.normal.magnitude() - 1.0).abs() < DISTANCE_TOLERANCE);
+}
+
+#[test]
+fn hit_from_inside() {
+    let five_cell = five_cell();
+    let ray = Ray {
+        origin: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+        direction: cgmath::vec4(0.0, 0.0, 0.0, -1.0),
+    };
+    let hit = five_cell
+        .intersect(ray)
+        .expect("the ray should hit the 5-cell");
+    assert!(!hit.front_face);
+    assert!(hit.normal.dot(ray.direction) < 0.0);
+    // the ray comes out through the middle of the cell opposite the top vertex
+    assert!((hit.distance - 1.0 / 5.0f32.sqrt()).abs() < DISTANCE_TOLERANCE);
+}
+
+#[test]
+fn rotated_hit() {
+    let mut five_cell = five_cell();
+    five_cell.position = cgmath::vec4(3.0, 1.0, 0.0, 0.0);
+    five_cell.rotation = Rotor::rotation_xw(0.7) * Rotor::rotation_yz(0.3);
+
+    let ray = Ray {
+        origin: cgmath::vec4(3.0, 1.0, 0.0, -5.0),
+        direction: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+    };
+    let hit = five_cell
+        .intersect(ray)
+        .expect("the ray should hit the 5-cell");
+    // the hit should be on one of the cells, after moving it into the space of the mesh
+    let local_position = (!five_cell.rotation).rotate(hit.position - five_cell.position);
+    let on_a_cell = (0..five_cell.mesh.cells().len()).any(|cell| {
+        let tetrahedron = five_cell.mesh.cell(cell);
+        (local_position - tetrahedron.vertices[0])
+            .dot(tetrahedron.normal())
+            .abs()
+            < DISTANCE_TOLERANCE
+    });
+    assert!(on_a_cell, "hit at {local_position:?} isnt on any cell");
+}
+
+#[test]
+fn spans() {
+    let five_cell = five_cell();
+    let spans = five_cell.spans(Ray {
+        origin: cgmath::vec4(-5.0, 0.1, 0.0, 0.0),
+        direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+    });
+    assert_eq!(spans.len(), 1);
+    assert!(spans[0].start.normal.x < 0.0);
+    assert!(spans[0].end.normal.x > 0.0);
+
+    let spans = five_cell.spans(Ray {
+        origin: cgmath::vec4(0.0, 0.05, 0.02, 0.01),
+        direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+    });
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0].start.distance, 0.0);
+}
+
+#[test]
+fn csg_difference() {
+    let csg = Csg {
+        operation: CsgOperation::Difference,
+        left: Box::new(five_cell().into()),
+        right: Box::new(
+            HyperSphere {
+                position: cgmath::vec4(0.0, 0.0, 0.0, 0.0),
+                radius: 0.3,
+                material: 1,
+            }
+            .into(),
+        ),
+    };
+    // going through the middle goes into the 5-cell, then through the hole, then out the other side
+    let spans = csg.spans(Ray {
+        origin: cgmath::vec4(-5.0, 0.05, 0.0, 0.0),
+        direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+    });
+    let half_hole = (0.3f32 * 0.3 - 0.05 * 0.05).sqrt();
+    assert_eq!(spans.len(), 2);
+    assert!((spans[0].end.distance - (5.0 - half_hole)).abs() < DISTANCE_TOLERANCE);
+    assert!((spans[1].start.distance - (5.0 + half_hole)).abs() < DISTANCE_TOLERANCE);
+    assert_eq!(spans[0].end.material, 1);
+}
+
+#[test]
+fn bvh_matches_every_cell() {
+    // a grid of 5-cells, for more than 10,000 cells in total
+    let mut vertices = vec![];
+    let mut cells = vec![];
+    for i in 0..2048 {
+        let center = cgmath::vec4(
+            (i % 8) as f32,
+            (i / 8 % 8) as f32,
+            (i / 64 % 8) as f32,
+            (i / 512) as f32,
+        );
+        five_cell_cells(&mut vertices, &mut cells, center, 0.3);
+    }
+    let mesh = Mesh::new(vertices, cells).expect("the grid should be a valid mesh");
+    assert!(mesh.cells().len() > 10_000);
+
+    let mut random = Random(7);
+    let mut hits = 0;
+    for _ in 0..200 {
+        let target = random.vector(4.0) + cgmath::vec4(4.0, 4.0, 4.0, 2.0);
+        let origin = random.vector(20.0);
+        let ray = Ray {
+            origin,
+            direction: (target - origin).normalize(),
+        };
+
+        let every_cell = (0..mesh.cells().len())
+            .filter_map(|cell| {
+                let distance = mesh.cell(cell).crossing(ray)?;
+                (distance > 0.0).then_some(distance)
+            })
+            .min_by(f32::total_cmp);
+        let closest = mesh.closest_crossing(ray).map(|(distance, _)| distance);
+        assert_eq!(closest, every_cell);
+        hits += closest.is_some() as u32;
+    }
+    assert!(hits > 0, "none of the rays hit anything");
+}
+
+#[test]
+fn vertex_out_of_range() {
+    let error = Mesh::new(
+        vec![cgmath::vec4(0.0, 0.0, 0.0, 0.0); 4],
+        vec![[0, 1, 2, 3], [0, 1, 2, 4]],
+    )
+    .expect_err("the second cell uses a vertex that doesnt exist");
+    assert_eq!(
+        error,
+        MeshError::VertexOutOfRange {
+            cell: 1,
+            vertex: 4,
+            vertex_count: 4,
+        },
+    );
+    assert_eq!(Mesh::new(vec![], vec![]).unwrap_err(), MeshError::NoCells);
+}
+
+#[test]
+fn scene_file() {
+    let scene = Scene::load("scenes/tetrahedron_meshes.ron").expect("the scene should load");
+    // the mesh should be the same after saving it again
+    let reloaded = Scene::from_ron(&scene.to_ron().expect("the scene should save"))
+        .expect("the saved scene should load");
+    assert_eq!(scene.objects, reloaded.objects);
+
+    let error = Scene::from_ron(
+        "(materials: [], objects: [TetrahedronMesh(position: (x: 0.0, y: 0.0, z: 0.0, w: 0.0), \
+         mesh: (vertices: [], cells: [(0, 1, 2, 3)]), material: 0)])",
+    )
+    .expect_err("the cell uses vertices that dont exist");
+    assert!(error.to_string().contains("cell 0 uses vertex 0"));
+}
@@ -0,0 +1,177 @@
+use cgmath::InnerSpace;
+use ray_tracer::{
+    mesh::Mesh,
+    ray::Ray,
+    triangle_mesh::{TriangleMesh, TriangleMeshError},
+};
+
+const DISTANCE_TOLERANCE: f32 = 0.0001;
+
+/// A unit cube from 0 to 1 with quad faces, written the way modelling tools write them
+const CUBE: &str = "# a cube
+o Cube
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+vn 0 0 -1
+f 1//1 4//1 3//1 2//1
+f 5/1 6/2 7/3 8/4
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f -8 -4 -1 -5
+";
+
+/// The 4d volume of a closed mesh, from the cones going from the origin to each cell
+fn volume(mesh: &Mesh) -> f32 {
+    (0..mesh.cells().len())
+        .map(|cell| {
+            let tetrahedron = mesh.cell(cell);
+            tetrahedron
+                .unnormalized_normal()
+                .dot(tetrahedron.vertices[0])
+                / 24.0
+        })
+        .sum()
+}
+
+fn cube() -> TriangleMesh {
+    TriangleMesh::from_obj(CUBE).expect("the cube should load")
+}
+
+#[test]
+fn load_obj() {
+    let cube = cube();
+    assert_eq!(cube.vertices().len(), 8);
+    assert_eq!(cube.triangles().len(), 12);
+    // the faces all go anticlockwise from the outside, so the last face uses negative indices for the x = 0 side
+    assert_eq!(cube.triangles()[10], [0, 4, 7]);
+}
+
+#[test]
+fn load_obj_errors() {
+    let error = TriangleMesh::from_obj("v 0 0 zero\n").unwrap_err();
+    assert_eq!(error.to_string(), "1: zero isnt a valid number");
+
+    let error = TriangleMesh::from_obj("v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap_err();
+    assert_eq!(error.to_string(), "3: there is no vertex 3");
+
+    let error = TriangleMesh::from_obj("v 0 0 0\n").unwrap_err();
+    assert!(matches!(error, TriangleMeshError::NoTriangles));
+}
+
+#[test]
+fn inside_out_triangles_are_flipped() {
+    let inside_out = CUBE
+        .lines()
+        .map(|line| match line.strip_prefix("f ") {
+            Some(face) => format!("f {}", face.split(' ').rev().collect::<Vec<_>>().join(" ")),
+            None => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mesh = TriangleMesh::from_obj(&inside_out).expect("the cube should load");
+    assert!((volume(&mesh.extrude(0.5).unwrap()) - 1.0).abs() < DISTANCE_TOLERANCE);
+}
+
+#[test]
+fn extrude() {
+    let prism = cube()
+        .fitted(1.0)
+        .extrude(1.0)
+        .expect("a cube can be extruded");
+    assert!((volume(&prism) - 2.0).abs() < DISTANCE_TOLERANCE);
+    // every cell of a convex mesh faces away from its middle
+    for cell in 0..prism.cells().len() {
+        let tetrahedron = prism.cell(cell);
+        let center = tetrahedron.vertices.iter().sum::<cgmath::Vector4<f32>>() / 4.0;
+        assert!(prism.normals()[cell].dot(center) > 0.0);
+    }
+
+    // going out of the end of the prism
+    let (distance, cell) = prism
+        .closest_crossing(Ray {
+            origin: cgmath::vec4(0.1, 0.2, -0.15, 0.0),
+            direction: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        })
+        .expect("the ray should leave through the end of the prism");
+    assert!((distance - 1.0).abs() < DISTANCE_TOLERANCE);
+    assert!((prism.normals()[cell].w - 1.0).abs() < DISTANCE_TOLERANCE);
+
+    // and through the side
+    let (distance, _) = prism
+        .closest_crossing(Ray {
+            origin: cgmath::vec4(-3.0, 0.2, -0.15, 0.7),
+            direction: cgmath::vec4(1.0, 0.0, 0.0, 0.0),
+        })
+        .expect("the ray should hit the side of the prism");
+    assert!((distance - 2.5).abs() < DISTANCE_TOLERANCE);
+}
+
+#[test]
+fn extrude_needs_a_star_shaped_mesh() {
+    // two cubes that dont touch
+    let mut source = CUBE.to_string();
+    for line in CUBE.lines() {
+        if let Some(vertex) = line.strip_prefix("v ") {
+            let [x, y, z] = vertex
+                .split(' ')
+                .map(|word| word.parse::<f32>().unwrap())
+                .collect::<Vec<_>>()
+                .try_into()
+                .unwrap();
+            source += &format!("v {} {y} {z}\n", x + 3.0);
+        }
+    }
+    for line in CUBE.lines().filter(|line| line.starts_with("f ")) {
+        source += &format!("{line}\n");
+    }
+    let error = TriangleMesh::from_obj(&source)
+        .expect("the cubes should load")
+        .extrude(0.5)
+        .unwrap_err();
+    assert!(matches!(error, TriangleMeshError::NotStarShaped));
+}
+
+#[test]
+fn spin() {
+    let segments = 32;
+    // the area of a ring between two regular polygons, for each point of the cube in the yz plane
+    let polygon_area = |radius: f32| {
+        segments as f32 / 2.0 * (std::f32::consts::TAU / segments as f32).sin() * radius * radius
+    };
+
+    let ring = cube()
+        .translated(cgmath::vec3(1.0, 0.0, 0.0))
+        .spin(segments)
+        .expect("the cube is all on one side of the plane");
+    let expected = polygon_area(2.0) - polygon_area(1.0);
+    assert!((volume(&ring) - expected).abs() < 0.001);
+
+    // the vertices on the plane stay where they are, so the cube makes a solid spherinder like shape without a hole
+    let solid = cube().spin(segments).expect("the cube touches the plane");
+    assert_eq!(solid.vertices().len(), 4 + 4 * segments as usize);
+    assert!((volume(&solid) - polygon_area(1.0)).abs() < 0.001);
+    let (distance, _) = solid
+        .closest_crossing(Ray {
+            origin: cgmath::vec4(0.0, 0.3, 0.6, 0.0),
+            direction: cgmath::vec4(0.0, 0.0, 0.0, 1.0),
+        })
+        .expect("the ray should go out of the side of the spun cube");
+    // a quarter turn is a whole number of segments, so there are vertices along w
+    assert!((distance - 1.0).abs() < DISTANCE_TOLERANCE);
+
+    let error = cube()
+        .translated(cgmath::vec3(-0.5, 0.0, 0.0))
+        .spin(segments)
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        TriangleMeshError::BehindSpinPlane { vertex: 0 }
+    ));
+}